@@ -0,0 +1,537 @@
+use crate::board::{self, GameState, Player};
+use crate::rl::{self, ACTION_COUNT, OBSERVATION_SIZE};
+use crate::rules::{self, Move, SearchState};
+use crate::search::{Rng, SearchResult};
+
+// AlphaZero 方式の探索
+//
+// 葉の局面をランダムプレイアウトではなく方策・価値のモデル（`PolicyValue`）で評価する MCTS。
+// 子の選び方は PUCT（Q + c_puct * 事前確率 * sqrt(親の訪問回数) / (1 + 子の訪問回数)）で、
+// 事前確率はモデルが返す行動ごとのロジットを合法手の間で softmax したもの。
+// `batch_size` 個の葉を virtual loss で散らしながら集め、まとめてモデルに渡す。
+//
+// 行動の番号と観測は `rl` モジュールのものを使う。
+
+/// モデルの出力
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    /// 行動ごとのロジット（長さ `ACTION_COUNT`）
+    pub policy: Vec<f32>,
+    /// 手番側から見た価値（-1 負け 〜 1 勝ち）
+    pub value: f32,
+}
+
+/// 局面から方策と価値を求めるモデル
+pub trait PolicyValue {
+    fn predict(&self, state: &GameState, player: Player) -> Prediction;
+
+    /// 複数の局面をまとめて評価する（既定では1局面ずつ `predict` を呼ぶ）
+    fn predict_batch(&self, positions: &[(GameState, Player)]) -> Vec<Prediction> {
+        positions
+            .iter()
+            .map(|(state, player)| self.predict(state, *player))
+            .collect()
+    }
+}
+
+impl<M: PolicyValue + ?Sized> PolicyValue for Box<M> {
+    fn predict(&self, state: &GameState, player: Player) -> Prediction {
+        (**self).predict(state, player)
+    }
+
+    fn predict_batch(&self, positions: &[(GameState, Player)]) -> Vec<Prediction> {
+        (**self).predict_batch(positions)
+    }
+}
+
+/// すべての手を同じ確率とし、価値を 0 とするモデル（探索木だけで指す）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uniform;
+
+impl PolicyValue for Uniform {
+    fn predict(&self, _state: &GameState, _player: Player) -> Prediction {
+        Prediction {
+            policy: vec![0.0; ACTION_COUNT],
+            value: 0.0,
+        }
+    }
+}
+
+// ---- 探索 ----
+
+/// 探索の設定
+#[derive(Debug, Clone, Copy)]
+pub struct PuctOptions {
+    /// 葉を評価する回数
+    pub simulations: u32,
+    /// 探索の広さ
+    pub c_puct: f32,
+    /// まとめてモデルに渡す葉の数
+    pub batch_size: usize,
+}
+
+impl Default for PuctOptions {
+    fn default() -> Self {
+        Self {
+            simulations: 400,
+            c_puct: 1.5,
+            batch_size: 8,
+        }
+    }
+}
+
+struct Node {
+    mv: Option<Move>,
+    prior: f32,
+    visits: u32,
+    /// このノードへ指した側から見た価値の合計
+    value_sum: f32,
+    children: Vec<Node>,
+    /// 子を作ったか（終局なら子がないまま true になる）
+    expanded: bool,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, prior: f32) -> Self {
+        Self {
+            mv,
+            prior,
+            visits: 0,
+            value_sum: 0.0,
+            children: Vec::new(),
+            expanded: false,
+        }
+    }
+
+    fn select_child(&self, c_puct: f32) -> usize {
+        let sqrt_visits = (self.visits.max(1) as f32).sqrt();
+        let score = |child: &Node| {
+            let q = if child.visits > 0 {
+                child.value_sum / child.visits as f32
+            } else {
+                0.0
+            };
+            q + c_puct * child.prior * sqrt_visits / (1.0 + child.visits as f32)
+        };
+        (0..self.children.len())
+            .max_by(|&a, &b| score(&self.children[a]).total_cmp(&score(&self.children[b])))
+            .expect("子がある")
+    }
+
+    fn descendant_mut(&mut self, path: &[usize]) -> &mut Node {
+        path.iter().fold(self, |node, &i| &mut node.children[i])
+    }
+}
+
+/// 終局なら空を返す（玉を取られた局面には指し手がないものとする）
+fn children_moves(state: &GameState, player: Player) -> Vec<Move> {
+    if !rules::has_king(state, Player::Sente) || !rules::has_king(state, Player::Gote) {
+        return Vec::new();
+    }
+    rules::generate_legal_moves(state, player)
+}
+
+/// 葉の価値 `value`（葉の手番から見た値）を根まで伝える
+///
+/// `virtual_loss` なら、選ぶときに加えた virtual loss を取り消しながら伝える（訪問回数は加え済み）。
+fn backup(root: &mut Node, path: &[usize], value: f32, virtual_loss: bool) {
+    let visit = u32::from(!virtual_loss);
+    let restore = if virtual_loss { 1.0 } else { 0.0 };
+    root.visits += visit;
+
+    let mut node = root;
+    for (depth, &i) in path.iter().enumerate() {
+        node = &mut node.children[i];
+        // 葉へ指した側から見ると価値は -value で、1手さかのぼるごとに符号が変わる
+        let sign = if (path.len() - depth) % 2 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        node.visits += visit;
+        node.value_sum += sign * value + restore;
+    }
+}
+
+/// 選んだ経路に virtual loss（指した側の負けとして1回分）を加える
+fn add_virtual_loss(root: &mut Node, path: &[usize]) {
+    root.visits += 1;
+    let mut node = root;
+    for &i in path {
+        node = &mut node.children[i];
+        node.visits += 1;
+        node.value_sum -= 1.0;
+    }
+}
+
+/// 葉を展開し、合法手の間で softmax した事前確率を子に付ける
+fn expand(node: &mut Node, moves: &[Move], policy: &[f32]) {
+    let logits: Vec<f32> = moves
+        .iter()
+        .map(|&mv| policy[rl::encode_move(mv)])
+        .collect();
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|&l| (l - max).exp()).collect();
+    let total: f32 = exps.iter().sum();
+
+    node.children = moves
+        .iter()
+        .zip(exps)
+        .map(|(&mv, e)| Node::new(Some(mv), e / total))
+        .collect();
+    node.expanded = true;
+}
+
+/// 価値（-1..1）を評価値に換算する（MCTS と同じ sigmoid のスケール）
+fn value_to_score(value: f32) -> i32 {
+    let p = f64::from((value + 1.0) / 2.0).clamp(1e-6, 1.0 - 1e-6);
+    (400.0 * (p / (1.0 - p)).ln()).round() as i32
+}
+
+/// モデルで葉を評価する MCTS
+///
+/// 返り値の訪問回数は根の各手のもので、評価値は最も訪問した手の価値を先手から見た値に換算したもの。
+pub fn search<M: PolicyValue + ?Sized>(
+    state: &GameState,
+    player: Player,
+    options: &PuctOptions,
+    model: &M,
+) -> SearchResult {
+    if children_moves(state, player).is_empty() {
+        return SearchResult::default();
+    }
+
+    let mut root = Node::new(None, 1.0);
+    let mut pos = SearchState::new(*state, player);
+    let batch_size = options.batch_size.max(1);
+
+    // 根の展開も1回の評価として数える
+    while root.visits < options.simulations.max(2) {
+        let mut pending: Vec<(Vec<usize>, Vec<Move>)> = Vec::new();
+        let mut positions = Vec::new();
+
+        while pending.len() < batch_size && root.visits < options.simulations.max(2) {
+            // 1. 選択
+            let mut path = Vec::new();
+            let mut undos = Vec::new();
+            let mut node = &root;
+            while node.expanded && !node.children.is_empty() {
+                let i = node.select_child(options.c_puct);
+                node = &node.children[i];
+                path.push(i);
+                undos.push(pos.do_move(node.mv.expect("子ノードは手を持つ")));
+            }
+            let leaf_state = pos.state;
+            let leaf_player = pos.player;
+            while let Some(undo) = undos.pop() {
+                pos.undo_move(undo);
+            }
+
+            // 2. 終局ならその場で伝える
+            let moves = children_moves(&leaf_state, leaf_player);
+            if moves.is_empty() {
+                root.descendant_mut(&path).expanded = true;
+                backup(&mut root, &path, -1.0, false);
+                continue;
+            }
+            // 同じ葉を2度選んだらこのバッチは打ち切る
+            if pending.iter().any(|(p, _)| *p == path) {
+                break;
+            }
+
+            add_virtual_loss(&mut root, &path);
+            pending.push((path, moves));
+            positions.push((leaf_state, leaf_player));
+        }
+
+        // 3. まとめて評価し、展開して伝える
+        let predictions = model.predict_batch(&positions);
+        for ((path, moves), prediction) in pending.iter().zip(predictions) {
+            expand(root.descendant_mut(path), moves, &prediction.policy);
+            backup(&mut root, path, prediction.value.clamp(-1.0, 1.0), true);
+        }
+    }
+
+    let best = root.children.iter().max_by_key(|c| c.visits);
+    let score = best.map_or(0, |c| value_to_score(c.value_sum / c.visits.max(1) as f32));
+    SearchResult {
+        best_move: best.and_then(|c| c.mv),
+        score: Some(match player {
+            Player::Sente => score,
+            Player::Gote => -score,
+        }),
+        visits: root
+            .children
+            .iter()
+            .filter_map(|c| Some((c.mv?, c.visits)))
+            .collect(),
+    }
+}
+
+// ---- 学習 ----
+
+/// 学習用の1局面
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingExample {
+    /// `rl::observe` の観測
+    pub observation: Vec<f32>,
+    /// 目標の方策（行動の番号と確率）
+    pub policy: Vec<(usize, f32)>,
+    /// 手番側から見た対局結果（勝ち 1、引き分け 0、負け -1）
+    pub value: f32,
+}
+
+/// 探索の訪問回数を目標の方策に変換する
+pub fn visit_policy(visits: &[(Move, u32)]) -> Vec<(usize, f32)> {
+    let total: u32 = visits.iter().map(|&(_, n)| n).sum();
+    visits
+        .iter()
+        .map(|&(mv, n)| (rl::encode_move(mv), n as f32 / total.max(1) as f32))
+        .collect()
+}
+
+/// モデル同士で1局指し、各局面の学習データを返す
+///
+/// 最初の `sampling_plies` 手は訪問回数に比例した確率で手を選び、以降は最も訪問した手を指す。
+/// 玉を取った側の勝ち、指し手がなくなった側の負けで、`max_plies` 手に達すれば引き分け。
+pub fn selfplay_game<M: PolicyValue + ?Sized>(
+    model: &M,
+    options: &PuctOptions,
+    rng: &mut Rng,
+    sampling_plies: usize,
+    max_plies: usize,
+) -> Vec<TrainingExample> {
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    // 結果は対局が終わってから書き込む
+    let mut examples = Vec::new();
+    // 先手から見た結果
+    let mut sente_result = 0.0;
+
+    while examples.len() < max_plies {
+        let result = search(&state, player, options, model);
+        if result.visits.is_empty() {
+            sente_result = if player == Player::Sente { -1.0 } else { 1.0 };
+            break;
+        }
+
+        let mv = if examples.len() < sampling_plies {
+            let total: u32 = result.visits.iter().map(|&(_, n)| n).sum();
+            let mut r = rng.range(total.max(1) as usize) as u32;
+            let mut chosen = result.visits[0].0;
+            for &(mv, n) in &result.visits {
+                if r < n {
+                    chosen = mv;
+                    break;
+                }
+                r -= n;
+            }
+            chosen
+        } else {
+            result.best_move.expect("合法手がある")
+        };
+
+        examples.push(TrainingExample {
+            observation: rl::observe(&state, player),
+            policy: visit_policy(&result.visits),
+            value: 0.0,
+        });
+        state = rules::make_move(&state, mv, player);
+        if !rules::has_king(&state, rules::opponent_of(player)) {
+            sente_result = if player == Player::Sente { 1.0 } else { -1.0 };
+            break;
+        }
+        player = rules::opponent_of(player);
+    }
+
+    // 先手番から交互に指しているので、偶数番目が先手番の局面
+    for (i, example) in examples.iter_mut().enumerate() {
+        example.value = if i % 2 == 0 {
+            sente_result
+        } else {
+            -sente_result
+        };
+    }
+    examples
+}
+
+// ---- 参照実装のモデル ----
+
+/// 隠れ層1層の小さな多層パーセプトロン
+///
+/// 観測 → 隠れ層（ReLU）→ 方策（ロジット）と価値（tanh）。
+#[derive(Debug, Clone)]
+pub struct Mlp {
+    hidden: usize,
+    /// [hidden][OBSERVATION_SIZE]
+    w1: Vec<f32>,
+    b1: Vec<f32>,
+    /// [ACTION_COUNT][hidden]
+    wp: Vec<f32>,
+    bp: Vec<f32>,
+    wv: Vec<f32>,
+    bv: f32,
+}
+
+/// 順伝播の途中の値
+struct Forward {
+    hidden: Vec<f32>,
+    logits: Vec<f32>,
+    value: f32,
+}
+
+impl Mlp {
+    /// 隠れ層の大きさを指定し、乱数で初期化する
+    pub fn new(hidden: usize, seed: u64) -> Self {
+        let mut rng = Rng::with_seed(seed);
+        let mut uniform = |limit: f32| (rng.next_u64() % 2001) as f32 / 1000.0 * limit - limit;
+        let l1 = (1.0 / OBSERVATION_SIZE as f32).sqrt();
+        let l2 = (1.0 / hidden as f32).sqrt();
+
+        Self {
+            hidden,
+            w1: (0..hidden * OBSERVATION_SIZE)
+                .map(|_| uniform(l1))
+                .collect(),
+            b1: vec![0.0; hidden],
+            wp: (0..ACTION_COUNT * hidden).map(|_| uniform(l2)).collect(),
+            bp: vec![0.0; ACTION_COUNT],
+            wv: (0..hidden).map(|_| uniform(l2)).collect(),
+            bv: 0.0,
+        }
+    }
+
+    fn forward(&self, observation: &[f32]) -> Forward {
+        let h = self.hidden;
+        let hidden: Vec<f32> = (0..h)
+            .map(|j| {
+                let row = &self.w1[j * OBSERVATION_SIZE..(j + 1) * OBSERVATION_SIZE];
+                let sum: f32 = row.iter().zip(observation).map(|(w, x)| w * x).sum();
+                (self.b1[j] + sum).max(0.0)
+            })
+            .collect();
+        let logits = (0..ACTION_COUNT)
+            .map(|a| {
+                let row = &self.wp[a * h..(a + 1) * h];
+                self.bp[a] + row.iter().zip(&hidden).map(|(w, x)| w * x).sum::<f32>()
+            })
+            .collect();
+        let value = (self.bv + self.wv.iter().zip(&hidden).map(|(w, x)| w * x).sum::<f32>()).tanh();
+        Forward {
+            hidden,
+            logits,
+            value,
+        }
+    }
+
+    /// 1例分の損失（方策の交差エントロピー + 価値の二乗誤差）
+    fn example_loss(forward: &Forward, example: &TrainingExample) -> (f32, Vec<f32>) {
+        let probs = softmax(&forward.logits);
+        let policy_loss: f32 = example
+            .policy
+            .iter()
+            .map(|&(a, p)| -p * probs[a].max(1e-12).ln())
+            .sum();
+        let value_loss = (forward.value - example.value).powi(2);
+        (policy_loss + value_loss, probs)
+    }
+
+    /// 学習データ全体での平均損失
+    pub fn loss(&self, examples: &[TrainingExample]) -> f32 {
+        if examples.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = examples
+            .iter()
+            .map(|e| Self::example_loss(&self.forward(&e.observation), e).0)
+            .sum();
+        total / examples.len() as f32
+    }
+
+    /// ミニバッチ1つ分の勾配で更新し（確率的勾配降下法）、更新前の平均損失を返す
+    pub fn train_batch(&mut self, batch: &[TrainingExample], learning_rate: f32) -> f32 {
+        if batch.is_empty() {
+            return 0.0;
+        }
+        let h = self.hidden;
+        let mut g_w1 = vec![0.0; self.w1.len()];
+        let mut g_b1 = vec![0.0; h];
+        let mut g_wp = vec![0.0; self.wp.len()];
+        let mut g_bp = vec![0.0; ACTION_COUNT];
+        let mut g_wv = vec![0.0; h];
+        let mut g_bv = 0.0;
+        let mut total = 0.0;
+
+        for example in batch {
+            let fwd = self.forward(&example.observation);
+            let (loss, mut d_logits) = Self::example_loss(&fwd, example);
+            total += loss;
+
+            // softmax + 交差エントロピーの勾配は 予測 - 目標
+            for &(a, p) in &example.policy {
+                d_logits[a] -= p;
+            }
+            let d_value = 2.0 * (fwd.value - example.value) * (1.0 - fwd.value * fwd.value);
+
+            let mut d_hidden = vec![0.0; h];
+            for (a, &d) in d_logits.iter().enumerate() {
+                g_bp[a] += d;
+                let row = a * h;
+                for j in 0..h {
+                    g_wp[row + j] += d * fwd.hidden[j];
+                    d_hidden[j] += d * self.wp[row + j];
+                }
+            }
+            g_bv += d_value;
+            for j in 0..h {
+                g_wv[j] += d_value * fwd.hidden[j];
+                d_hidden[j] += d_value * self.wv[j];
+            }
+
+            for j in 0..h {
+                if fwd.hidden[j] <= 0.0 {
+                    continue;
+                }
+                g_b1[j] += d_hidden[j];
+                let row = j * OBSERVATION_SIZE;
+                for (i, &x) in example.observation.iter().enumerate() {
+                    if x != 0.0 {
+                        g_w1[row + i] += d_hidden[j] * x;
+                    }
+                }
+            }
+        }
+
+        let step = learning_rate / batch.len() as f32;
+        let update = |params: &mut [f32], grads: &[f32]| {
+            for (p, g) in params.iter_mut().zip(grads) {
+                *p -= step * g;
+            }
+        };
+        update(&mut self.w1, &g_w1);
+        update(&mut self.b1, &g_b1);
+        update(&mut self.wp, &g_wp);
+        update(&mut self.bp, &g_bp);
+        update(&mut self.wv, &g_wv);
+        self.bv -= step * g_bv;
+
+        total / batch.len() as f32
+    }
+}
+
+impl PolicyValue for Mlp {
+    fn predict(&self, state: &GameState, player: Player) -> Prediction {
+        let fwd = self.forward(&rl::observe(state, player));
+        Prediction {
+            policy: fwd.logits,
+            value: fwd.value,
+        }
+    }
+}
+
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|&l| (l - max).exp()).collect();
+    let total: f32 = exps.iter().sum();
+    exps.into_iter().map(|e| e / total).collect()
+}
@@ -0,0 +1,478 @@
+use crate::board::{GameState, HAND_TYPES, Hand, Piece, PieceType, Player, player_index};
+use crate::rules::{Move, Position, opponent_of};
+
+// ビットボードによる局面表現
+// 25マスを u32 の下位25ビットで表す。ビット番号は y * 5 + x（5a が 0、1e が 24）。
+
+pub type Bitboard = u32;
+
+/// 盤上の全マス
+pub const FULL: Bitboard = (1 << 25) - 1;
+
+/// 成りを区別した駒の種類の数
+const KIND_COUNT: usize = 10;
+
+const KING: usize = 0;
+const GOLD: usize = 1;
+const SILVER: usize = 2;
+const BISHOP: usize = 3;
+const ROOK: usize = 4;
+const PAWN: usize = 5;
+const PRO_SILVER: usize = 6;
+const HORSE: usize = 7;
+const DRAGON: usize = 8;
+const TOKIN: usize = 9;
+
+fn kind_of(piece_type: PieceType, promoted: bool) -> usize {
+    match (piece_type, promoted) {
+        (PieceType::King, _) => KING,
+        (PieceType::Gold, _) => GOLD,
+        (PieceType::Silver, false) => SILVER,
+        (PieceType::Bishop, false) => BISHOP,
+        (PieceType::Rook, false) => ROOK,
+        (PieceType::Pawn, false) => PAWN,
+        (PieceType::Silver, true) => PRO_SILVER,
+        (PieceType::Bishop, true) => HORSE,
+        (PieceType::Rook, true) => DRAGON,
+        (PieceType::Pawn, true) => TOKIN,
+    }
+}
+
+fn piece_of(kind: usize) -> (PieceType, bool) {
+    match kind {
+        KING => (PieceType::King, false),
+        GOLD => (PieceType::Gold, false),
+        SILVER => (PieceType::Silver, false),
+        BISHOP => (PieceType::Bishop, false),
+        ROOK => (PieceType::Rook, false),
+        PAWN => (PieceType::Pawn, false),
+        PRO_SILVER => (PieceType::Silver, true),
+        HORSE => (PieceType::Bishop, true),
+        DRAGON => (PieceType::Rook, true),
+        TOKIN => (PieceType::Pawn, true),
+        _ => unreachable!(),
+    }
+}
+
+pub fn square(pos: Position) -> usize {
+    pos.y * 5 + pos.x
+}
+
+pub fn position(sq: usize) -> Position {
+    Position::new(sq % 5, sq / 5)
+}
+
+// ---- 利きテーブル ----
+
+/// 8方向（先手から見た前・後・左・右・左前・右前・左後・右後）
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+const ORTHOGONAL: [usize; 4] = [0, 1, 2, 3];
+const DIAGONAL: [usize; 4] = [4, 5, 6, 7];
+
+/// 先手の駒ごとの1マスの動き（DIRECTIONS の添字）
+const fn step_directions(kind: usize) -> &'static [usize] {
+    match kind {
+        KING => &[0, 1, 2, 3, 4, 5, 6, 7],
+        GOLD | PRO_SILVER | TOKIN => &[0, 1, 2, 3, 4, 5],
+        SILVER => &[0, 4, 5, 6, 7],
+        PAWN => &[0],
+        HORSE => &ORTHOGONAL,
+        DRAGON => &DIAGONAL,
+        _ => &[],
+    }
+}
+
+const fn offset_square(sq: usize, dx: i32, dy: i32) -> Option<usize> {
+    let x = (sq % 5) as i32 + dx;
+    let y = (sq / 5) as i32 + dy;
+    if x >= 0 && x < 5 && y >= 0 && y < 5 {
+        Some((y * 5 + x) as usize)
+    } else {
+        None
+    }
+}
+
+struct Tables {
+    /// [手番][駒種][マス] の1マスの利き
+    step: [[[Bitboard; 25]; KIND_COUNT]; 2],
+    /// [方向][マス] の盤端までの直線
+    rays: [[Bitboard; 25]; 8],
+}
+
+const fn make_tables() -> Tables {
+    let mut tables = Tables {
+        step: [[[0; 25]; KIND_COUNT]; 2],
+        rays: [[0; 25]; 8],
+    };
+
+    let mut sq = 0;
+    while sq < 25 {
+        let mut kind = 0;
+        while kind < KIND_COUNT {
+            let dirs = step_directions(kind);
+            let mut i = 0;
+            while i < dirs.len() {
+                let (dx, dy) = DIRECTIONS[dirs[i]];
+                // 後手は先手の動きを反転する
+                if let Some(to) = offset_square(sq, dx, dy) {
+                    tables.step[0][kind][sq] |= 1 << to;
+                }
+                if let Some(to) = offset_square(sq, -dx, -dy) {
+                    tables.step[1][kind][sq] |= 1 << to;
+                }
+                i += 1;
+            }
+            kind += 1;
+        }
+
+        let mut dir = 0;
+        while dir < 8 {
+            let (dx, dy) = DIRECTIONS[dir];
+            let mut curr = sq;
+            while let Some(next) = offset_square(curr, dx, dy) {
+                tables.rays[dir][sq] |= 1 << next;
+                curr = next;
+            }
+            dir += 1;
+        }
+        sq += 1;
+    }
+
+    tables
+}
+
+static TABLES: Tables = make_tables();
+
+/// 障害物 `occupied` で止まる飛び利き（止めた駒のマスを含む）
+fn sliding_attacks(sq: usize, occupied: Bitboard, dirs: &[usize]) -> Bitboard {
+    let mut attacks = 0;
+    for &dir in dirs {
+        let ray = TABLES.rays[dir][sq];
+        let blockers = ray & occupied;
+        if blockers == 0 {
+            attacks |= ray;
+            continue;
+        }
+        // マス番号が増える向きなら最下位、減る向きなら最上位の障害物が最初に当たる
+        let (dx, dy) = DIRECTIONS[dir];
+        let first = if dy * 5 + dx > 0 {
+            blockers.trailing_zeros()
+        } else {
+            31 - blockers.leading_zeros()
+        } as usize;
+        attacks |= ray & !TABLES.rays[dir][first];
+    }
+    attacks
+}
+
+/// 駒の利き
+pub fn attacks(
+    player: Player,
+    piece_type: PieceType,
+    promoted: bool,
+    sq: usize,
+    occupied: Bitboard,
+) -> Bitboard {
+    kind_attacks(
+        player_index(player),
+        kind_of(piece_type, promoted),
+        sq,
+        occupied,
+    )
+}
+
+fn kind_attacks(c: usize, kind: usize, sq: usize, occupied: Bitboard) -> Bitboard {
+    let step = TABLES.step[c][kind][sq];
+    match kind {
+        BISHOP | HORSE => step | sliding_attacks(sq, occupied, &DIAGONAL),
+        ROOK | DRAGON => step | sliding_attacks(sq, occupied, &ORTHOGONAL),
+        _ => step,
+    }
+}
+
+/// ビットを1つずつ取り出すイテレータ
+struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let sq = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(sq)
+    }
+}
+
+/// 段 `y` の全マス
+const fn rank_mask(y: usize) -> Bitboard {
+    0b11111 << (y * 5)
+}
+
+/// 筋 `x` の全マス
+const fn file_mask(x: usize) -> Bitboard {
+    0x0010_8421 << x
+}
+
+fn promotion_zone(c: usize) -> Bitboard {
+    if c == 0 { rank_mask(0) } else { rank_mask(4) }
+}
+
+// ---- 局面 ----
+
+/// ビットボードによる局面
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitPosition {
+    /// [手番][駒種] ごとの配置
+    pieces: [[Bitboard; KIND_COUNT]; 2],
+    /// 手番ごとの全駒
+    occupied: [Bitboard; 2],
+    hands: [Hand; 2],
+}
+
+impl BitPosition {
+    pub fn from_state(state: &GameState) -> Self {
+        let mut pos = Self {
+            pieces: [[0; KIND_COUNT]; 2],
+            occupied: [0; 2],
+            hands: [state.sente_hand, state.gote_hand],
+        };
+        for (y, row) in state.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Some(piece) = cell {
+                    let c = player_index(piece.owner);
+                    let bit = 1 << (y * 5 + x);
+                    pos.pieces[c][kind_of(piece.piece_type, piece.promoted)] |= bit;
+                    pos.occupied[c] |= bit;
+                }
+            }
+        }
+        pos
+    }
+
+    pub fn to_state(&self) -> GameState {
+        let mut state = GameState {
+            board: [[None; 5]; 5],
+            sente_hand: self.hands[0],
+            gote_hand: self.hands[1],
+        };
+        for (c, owner) in [(0, Player::Sente), (1, Player::Gote)] {
+            for kind in 0..KIND_COUNT {
+                let (piece_type, promoted) = piece_of(kind);
+                for sq in Squares(self.pieces[c][kind]) {
+                    state.board[sq / 5][sq % 5] = Some(Piece {
+                        piece_type,
+                        owner,
+                        promoted,
+                    });
+                }
+            }
+        }
+        state
+    }
+
+    /// 指定した手番・駒の配置
+    pub fn pieces(&self, player: Player, piece_type: PieceType, promoted: bool) -> Bitboard {
+        self.pieces[player_index(player)][kind_of(piece_type, promoted)]
+    }
+
+    /// 指定した手番の全駒
+    pub fn occupied_by(&self, player: Player) -> Bitboard {
+        self.occupied[player_index(player)]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.occupied[0] | self.occupied[1]
+    }
+
+    pub fn hand(&self, player: Player) -> &Hand {
+        &self.hands[player_index(player)]
+    }
+
+    fn kind_at(&self, c: usize, sq: usize) -> Option<usize> {
+        (0..KIND_COUNT).find(|&kind| self.pieces[c][kind] & (1 << sq) != 0)
+    }
+
+    pub fn king_square(&self, player: Player) -> Option<usize> {
+        let kings = self.pieces[player_index(player)][KING];
+        (kings != 0).then(|| kings.trailing_zeros() as usize)
+    }
+
+    /// `sq` に利いている `player` の駒
+    pub fn attackers_to(&self, sq: usize, player: Player) -> Bitboard {
+        let c = player_index(player);
+        let occupied = self.occupied();
+        let mut attackers = 0;
+        for kind in 0..KIND_COUNT {
+            let pieces = self.pieces[c][kind];
+            if pieces != 0 {
+                // 相手側から見た利きの逆引き
+                attackers |= kind_attacks(1 - c, kind, sq, occupied) & pieces;
+            }
+        }
+        attackers
+    }
+
+    pub fn is_in_check(&self, player: Player) -> bool {
+        match self.king_square(player) {
+            Some(sq) => self.attackers_to(sq, opponent_of(player)) != 0,
+            None => false,
+        }
+    }
+
+    /// 王手を回避できない状態か
+    pub fn is_checkmate(&self, player: Player) -> bool {
+        if !self.is_in_check(player) {
+            return false;
+        }
+        let mut moves = Vec::new();
+        self.generate_moves_fast_into(player, &mut moves);
+        moves
+            .iter()
+            .all(|&mv| self.make_move(mv, player).is_in_check(player))
+    }
+
+    /// 二歩チェック付きの手生成（打ち歩詰めチェックなし）
+    pub fn generate_moves_fast(&self, player: Player) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        self.generate_moves_fast_into(player, &mut moves);
+        moves
+    }
+
+    fn generate_moves_fast_into(&self, player: Player, moves: &mut Vec<Move>) {
+        let c = player_index(player);
+        let own = self.occupied[c];
+        let occupied = self.occupied();
+        let zone = promotion_zone(c);
+        let last_rank = zone;
+
+        for kind in 0..KIND_COUNT {
+            let promotable = matches!(kind, SILVER | BISHOP | ROOK | PAWN);
+            for from in Squares(self.pieces[c][kind]) {
+                let targets = kind_attacks(c, kind, from, occupied) & !own;
+                let from_in_zone = zone & (1 << from) != 0;
+                for to in Squares(targets) {
+                    let from_pos = position(from);
+                    let to_pos = position(to);
+                    if promotable && (from_in_zone || zone & (1 << to) != 0) {
+                        moves.push(Move::To(from_pos, to_pos, true));
+                        // 歩が最奥段に到達した場合は強制成り
+                        if !(kind == PAWN && last_rank & (1 << to) != 0) {
+                            moves.push(Move::To(from_pos, to_pos, false));
+                        }
+                    } else {
+                        moves.push(Move::To(from_pos, to_pos, false));
+                    }
+                }
+            }
+        }
+
+        let empty = !occupied & FULL;
+        let hand = &self.hands[c];
+        for piece_type in HAND_TYPES {
+            if hand.get(piece_type) == 0 {
+                continue;
+            }
+            let mut targets = empty;
+            if piece_type == PieceType::Pawn {
+                // 二歩・最奥段への歩打ちは禁止
+                targets &= !last_rank;
+                for pawn in Squares(self.pieces[c][PAWN]) {
+                    targets &= !file_mask(pawn % 5);
+                }
+            }
+            for to in Squares(targets) {
+                moves.push(Move::Drop(position(to), piece_type));
+            }
+        }
+    }
+
+    /// 打ち歩詰めを除いた手生成（`rules::generate_legal_moves` と同じ手の集合）
+    pub fn generate_legal_moves(&self, player: Player) -> Vec<Move> {
+        let mut moves = self.generate_moves_fast(player);
+        let opp = opponent_of(player);
+        let enemy_king = self.pieces[player_index(opp)][KING];
+        moves.retain(|&mv| match mv {
+            Move::Drop(to, PieceType::Pawn) => {
+                // 玉の正面への歩打ちだけが王手になりうる
+                let gives_check =
+                    TABLES.step[player_index(player)][PAWN][square(to)] & enemy_king != 0;
+                !gives_check || !self.make_move(mv, player).is_checkmate(opp)
+            }
+            _ => true,
+        });
+        moves
+    }
+
+    /// 指し手を適用した局面を返す
+    pub fn make_move(&self, mv: Move, player: Player) -> Self {
+        let mut next = *self;
+        let c = player_index(player);
+
+        match mv {
+            Move::To(from, to, promote) => {
+                let from = square(from);
+                let to = square(to);
+                let kind = self
+                    .kind_at(c, from)
+                    .expect("move_from position should have a piece");
+
+                if let Some(captured) = self.kind_at(1 - c, to) {
+                    next.pieces[1 - c][captured] &= !(1 << to);
+                    next.occupied[1 - c] &= !(1 << to);
+                    next.hands[c].add(piece_of(captured).0);
+                }
+
+                let new_kind = if promote {
+                    kind_of(piece_of(kind).0, true)
+                } else {
+                    kind
+                };
+                next.pieces[c][kind] &= !(1 << from);
+                next.pieces[c][new_kind] |= 1 << to;
+                next.occupied[c] ^= (1 << from) | (1 << to);
+            }
+            Move::Drop(to, piece_type) => {
+                if next.hands[c].remove(piece_type) {
+                    let to = square(to);
+                    next.pieces[c][kind_of(piece_type, false)] |= 1 << to;
+                    next.occupied[c] |= 1 << to;
+                }
+            }
+        }
+
+        next
+    }
+
+    /// `perft::perft` と同じ定義の局面数（自玉を王手に晒す手は数えない）
+    pub fn perft(&self, player: Player, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let opp = opponent_of(player);
+        let mut nodes = 0;
+        for mv in self.generate_legal_moves(player) {
+            let next = self.make_move(mv, player);
+            if next.is_in_check(player) {
+                continue;
+            }
+            nodes += if depth == 1 {
+                1
+            } else {
+                next.perft(opp, depth - 1)
+            };
+        }
+        nodes
+    }
+}
@@ -4,6 +4,14 @@ pub enum Player {
     Gote,
 }
 
+/// 手番を配列の添字にする（先手 0、後手 1）
+pub(crate) fn player_index(player: Player) -> usize {
+    match player {
+        Player::Sente => 0,
+        Player::Gote => 1,
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PieceType {
     King,
@@ -30,6 +38,18 @@ pub struct Piece {
 // y=4 [ .. , .. , .. , .. , .. ]
 pub type Board = [[Option<Piece>; 5]; 5];
 
+/// 持ち駒になる駒（安い順。評価関数の持ち駒の重みもこの順）
+pub const HAND_TYPES: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Silver,
+    PieceType::Gold,
+    PieceType::Bishop,
+    PieceType::Rook,
+];
+
+/// 持ち駒の枚数の上限（各駒種とも2枚ずつしかない）
+pub const MAX_HAND: usize = 2;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Hand {
     pub gold: u8,
@@ -119,6 +139,31 @@ impl GameState {
             Player::Gote => &mut self.gote_hand,
         }
     }
+
+    /// 五々将棋の駒の数を超えている駒種
+    ///
+    /// 玉は先手・後手とも1枚まで、ほかの駒は盤上と両者の持ち駒を合わせて `MAX_HAND` 枚まで。
+    /// 玉を取って終わった局面もあるので、玉がないことは許す。
+    pub fn excess_piece(&self) -> Option<PieceType> {
+        let on_board = |piece_type: PieceType, owner: Option<Player>| {
+            self.board
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|p| p.piece_type == piece_type && owner.is_none_or(|o| p.owner == o))
+                .count()
+        };
+        if [Player::Sente, Player::Gote]
+            .into_iter()
+            .any(|owner| on_board(PieceType::King, Some(owner)) > 1)
+        {
+            return Some(PieceType::King);
+        }
+        HAND_TYPES.into_iter().find(|&piece_type| {
+            let in_hands = [self.sente_hand, self.gote_hand].map(|h| h.get(piece_type) as usize);
+            on_board(piece_type, None) + in_hands[0] + in_hands[1] > MAX_HAND
+        })
+    }
 }
 
 pub fn init() -> GameState {
@@ -0,0 +1,265 @@
+use crate::board::{self, GameState, Player};
+use crate::kifu;
+use crate::rules::{self, Move};
+use crate::search::Rng;
+use crate::sfen;
+use crate::zobrist;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// 定跡ファイルはテキスト形式で、1行に「局面ハッシュ(16進) 指し手(USI形式) 重み」を書く。
+// '#' で始まる行は注釈として読み飛ばす。
+
+/// 定跡手とその重み
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    pub mv: Move,
+    pub weight: u32,
+}
+
+/// 定跡手の選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookSelection {
+    /// 重みに比例した確率で選ぶ
+    #[default]
+    WeightedRandom,
+    /// 最も重みの大きい手を選ぶ
+    Best,
+}
+
+/// 局面ハッシュ → 定跡手の表
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登録されている局面数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 定跡手を追加する。既に登録済みの手なら重みを加算する（`u32::MAX` で頭打ち）
+    pub fn add(&mut self, state: &GameState, player: Player, mv: Move, weight: u32) {
+        if weight == 0 {
+            return;
+        }
+        self.add_hashed(zobrist::hash(state, player), mv, weight);
+    }
+
+    fn add_hashed(&mut self, key: u64, mv: Move, weight: u32) {
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|m| m.mv == mv) {
+            Some(m) => m.weight = m.weight.saturating_add(weight),
+            None => moves.push(BookMove { mv, weight }),
+        }
+    }
+
+    /// 局面の定跡手を引く
+    pub fn probe(&self, state: &GameState, player: Player) -> Option<&[BookMove]> {
+        self.entries
+            .get(&zobrist::hash(state, player))
+            .map(Vec::as_slice)
+    }
+
+    /// 定跡手を1つ選ぶ。ハッシュ衝突に備えて合法手でない手は除外する
+    pub fn select(
+        &self,
+        state: &GameState,
+        player: Player,
+        selection: BookSelection,
+        rng: &mut Rng,
+    ) -> Option<Move> {
+        let legal_moves = rules::generate_legal_moves(state, player);
+        let candidates: Vec<BookMove> = self
+            .probe(state, player)?
+            .iter()
+            .filter(|m| legal_moves.contains(&m.mv))
+            .copied()
+            .collect();
+
+        match selection {
+            BookSelection::Best => candidates.iter().max_by_key(|m| m.weight).map(|m| m.mv),
+            BookSelection::WeightedRandom => {
+                let total: u64 = candidates.iter().map(|m| u64::from(m.weight)).sum();
+                if total == 0 {
+                    return None;
+                }
+                let mut r = rng.next_u64() % total;
+                for m in &candidates {
+                    let w = u64::from(m.weight);
+                    if r < w {
+                        return Some(m.mv);
+                    }
+                    r -= w;
+                }
+                None
+            }
+        }
+    }
+
+    /// 初期局面からの1局分の指し手を、先手・後手それぞれの重みで `max_plies` 手目まで登録する
+    pub fn add_game(&mut self, moves: &[Move], max_plies: usize, weights: (u32, u32)) {
+        let mut state = board::init();
+        let mut player = Player::Sente;
+        for &mv in moves.iter().take(max_plies) {
+            let weight = match player {
+                Player::Sente => weights.0,
+                Player::Gote => weights.1,
+            };
+            self.add(&state, player, mv, weight);
+            state = rules::make_move(&state, mv, player);
+            player = rules::opponent_of(player);
+        }
+    }
+
+    /// 棋譜集から定跡を作る
+    ///
+    /// 1行1局で、初期局面からの指し手を USI 形式で空白区切りに並べる。
+    /// 末尾に結果（`1-0` 先手勝ち、`0-1` 後手勝ち、`1/2-1/2` 引き分け）があれば
+    /// 勝った側の手を重み2、引き分けは重み1とし、負けた側の手は登録しない。
+    pub fn from_records(text: &str, max_plies: usize) -> Result<Self, String> {
+        let mut book = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+            let weights = match tokens.last().copied() {
+                Some("1-0") => Some((2, 0)),
+                Some("0-1") => Some((0, 2)),
+                Some("1/2-1/2") => Some((1, 1)),
+                _ => None,
+            };
+            if weights.is_some() {
+                tokens.pop();
+            }
+
+            let moves = replay(&tokens).map_err(|e| format!("{}行目: {}", i + 1, e))?;
+            book.add_game(&moves, max_plies, weights.unwrap_or((1, 1)));
+        }
+
+        Ok(book)
+    }
+
+    /// 自己対局から定跡を作る。`engine` は局面と手番から指し手を返す
+    ///
+    /// 玉が取られたらその局の記録を終える。
+    pub fn from_selfplay<F>(games: usize, max_plies: usize, mut engine: F) -> Self
+    where
+        F: FnMut(&GameState, Player) -> Option<Move>,
+    {
+        let mut book = Self::new();
+
+        for _ in 0..games {
+            let mut state = board::init();
+            let mut player = Player::Sente;
+            let mut moves = Vec::new();
+
+            while moves.len() < max_plies {
+                let Some(mv) = engine(&state, player) else {
+                    break;
+                };
+                moves.push(mv);
+                state = rules::make_move(&state, mv, player);
+                player = rules::opponent_of(player);
+                if kifu::is_finished(&state) {
+                    break;
+                }
+            }
+
+            book.add_game(&moves, max_plies, (1, 1));
+        }
+
+        book
+    }
+
+    /// テキスト形式の定跡を読み込む
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut book = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let err = |msg: String| format!("{}行目: {}", i + 1, msg);
+            let [key, mv, weight] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(err(format!("項目数が不正です: {}", line)));
+            };
+            let key = u64::from_str_radix(key, 16)
+                .map_err(|_| err(format!("ハッシュ値が不正です: {}", key)))?;
+            let mv = sfen::parse_move(mv).map_err(err)?;
+            let weight = weight
+                .parse::<u32>()
+                .map_err(|_| err(format!("重みが不正です: {}", weight)))?;
+
+            book.add_hashed(key, mv, weight);
+        }
+
+        Ok(book)
+    }
+
+    /// テキスト形式に変換する（ハッシュ順・重みの大きい順）
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+
+        let mut text = String::from("# minishogi-rs opening book: <hash> <move> <weight>\n");
+        for key in keys {
+            let mut moves = self.entries[key].clone();
+            moves.sort_by_key(|m| std::cmp::Reverse(m.weight));
+            for m in moves {
+                text.push_str(&format!(
+                    "{:016x} {} {}\n",
+                    key,
+                    sfen::format_move(m.mv),
+                    m.weight
+                ));
+            }
+        }
+        text
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
+
+/// 初期局面から USI 形式の指し手を再生し、合法性を確かめる
+pub(crate) fn replay(tokens: &[&str]) -> Result<Vec<Move>, String> {
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    let mut moves = Vec::with_capacity(tokens.len());
+
+    for (n, token) in tokens.iter().enumerate() {
+        let mv = sfen::parse_move(token)?;
+        if !rules::generate_legal_moves(&state, player).contains(&mv) {
+            return Err(format!("{}手目 {} は合法手ではありません", n + 1, token));
+        }
+        moves.push(mv);
+        state = rules::make_move(&state, mv, player);
+        player = rules::opponent_of(player);
+    }
+
+    Ok(moves)
+}
@@ -0,0 +1,576 @@
+mod analyze;
+pub mod bookgen;
+pub mod csa;
+mod input;
+pub mod nnue;
+pub mod perft;
+mod play;
+pub mod selfplay;
+pub mod tbgen;
+pub mod tournament;
+pub mod tsume;
+pub mod tune;
+pub mod usi;
+
+use crate::board::Player;
+use crate::engine::{Builtin, Engine};
+use crate::locale::Locale;
+use crate::notation::Notation;
+use crate::{board, book, eval, kif, kifu, search, sfen, tablebase, tr};
+use std::time::Duration;
+
+// コマンドライン引数
+//
+// 対局（`play`）と局面の検討（`analyze`）の引数を読んで実行する。`perft`・`selfplay`・`usi` などの
+// サブコマンドは同名のモジュールの `run` が残りの引数と表示言語を受け取る。どれもエラーは `Err` で返し、
+// 表示と終了コードは呼び出し側に任せる。
+
+/// `--help` で表示する使い方
+pub const USAGE: &str = "\
+使い方: minishogi-rs [play] [オプション]
+        minishogi-rs analyze [オプション] [SFEN]
+        minishogi-rs <サブコマンド> ...
+
+対局者（指定しなければ対話的に選ぶ）:
+  --sente <対局者>        先手の対局者（human、ab[:深さ]、mcts[:反復回数]）
+  --gote <対局者>         後手の対局者
+  --side <s|g>            人間の手番（もう一方は --engine の AI）
+  --engine <ab|mcts>      --side の相手と analyze の AI（既定は ab）
+
+探索:
+  --depth <N>             Alpha-Beta 探索の深さ
+  --iterations <N>        MCTS の反復回数
+  --time <ミリ秒>         1手の思考時間の上限
+  --seed <N>              乱数のシード
+  --tb <ファイル>         終盤データベース（複数指定可）
+  --book <ファイル>       定跡
+  --book-best             定跡から最善手だけを選ぶ
+  --eval <ファイル>       評価関数の重み
+  --nnue <ファイル>       NNUE の重み
+
+対局:
+  --sfen <SFEN>           開始局面
+  --load <ファイル>       棋譜（.kif か .csa）を読み込み、最後の局面から指す
+  --record <ファイル>     棋譜の保存先（.kif なら KIF 形式、.csa なら CSA 形式）
+  --delay <ミリ秒>        AI 同士の対局で1手ごとに待つ時間
+  --notation <ja|western> 指し手の表記（既定は表示言語に合わせる。western は S-2b+ のような Hodges 表記）
+  --lang <ja|en>          表示言語（既定は環境変数 MINISHOGI_LANG、なければ ja）
+
+サブコマンド:
+  play                    対局する（省略可）
+  analyze [SFEN]          局面を探索して最善手と評価値を表示する
+  perft|divide <深さ> [SFEN]
+  selfplay <局数> <出力ファイル> [...]
+  usi [ab[:深さ]|mcts[:反復回数]]
+  csa <ホスト:ポート> <名前> <パスワード> [ab|mcts]
+                          CSA サーバーに接続して対局する
+  csaserver <ホスト:ポート> [持ち時間] [秒読み]
+                          2人のクライアントを対局させる CSA サーバー
+  match / tournament / tsume / tbgen / bookgen / tune / nnue / bench
+                          それぞれ引数なしで使い方を表示する
+
+--lang はどのサブコマンドにも指定できる。
+";
+
+/// `--help` で表示する英語の使い方
+pub const USAGE_EN: &str = "\
+Usage: minishogi-rs [play] [options]
+       minishogi-rs analyze [options] [SFEN]
+       minishogi-rs <subcommand> ...
+
+Players (chosen interactively if omitted):
+  --sente <player>        Sente player (human, ab[:depth], mcts[:iterations])
+  --gote <player>         Gote player
+  --side <s|g>            Side played by the human (the other is the --engine AI)
+  --engine <ab|mcts>      Engine for --side and analyze (default: ab)
+
+Search:
+  --depth <N>             Alpha-Beta search depth
+  --iterations <N>        MCTS iterations
+  --time <ms>             Time limit per move
+  --seed <N>              Random seed
+  --tb <file>             Endgame tablebase (may be repeated)
+  --book <file>           Opening book
+  --book-best             Always play the best book move
+  --eval <file>           Evaluation weights
+  --nnue <file>           NNUE weights
+
+Game:
+  --sfen <SFEN>           Starting position
+  --load <file>           Load a game record (.kif or .csa) and continue from its last position
+  --record <file>         Save the game record (KIF for .kif, CSA for .csa)
+  --delay <ms>            Delay per move in engine-vs-engine games
+  --notation <ja|western> Move notation (default: follows --lang; western is Hodges notation such as S-2b+)
+  --lang <ja|en>          Display language (default: $MINISHOGI_LANG, otherwise ja)
+
+Subcommands:
+  play                    Play a game (may be omitted)
+  analyze [SFEN]          Search a position and show the best move and score
+  perft|divide <depth> [SFEN]
+  selfplay <games> <output file> [...]
+  usi [ab[:depth]|mcts[:iterations]]
+  csa <host:port> <name> <password> [ab|mcts]
+                          Connect to a CSA server and play
+  csaserver <host:port> [total time] [byoyomi]
+                          CSA server that pairs two clients
+  match / tournament / tsume / tbgen / bookgen / tune / nnue / bench
+                          Run without arguments to show their usage
+
+--lang works with every subcommand. The details of errors in SFEN, game records
+and data files are shown in Japanese.
+";
+
+/// 値を1つとるオプション
+const VALUE_FLAGS: &[&str] = &[
+    "--sente",
+    "--gote",
+    "--side",
+    "--engine",
+    "--depth",
+    "--iterations",
+    "--time",
+    "--seed",
+    "--delay",
+    "--sfen",
+    "--record",
+    "--load",
+    "--tb",
+    "--book",
+    "--eval",
+    "--nnue",
+    "--notation",
+    "--lang",
+];
+
+/// 対局者
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Human,
+    Ai {
+        engine: Engine,
+        /// Alpha-Beta なら探索の深さ、MCTS なら反復回数（`None` なら探索の設定に従う）
+        strength: Option<u32>,
+    },
+}
+
+impl Side {
+    /// `human` か `ab[:深さ]`・`mcts[:反復回数]`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if s == "human" {
+            return Ok(Side::Human);
+        }
+        let Builtin { engine, strength } = Builtin::parse(s)?;
+        Ok(Side::Ai { engine, strength })
+    }
+}
+
+/// `play` と `analyze` の引数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /// 先手・後手の対局者（`None` なら対話的に選ぶ）
+    pub sides: [Option<Side>; 2],
+    /// `analyze` と、対局者を指定しない AI のアルゴリズム
+    pub engine: Option<Engine>,
+    pub depth: Option<u32>,
+    pub iterations: Option<u32>,
+    /// 1手の思考時間の上限（ミリ秒）
+    pub time_ms: Option<u64>,
+    pub seed: Option<u64>,
+    /// 開始局面の SFEN
+    pub sfen: Option<String>,
+    /// 棋譜の保存先
+    pub record: Option<String>,
+    /// 読み込む棋譜（最後の局面から指す・検討する）
+    pub load: Option<String>,
+    /// AI 同士の対局で1手ごとに待つ時間（ミリ秒）
+    pub delay_ms: Option<u64>,
+    /// 表示と入力に使う指し手の表記（`None` なら表示言語に合わせる）
+    pub notation: Option<Notation>,
+    /// 表示言語（`None` なら環境変数で決める）
+    pub locale: Option<Locale>,
+    pub tablebases: Vec<String>,
+    pub book: Option<String>,
+    pub book_best: bool,
+    pub eval: Option<String>,
+    pub nnue: Option<String>,
+}
+
+impl Options {
+    /// 先手・後手とも決まっていれば返す
+    pub fn fixed_sides(&self) -> Option<[Side; 2]> {
+        match self.sides {
+            [Some(sente), Some(gote)] => Some([sente, gote]),
+            _ => None,
+        }
+    }
+}
+
+/// サブコマンド
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Play(Options),
+    Analyze(Options),
+    Help,
+}
+
+/// 引数の `--lang` の言語（指定がないか読めなければ `default`）
+pub fn locale(args: &[String], default: Locale) -> Locale {
+    args.windows(2)
+        .find(|w| w[0] == "--lang")
+        .and_then(|w| Locale::parse(&w[1]))
+        .unwrap_or(default)
+}
+
+/// サブコマンドの引数から `--lang <言語>` を取り除き、残りの引数と表示言語を返す
+/// （指定がなければ `default`）
+pub fn split_lang(args: &[String], default: Locale) -> Result<(Vec<String>, Locale), String> {
+    let mut locale = default;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg != "--lang" {
+            rest.push(arg.clone());
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| tr!(locale, "{} には値が必要です", "{} needs a value", arg))?;
+        locale = Locale::parse(value)
+            .ok_or_else(|| tr!(locale, "不明な言語です: {}", "Unknown language: {}", value))?;
+    }
+    Ok((rest, locale))
+}
+
+/// `play`・`analyze`・`help` の引数を読む（サブコマンドを省略すれば `play`）
+///
+/// エラーは `--lang` の言語で返す（指定がなければ `locale`）。
+pub fn parse(args: &[String], locale: Locale) -> Result<Command, String> {
+    let locale = self::locale(args, locale);
+    let (analyze, args) = match args.first().map(String::as_str) {
+        Some("help") => return Ok(Command::Help),
+        Some("play") => (false, &args[1..]),
+        Some("analyze") => (true, &args[1..]),
+        _ => (false, args),
+    };
+
+    let mut options = Options::default();
+    let mut side = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let flag = arg.as_str();
+        match flag {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--book-best" => {
+                options.book_best = true;
+                continue;
+            }
+            _ if !flag.starts_with("--") => {
+                // analyze の SFEN は引用符なしでも空白区切りで受け付ける
+                if !analyze {
+                    return Err(tr!(
+                        locale,
+                        "不明な引数です: {}",
+                        "Unknown argument: {}",
+                        arg
+                    ));
+                }
+                let sfen = options.sfen.get_or_insert_with(String::new);
+                if !sfen.is_empty() {
+                    sfen.push(' ');
+                }
+                sfen.push_str(arg);
+                continue;
+            }
+            _ => {}
+        }
+
+        if !VALUE_FLAGS.contains(&flag) {
+            return Err(tr!(
+                locale,
+                "不明な引数です: {}",
+                "Unknown argument: {}",
+                flag
+            ));
+        }
+        let value = rest
+            .next()
+            .ok_or_else(|| tr!(locale, "{} には値が必要です", "{} needs a value", flag))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| invalid_value(locale, flag, value))
+        };
+        // 値を読む関数のエラーは日本語なので、英語では値が不正なことだけを伝える
+        let invalid = |e: String| match locale {
+            Locale::Japanese => e,
+            Locale::English => invalid_value(locale, flag, value),
+        };
+        match flag {
+            "--sente" => options.sides[0] = Some(Side::parse(value).map_err(invalid)?),
+            "--gote" => options.sides[1] = Some(Side::parse(value).map_err(invalid)?),
+            "--side" => {
+                side = Some(match value.as_str() {
+                    "s" | "sente" => Player::Sente,
+                    "g" | "gote" => Player::Gote,
+                    _ => {
+                        return Err(tr!(
+                            locale,
+                            "--side は s か g です: {}",
+                            "--side must be s or g: {}",
+                            value
+                        ));
+                    }
+                })
+            }
+            "--engine" => options.engine = Some(Engine::parse(value).map_err(invalid)?),
+            "--depth" => options.depth = Some(number_u32(locale, flag, value)?),
+            "--iterations" => options.iterations = Some(number_u32(locale, flag, value)?),
+            "--time" => options.time_ms = Some(number()?),
+            "--seed" => options.seed = Some(number()?),
+            "--delay" => options.delay_ms = Some(number()?),
+            "--sfen" => options.sfen = Some(value.clone()),
+            "--record" => options.record = Some(value.clone()),
+            "--load" => options.load = Some(value.clone()),
+            "--tb" => options.tablebases.push(value.clone()),
+            "--book" => options.book = Some(value.clone()),
+            "--eval" => options.eval = Some(value.clone()),
+            "--nnue" => options.nnue = Some(value.clone()),
+            "--notation" => options.notation = Some(Notation::parse(value).map_err(invalid)?),
+            "--lang" => {
+                let unknown = || tr!(locale, "不明な言語です: {}", "Unknown language: {}", value);
+                options.locale = Some(Locale::parse(value).ok_or_else(unknown)?)
+            }
+            _ => unreachable!("VALUE_FLAGS にあるオプションはすべて扱う"),
+        }
+    }
+
+    if options.sfen.is_some() && options.load.is_some() {
+        return Err(tr!(
+            locale,
+            "--sfen と --load は同時に指定できません",
+            "--sfen and --load cannot be used together"
+        ));
+    }
+
+    // --side は人間の手番。もう一方は --engine の AI にする
+    if let Some(human) = side {
+        let (mine, theirs) = match human {
+            Player::Sente => (0, 1),
+            Player::Gote => (1, 0),
+        };
+        options.sides[mine].get_or_insert(Side::Human);
+        options.sides[theirs].get_or_insert(Side::Ai {
+            engine: options.engine.unwrap_or(Engine::AlphaBeta),
+            strength: None,
+        });
+    }
+
+    Ok(if analyze {
+        Command::Analyze(options)
+    } else {
+        Command::Play(options)
+    })
+}
+
+fn number_u32(locale: Locale, flag: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| invalid_value(locale, flag, value))
+}
+
+fn invalid_value(locale: Locale, flag: &str, value: &str) -> String {
+    tr!(
+        locale,
+        "{} の値が不正です: {}",
+        "Invalid value for {}: {}",
+        flag,
+        value
+    )
+}
+
+/// `play`・`analyze` を引数に従って実行する
+pub fn run(args: &[String]) -> Result<(), String> {
+    // --help や引数の誤りも --lang の言語で表示する
+    let locale = locale(args, Locale::from_env());
+    let (analyze, cli_options) = match parse(args, locale) {
+        Ok(Command::Help) => {
+            print!("{}", locale.text(USAGE, USAGE_EN));
+            return Ok(());
+        }
+        Ok(Command::Play(o)) => (false, o),
+        Ok(Command::Analyze(o)) => (true, o),
+        Err(e) => {
+            return Err(tr!(
+                locale,
+                "エラー: {}（--help で使い方を表示）",
+                "Error: {} (see --help)",
+                e
+            ));
+        }
+    };
+    let notation = cli_options
+        .notation
+        .unwrap_or_else(|| locale.default_notation());
+
+    let data = SearchData::load(&cli_options, locale)?;
+    let mut evaluator = data.evaluator;
+    let options = search::SearchOptions {
+        tablebases: &data.tablebases,
+        book: data.book.as_ref(),
+        book_selection: if cli_options.book_best {
+            book::BookSelection::Best
+        } else {
+            book::BookSelection::default()
+        },
+        depth: cli_options.depth,
+        iterations: cli_options.iterations,
+        time_limit: cli_options.time_ms.map(Duration::from_millis),
+        stop: None,
+        seed: cli_options.seed,
+    };
+    let start = match (&cli_options.sfen, &cli_options.load) {
+        (Some(s), _) => {
+            let (state, player) = sfen::parse(s).map_err(|e| invalid_sfen(s, &e, locale))?;
+            kifu::Kifu::new(state, player)
+        }
+        (None, Some(path)) => load_kifu(path, locale).map_err(|e| {
+            tr!(
+                locale,
+                "棋譜を読み込めません: {}: {}",
+                "Cannot load the game record: {}: {}",
+                path,
+                e
+            )
+        })?,
+        (None, None) => kifu::Kifu::new(board::init(), Player::Sente),
+    };
+
+    if analyze {
+        let engine = cli_options.engine.unwrap_or(Engine::AlphaBeta);
+        let (state, player) = start.position();
+        analyze::run(
+            notation,
+            locale,
+            &state,
+            player,
+            engine,
+            &options,
+            evaluator.as_mut(),
+        );
+        return Ok(());
+    }
+    play::run(
+        &cli_options,
+        notation,
+        locale,
+        &start,
+        &options,
+        evaluator.as_mut(),
+    )
+}
+
+/// 表示用のアルゴリズム名
+fn engine_name(engine: Engine) -> &'static str {
+    match engine {
+        Engine::AlphaBeta => "Alpha-Beta",
+        Engine::Mcts => "MCTS",
+    }
+}
+
+/// 読めない SFEN のエラー（SFEN のエラーの文は日本語なので、英語では入力も添える）
+fn invalid_sfen(input: &str, e: &str, locale: Locale) -> String {
+    match locale {
+        Locale::Japanese => format!("SFEN が不正です: {}", e),
+        Locale::English => format!("Invalid SFEN: {} ({})", input, e),
+    }
+}
+
+/// 拡張子が `ext` のファイルか（大文字・小文字は区別しない）
+fn has_extension(path: &str, ext: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// 棋譜ファイルを読み込む（`.kif`・`.kifu` は KIF 形式、`.csa` は CSA 形式）
+fn load_kifu(path: &str, locale: Locale) -> Result<kifu::Kifu, String> {
+    if has_extension(path, "kif") || has_extension(path, "kifu") {
+        kif::load(path).map_err(|e| e.to_string())
+    } else if has_extension(path, "csa") {
+        crate::csa::load(path).map_err(|e| e.to_string())
+    } else {
+        Err(tr!(
+            locale,
+            "拡張子から形式が分かりません（.kif・.kifu・.csa）",
+            "Unknown format for this extension (.kif, .kifu or .csa)"
+        ))
+    }
+}
+
+/// `--tb`・`--book`・`--eval`・`--nnue` で読み込むデータ
+struct SearchData {
+    tablebases: Vec<tablebase::Tablebase>,
+    book: Option<book::Book>,
+    evaluator: Box<dyn eval::Evaluator>,
+}
+
+impl SearchData {
+    fn load(options: &Options, locale: Locale) -> Result<Self, String> {
+        let mut data = SearchData {
+            tablebases: Vec::new(),
+            book: None,
+            evaluator: Box::new(eval::Positional::default()),
+        };
+        for path in &options.tablebases {
+            let tb = tablebase::Tablebase::load(path).map_err(|e| {
+                tr!(
+                    locale,
+                    "終盤データベースを読み込めません: {}: {}",
+                    "Cannot load the tablebase: {}: {}",
+                    path,
+                    e
+                )
+            })?;
+            data.tablebases.push(tb);
+        }
+        if let Some(path) = &options.book {
+            let b = book::Book::load(path).map_err(|e| {
+                tr!(
+                    locale,
+                    "定跡を読み込めません: {}: {}",
+                    "Cannot load the opening book: {}: {}",
+                    path,
+                    e
+                )
+            })?;
+            data.book = Some(b);
+        }
+        if let Some(path) = &options.eval {
+            let w = eval::Weights::load(path).map_err(|e| {
+                tr!(
+                    locale,
+                    "評価関数の重みを読み込めません: {}: {}",
+                    "Cannot load the evaluation weights: {}: {}",
+                    path,
+                    e
+                )
+            })?;
+            data.evaluator = Box::new(eval::Positional::new(w));
+        }
+        if let Some(path) = &options.nnue {
+            let n = crate::nnue::Network::load(path).map_err(|e| {
+                tr!(
+                    locale,
+                    "NNUE の重みを読み込めません: {}: {}",
+                    "Cannot load the NNUE weights: {}: {}",
+                    path,
+                    e
+                )
+            })?;
+            data.evaluator = Box::new(crate::nnue::Nnue::new(n));
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::cli::engine_name;
+use crate::locale::Locale;
+use crate::{board, engine, eval, notation, search, sfen, tr, ui};
+
+// 局面の検討
+//
+// 1局面を探索して最善手と評価値、MCTS なら訪問回数の多い候補手を表示する。
+
+/// 局面の検討で表示する MCTS の候補手の数
+const ANALYZE_TOP_MOVES: usize = 5;
+
+/// 局面の検討: `analyze [オプション] [SFEN]`
+pub(crate) fn run(
+    notation: notation::Notation,
+    locale: Locale,
+    state: &board::GameState,
+    player: board::Player,
+    engine: engine::Engine,
+    options: &search::SearchOptions,
+    evaluator: &mut dyn eval::Evaluator,
+) {
+    let position = sfen::format(state, player);
+    println!("{}", tr!(locale, "局面: {}", "Position: {}", position));
+    ui::print_game_state(state, board::Player::Sente, None, locale);
+
+    let started = std::time::Instant::now();
+    let builtin = engine::Builtin {
+        engine,
+        strength: None,
+    };
+    let result = builtin.search(state, player, options, evaluator);
+    let Some(mv) = result.best_move else {
+        let name = locale.player_name(player);
+        println!(
+            "{}",
+            tr!(
+                locale,
+                "{}に合法手がありません",
+                "{} has no legal moves",
+                name
+            )
+        );
+        return;
+    };
+    let best = notation.format_move(state, player, mv, None);
+    let usi = sfen::format_move(mv);
+    println!(
+        "{}",
+        tr!(locale, "最善手: {}（{}）", "Best move: {} ({})", best, usi)
+    );
+    let score = match result.score {
+        Some(score) => tr!(
+            locale,
+            "評価値: {:+}（先手から見た値）",
+            "Score: {:+} (from Sente's view)",
+            score
+        ),
+        None => tr!(locale, "評価値: -（定跡の手）", "Score: - (book move)"),
+    };
+    println!("{}", score);
+
+    let mut visits = result.visits;
+    visits.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    for (mv, n) in visits.iter().take(ANALYZE_TOP_MOVES) {
+        let mv = notation.format_move(state, player, *mv, None);
+        println!(
+            "{}",
+            tr!(locale, "  {:<10} {:>7}回", "  {:<10} {:>7} visits", mv, n)
+        );
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    let engine = engine_name(engine);
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "探索時間: {:.2}秒（{}）",
+            "Search time: {:.2}s ({})",
+            seconds,
+            engine
+        )
+    );
+}
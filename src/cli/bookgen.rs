@@ -0,0 +1,81 @@
+use crate::locale::Locale;
+use crate::{book, search, tr};
+use std::fs;
+
+// 定跡の生成
+
+const USAGE: &str = "\
+使い方:
+  bookgen selfplay <局数> <手数> <出力ファイル>
+  bookgen records <棋譜ファイル> <手数> <出力ファイル>";
+
+const USAGE_EN: &str = "\
+Usage:
+  bookgen selfplay <games> <plies> <output file>
+  bookgen records <record file> <plies> <output file>";
+
+/// 定跡生成:
+///   `bookgen selfplay <局数> <手数> <出力ファイル>`（MCTS 同士の自己対局）
+///   `bookgen records <棋譜ファイル> <手数> <出力ファイル>`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let usage = || locale.text(USAGE, USAGE_EN).to_string();
+    let [kind, source, plies, path] = args else {
+        return Err(usage());
+    };
+    let plies = plies.parse::<usize>().map_err(|_| {
+        tr!(
+            locale,
+            "手数が不正です: {}",
+            "Invalid number of plies: {}",
+            plies
+        )
+    })?;
+
+    let opening_book = match kind.as_str() {
+        "selfplay" => {
+            let games = source.parse::<usize>().map_err(|_| {
+                tr!(
+                    locale,
+                    "局数が不正です: {}",
+                    "Invalid number of games: {}",
+                    source
+                )
+            })?;
+            println!(
+                "{}",
+                locale.text("自己対局中...", "Playing self-play games...")
+            );
+            book::Book::from_selfplay(games, plies, search::best_move_mcts)
+        }
+        "records" => {
+            let text = fs::read_to_string(source).map_err(|e| {
+                tr!(
+                    locale,
+                    "棋譜ファイルを読み込めません: {}: {}",
+                    "Cannot read the record file: {}: {}",
+                    source,
+                    e
+                )
+            })?;
+            book::Book::from_records(&text, plies)
+                .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?
+        }
+        _ => return Err(usage()),
+    };
+
+    opening_book
+        .save(path)
+        .map_err(|e| tr!(locale, "保存に失敗しました: {}", "Cannot save: {}", e))?;
+    let n = opening_book.len();
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "{} に保存しました（{}局面）",
+            "Saved to {} ({} positions)",
+            path,
+            n
+        )
+    );
+    Ok(())
+}
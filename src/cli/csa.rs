@@ -0,0 +1,114 @@
+use crate::locale::Locale;
+use crate::{csa, csaprotocol, engine, search, tr};
+use std::net::TcpListener;
+
+// CSA 通信対局（クライアントとサーバー）
+
+/// CSA サーバーに接続して1局指す: `csa <アドレス> <名前> <パスワード> [ab|mcts]`
+pub fn client(args: &[String], locale: Locale) -> Result<(), String> {
+    let [addr, name, password, rest @ ..] = args else {
+        return Err(tr!(
+            locale,
+            "使い方: csa <ホスト:ポート> <名前> <パスワード> [ab|mcts]",
+            "Usage: csa <host:port> <name> <password> [ab|mcts]"
+        ));
+    };
+    let engine = match rest.first() {
+        Some(s) => {
+            engine::Engine::parse(s).map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?
+        }
+        None => engine::Engine::AlphaBeta,
+    };
+
+    let report = csaprotocol::Client::login(addr.as_str(), name, password)
+        .and_then(|mut client| {
+            println!(
+                "{}",
+                tr!(
+                    locale,
+                    "{} に {} としてログインしました",
+                    "Logged in to {} as {}",
+                    addr,
+                    name
+                )
+            );
+            let report = client.play(|state, player, think_time| {
+                // 持ち時間から決めた思考時間で探索する
+                let options = search::SearchOptions {
+                    time_limit: Some(think_time),
+                    ..search::SearchOptions::default()
+                };
+                engine.search(state, player, &options)
+            })?;
+            client.logout()?;
+            Ok(report)
+        })
+        .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+
+    let players = &report.summary.kifu.players;
+    let (game_id, plies) = (&report.summary.game_id, report.kifu.moves.len());
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "対局 {}: ▲{} △{}（{}手）",
+            "Game {}: ▲{} △{} ({} moves)",
+            game_id,
+            players[0],
+            players[1],
+            plies
+        )
+    );
+    let outcome = match report.outcome {
+        csaprotocol::Outcome::Win => locale.text("勝ち", "win"),
+        csaprotocol::Outcome::Lose => locale.text("負け", "loss"),
+        csaprotocol::Outcome::Draw => locale.text("引き分け", "draw"),
+        csaprotocol::Outcome::Censored => locale.text("打ち切り", "censored"),
+        csaprotocol::Outcome::Chudan => locale.text("中断", "interrupted"),
+    };
+    let line = match &report.reason {
+        Some(reason) => tr!(locale, "結果: {}（{}）", "Result: {} ({})", outcome, reason),
+        None => tr!(locale, "結果: {}", "Result: {}", outcome),
+    };
+    println!("{}", line);
+    Ok(())
+}
+
+/// 2つのクライアントを1局対局させる CSA サーバー: `csaserver <アドレス> [持ち時間] [秒読み]`
+pub fn server(args: &[String], locale: Locale) -> Result<(), String> {
+    let usage = || {
+        tr!(
+            locale,
+            "使い方: csaserver <ホスト:ポート> [持ち時間（秒）] [秒読み（秒）]",
+            "Usage: csaserver <host:port> [main time (s)] [byoyomi (s)]"
+        )
+    };
+    let Some(addr) = args.first() else {
+        return Err(usage());
+    };
+    let mut config = csaprotocol::ServerConfig::default();
+    for (i, field) in [&mut config.time.total, &mut config.time.byoyomi]
+        .into_iter()
+        .enumerate()
+    {
+        if let Some(s) = args.get(i + 1) {
+            *field = s.parse::<u64>().map_err(|_| usage())?;
+        }
+    }
+
+    let listener = TcpListener::bind(addr.as_str())
+        .map_err(|e| tr!(locale, "エラー: {}: {}", "Error: {}: {}", addr, e))?;
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "{} で2人のログインを待っています",
+            "Waiting for two players to log in on {}",
+            addr
+        )
+    );
+    let kifu = csaprotocol::serve_game(&listener, &config)
+        .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+    print!("{}", csa::to_text(&kifu));
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use crate::locale::Locale;
+use crate::{board, notation, rules, tr};
+use std::io;
+
+// 人間の指し手の入力
+//
+// 対局と詰将棋で共通の、標準入力の読み込みと指し手・位置・駒の読み取り。
+
+/// 標準入力から 1 行読む。入力が終わった（EOF）ときは None
+pub(crate) fn read_input() -> Option<String> {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input),
+    }
+}
+
+pub(crate) fn end_of_input(locale: Locale) -> &'static str {
+    locale.text("\n入力が終わったので終了します", "\nEnd of input, exiting")
+}
+
+/// 指し手の入力形式を表示する
+pub(crate) fn print_move_formats(notation: notation::Notation, locale: Locale) {
+    match locale {
+        Locale::Japanese => {
+            println!("\n入力形式:");
+            println!("  移動: <from> <to> (例: 1e 1d)");
+            println!("  成り: <from> <to>+ (例: 1e 1d+)");
+            println!("  打つ: drop <駒> <to> (例: drop 金 3c)");
+        }
+        Locale::English => {
+            println!("\nInput:");
+            println!("  move: <from> <to> (e.g. 1e 1d)");
+            println!("  promote: <from> <to>+ (e.g. 1e 1d+)");
+            println!("  drop: drop <piece> <to> (e.g. drop G 3c)");
+        }
+    }
+    let example = match notation {
+        notation::Notation::Japanese => locale.text(
+            "２三銀成・同銀・３四歩打 など（筋は半角数字でもよい）",
+            "e.g. ２三銀成, 同銀, ３四歩打",
+        ),
+        notation::Notation::Western => locale.text(
+            "S-2b+・P*3c・Bx4d=・G3c-2b など（Hodges 表記）",
+            "e.g. S-2b+, P*3c, Bx4d=, G3c-2b (Hodges)",
+        ),
+    };
+    println!(
+        "{}",
+        tr!(locale, "  棋譜の表記: {}", "  notation: {}", example)
+    );
+}
+
+pub(crate) fn move_destination(mv: rules::Move) -> rules::Position {
+    match mv {
+        rules::Move::To(_, to, _) => to,
+        rules::Move::Drop(to, _) => to,
+    }
+}
+
+/// 手順を「▲２二金 △同玉 ...」のように表示する
+pub(crate) fn format_line(
+    notation: notation::Notation,
+    state: &board::GameState,
+    player: board::Player,
+    moves: &[rules::Move],
+) -> String {
+    let (mut state, mut player) = (*state, player);
+    let mut previous = None;
+    let mut line = Vec::with_capacity(moves.len());
+    for &mv in moves {
+        line.push(format!(
+            "{}{}",
+            notation::turn_mark(player),
+            notation.format_move(&state, player, mv, previous)
+        ));
+        state = rules::make_move(&state, mv, player);
+        player = rules::opponent_of(player);
+        previous = Some(mv);
+    }
+    line.join(" ")
+}
+
+pub(crate) fn parse_input(
+    notation: notation::Notation,
+    locale: Locale,
+    input: &str,
+    legal_moves: &[rules::Move],
+    state: &board::GameState,
+    player: board::Player,
+    previous: Option<rules::Move>,
+) -> Result<rules::Move, String> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+
+    if parts.is_empty() {
+        return Err(tr!(locale, "入力が空です", "Empty input"));
+    }
+
+    // 棋譜の表記（２三銀成・同銀、S-2b+ など）は空白で区切らない1語か、全角文字を含む
+    if parts[0] != "drop" && (parts.len() == 1 || !input.is_ascii()) {
+        return notation
+            .parse_move(input, state, player, previous)
+            .map_err(|e| notation_error(&e, locale));
+    }
+
+    let mv = if parts[0] == "drop" {
+        if parts.len() != 3 {
+            return Err(tr!(
+                locale,
+                "drop コマンドの形式: drop <駒> <位置>",
+                "Usage: drop <piece> <square>"
+            ));
+        }
+
+        let piece_type = parse_piece_type(parts[1], locale)?;
+        let to = parse_position(parts[2], locale)?;
+
+        // 持ち駒にあるかチェック
+        if state.get_hand(player).get(piece_type) == 0 {
+            let name = locale.piece_name(piece_type, false);
+            return Err(tr!(locale, "{}は持ち駒にありません", "No {} in hand", name));
+        }
+
+        rules::Move::Drop(to, piece_type)
+    } else {
+        if parts.len() != 2 {
+            return Err(tr!(
+                locale,
+                "移動の形式: <from> <to> または <from> <to>+",
+                "Usage: <from> <to> or <from> <to>+"
+            ));
+        }
+
+        let from = parse_position(parts[0], locale)?;
+        let promote = parts[1].ends_with('+');
+        let to_str = if promote {
+            &parts[1][..parts[1].len() - 1]
+        } else {
+            parts[1]
+        };
+        let to = parse_position(to_str, locale)?;
+
+        rules::Move::To(from, to, promote)
+    };
+
+    if !legal_moves.contains(&mv) {
+        return Err(tr!(
+            locale,
+            "その手は合法手ではありません",
+            "That move is not legal"
+        ));
+    }
+
+    Ok(mv)
+}
+
+/// 指し手の表記のエラーを表示言語の文にする
+fn notation_error(e: &notation::ParseError, locale: Locale) -> String {
+    use notation::ParseError;
+    match (locale, e) {
+        (Locale::Japanese, _) => e.to_string(),
+        (Locale::English, ParseError::Unreadable(input)) => {
+            format!("Cannot read the move: {}", input)
+        }
+        (Locale::English, ParseError::Illegal(input)) => format!("Not a legal move: {}", input),
+        (Locale::English, ParseError::Ambiguous(input, notation::Notation::Japanese)) => {
+            format!(
+                "Ambiguous move (add 右, 左, 上, 引, 寄, 直 or 打): {}",
+                input
+            )
+        }
+        (Locale::English, ParseError::Ambiguous(input, notation::Notation::Western)) => format!(
+            "Ambiguous move (add the origin square, e.g. G3c-2b): {}",
+            input
+        ),
+        (Locale::English, ParseError::NoPrevious) => "No previous move for 同".to_string(),
+    }
+}
+
+pub(crate) fn parse_position(s: &str, locale: Locale) -> Result<rules::Position, String> {
+    if s.len() != 2 {
+        return Err(tr!(
+            locale,
+            "位置の形式が不正です: {}",
+            "Invalid square: {}",
+            s
+        ));
+    }
+
+    let chars: Vec<char> = s.chars().collect();
+    let x_char = chars[0];
+    let y_char = chars[1];
+
+    let x = match x_char {
+        '1' => 4,
+        '2' => 3,
+        '3' => 2,
+        '4' => 1,
+        '5' => 0,
+        _ => {
+            return Err(tr!(
+                locale,
+                "x座標が不正です: {}",
+                "Invalid file: {}",
+                x_char
+            ));
+        }
+    };
+
+    let y = match y_char {
+        'a' => 0,
+        'b' => 1,
+        'c' => 2,
+        'd' => 3,
+        'e' => 4,
+        _ => {
+            return Err(tr!(
+                locale,
+                "y座標が不正です: {}",
+                "Invalid rank: {}",
+                y_char
+            ));
+        }
+    };
+
+    Ok(rules::Position::new(x, y))
+}
+
+/// 駒打ちの駒（漢字でも英字でもよい）
+fn parse_piece_type(s: &str, locale: Locale) -> Result<board::PieceType, String> {
+    match s {
+        "王" | "玉" | "K" => Ok(board::PieceType::King),
+        "金" | "G" => Ok(board::PieceType::Gold),
+        "銀" | "S" => Ok(board::PieceType::Silver),
+        "角" | "B" => Ok(board::PieceType::Bishop),
+        "飛" | "R" => Ok(board::PieceType::Rook),
+        "歩" | "P" => Ok(board::PieceType::Pawn),
+        _ => Err(tr!(locale, "不明な駒: {}", "Unknown piece: {}", s)),
+    }
+}
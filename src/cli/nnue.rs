@@ -0,0 +1,121 @@
+use crate::locale::Locale;
+use crate::{nnue, search, tr, tune};
+use std::fs;
+
+// NNUE の学習
+
+/// NNUE の学習でエポック数を省略した場合の値
+const NNUE_DEFAULT_EPOCHS: usize = 20;
+/// NNUE の学習のミニバッチの大きさ
+const NNUE_BATCH_SIZE: usize = 64;
+/// NNUE の学習用の自己対局で、序盤にランダムに指す手数
+const NNUE_RANDOM_PLIES: usize = 4;
+/// NNUE の学習用の自己対局の最大手数
+const NNUE_MAX_PLIES: usize = 200;
+
+const USAGE: &str = "\
+使い方:
+  nnue selfplay <局数> <出力ファイル> [エポック数]
+  nnue records <棋譜ファイル> <出力ファイル> [エポック数]";
+
+const USAGE_EN: &str = "\
+Usage:
+  nnue selfplay <games> <output file> [epochs]
+  nnue records <record file> <output file> [epochs]";
+
+/// NNUE の学習:
+///   `nnue selfplay <局数> <出力ファイル> [エポック数]`（αβ探索同士の自己対局）
+///   `nnue records <棋譜ファイル> <出力ファイル> [エポック数]`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let usage = || locale.text(USAGE, USAGE_EN).to_string();
+    let (kind, source, path) = match args {
+        [kind, source, path] | [kind, source, path, _] => (kind, source, path),
+        _ => return Err(usage()),
+    };
+    let epochs = match args.get(3) {
+        None => NNUE_DEFAULT_EPOCHS,
+        Some(s) => s.parse::<usize>().map_err(|_| {
+            tr!(
+                locale,
+                "エポック数が不正です: {}",
+                "Invalid number of epochs: {}",
+                s
+            )
+        })?,
+    };
+
+    let positions = match kind.as_str() {
+        "selfplay" => {
+            let games = source.parse::<usize>().map_err(|_| {
+                tr!(
+                    locale,
+                    "局数が不正です: {}",
+                    "Invalid number of games: {}",
+                    source
+                )
+            })?;
+            println!(
+                "{}",
+                locale.text("自己対局中...", "Playing self-play games...")
+            );
+            let mut rng = search::Rng::new();
+            let mut positions = Vec::new();
+            for _ in 0..games {
+                let (moves, result) = tune::selfplay_game(
+                    &mut rng,
+                    NNUE_RANDOM_PLIES,
+                    NNUE_MAX_PLIES,
+                    search::best_move_alpha_beta,
+                );
+                positions.extend(tune::positions_from_game(&moves, result));
+            }
+            positions
+        }
+        "records" => {
+            let text = fs::read_to_string(source).map_err(|e| {
+                tr!(
+                    locale,
+                    "棋譜ファイルを読み込めません: {}: {}",
+                    "Cannot read the record file: {}: {}",
+                    source,
+                    e
+                )
+            })?;
+            tune::positions_from_records(&text)
+                .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?
+        }
+        _ => return Err(usage()),
+    };
+    if positions.is_empty() {
+        return Err(tr!(
+            locale,
+            "結果付きの局面がありません",
+            "No positions with a result"
+        ));
+    }
+
+    let samples: Vec<nnue::TrainingSample> = positions
+        .iter()
+        .map(|(state, player, result)| nnue::TrainingSample::new(state, *player, *result))
+        .collect();
+    let n = samples.len();
+    println!(
+        "{}",
+        tr!(locale, "{}局面で学習します", "Training on {} positions", n)
+    );
+    let mut trainer = nnue::Trainer::new(search::Rng::new().next_u64());
+    for i in 0..epochs {
+        let loss = trainer.epoch(&samples, NNUE_BATCH_SIZE);
+        println!(
+            "{}",
+            tr!(locale, "{:>4}: 損失 {:.6}", "{:>4}: loss {:.6}", i, loss)
+        );
+    }
+
+    trainer
+        .to_network()
+        .save(path)
+        .map_err(|e| tr!(locale, "保存に失敗しました: {}", "Cannot save: {}", e))?;
+    println!("{}", tr!(locale, "{} に保存しました", "Saved to {}", path));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::cli::invalid_sfen;
+use crate::locale::Locale;
+use crate::{bitboard, perft, sfen, tr};
+use std::time::Instant;
+
+// 手生成の検証と速度比較（perft・divide・bench）
+
+/// 手生成の検証: `perft <深さ> [SFEN]` / `divide <深さ> [SFEN]`
+pub fn run(divide: bool, args: &[String], locale: Locale) -> Result<(), String> {
+    let Some(Ok(depth)) = args.first().map(|s| s.parse::<u32>()) else {
+        return Err(tr!(
+            locale,
+            "使い方: perft|divide <深さ> [SFEN]",
+            "Usage: perft|divide <depth> [SFEN]"
+        ));
+    };
+    let position = args.get(1).map_or(sfen::STARTPOS, String::as_str);
+    let (state, player) = sfen::parse(position).map_err(|e| invalid_sfen(position, &e, locale))?;
+
+    let start = Instant::now();
+    let nodes = if divide {
+        let mut total = 0;
+        for (mv, n) in perft::divide(&state, player, depth) {
+            println!("{}: {}", sfen::format_move(mv), n);
+            total += n;
+        }
+        total
+    } else {
+        perft::perft(&state, player, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("nodes: {}", nodes);
+    println!(
+        "time: {:.3}s ({:.0} nps)",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
+
+/// 配列版とビットボード版の手生成の速度比較: `bench [深さ] [SFEN]`
+pub fn bench(args: &[String], locale: Locale) -> Result<(), String> {
+    let depth = match args.first() {
+        None => 5,
+        Some(s) => s.parse::<u32>().map_err(|_| {
+            tr!(
+                locale,
+                "使い方: bench [深さ] [SFEN]",
+                "Usage: bench [depth] [SFEN]"
+            )
+        })?,
+    };
+    let position = args.get(1).map_or(sfen::STARTPOS, String::as_str);
+    let (state, player) = sfen::parse(position).map_err(|e| invalid_sfen(position, &e, locale))?;
+
+    let start = Instant::now();
+    let nodes = perft::perft(&state, player, depth);
+    let board_time = start.elapsed();
+
+    let start = Instant::now();
+    let bit_nodes = bitboard::BitPosition::from_state(&state).perft(player, depth);
+    let bit_time = start.elapsed();
+
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "perft {} ({}局面)",
+            "perft {} ({} positions)",
+            depth,
+            nodes
+        )
+    );
+    for (name, n, t) in [
+        (locale.text("配列", "array"), nodes, board_time),
+        (locale.text("ビットボード", "bitboard"), bit_nodes, bit_time),
+    ] {
+        println!(
+            "  {}: {:.3}s ({:.0} nps)",
+            name,
+            t.as_secs_f64(),
+            n as f64 / t.as_secs_f64().max(1e-9)
+        );
+    }
+    if nodes != bit_nodes {
+        return Err(tr!(
+            locale,
+            "局面数が一致しません: {} != {}",
+            "Node counts differ: {} != {}",
+            nodes,
+            bit_nodes
+        ));
+    }
+    Ok(())
+}
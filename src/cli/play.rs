@@ -0,0 +1,734 @@
+use crate::cli::input::{
+    end_of_input, move_destination, parse_input, parse_position, print_move_formats, read_input,
+};
+use crate::cli::{Options, Side, engine_name, has_extension};
+use crate::locale::Locale;
+use crate::{board, csa, engine, eval, kif, kifu, notation, rules, search, selfplay, tr, ui};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+// 対局
+//
+// 対局者を選び、人間の入力と AI の思考を交互に進めて、終わったら棋譜を保存する。
+
+/// AI 同士の対局でこの手数に達したら引き分け
+const SPECTATOR_MAX_PLIES: usize = 300;
+/// 観戦時の1手ごとの待ち時間の既定値（ミリ秒）
+const SPECTATOR_DEFAULT_DELAY_MS: u64 = 1000;
+
+/// 対局の設定
+struct GameSetup {
+    /// 先手・後手の対局者
+    sides: [Side; 2],
+    /// AI 同士の対局で1手ごとに待つ時間
+    delay: Duration,
+    /// 表示言語
+    locale: Locale,
+}
+
+impl GameSetup {
+    fn side(&self, player: board::Player) -> Side {
+        match player {
+            board::Player::Sente => self.sides[0],
+            board::Player::Gote => self.sides[1],
+        }
+    }
+
+    /// 「先手（あなた）」のような表示用の名前
+    fn label(&self, player: board::Player) -> String {
+        let name = self.locale.player_name(player);
+        match (self.sides, self.side(player)) {
+            ([Side::Human, Side::Human], _) => name.to_string(),
+            (_, Side::Human) => tr!(self.locale, "{}（あなた）", "{} (you)", name),
+            ([Side::Ai { .. }, Side::Ai { .. }], Side::Ai { engine, .. }) => {
+                tr!(
+                    self.locale,
+                    "{}（{}）",
+                    "{} ({})",
+                    name,
+                    engine_name(engine)
+                )
+            }
+            (_, Side::Ai { .. }) => tr!(self.locale, "{}（AI）", "{} (AI)", name),
+        }
+    }
+}
+
+/// 対局者を決めて1局指し、`--record` があれば棋譜を保存する
+pub(crate) fn run(
+    cli_options: &Options,
+    notation: notation::Notation,
+    locale: Locale,
+    start: &kifu::Kifu,
+    options: &search::SearchOptions,
+    evaluator: &mut dyn eval::Evaluator,
+) -> Result<(), String> {
+    // 対局を終えてから保存できないと分からないよう、先に書き込めるか確かめる
+    if let Some(path) = &cli_options.record {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| {
+                tr!(
+                    locale,
+                    "棋譜を保存できません: {}: {}",
+                    "Cannot save the game: {}: {}",
+                    path,
+                    e
+                )
+            })?;
+    }
+
+    println!("=== 5×5 Mini Shogi Start ===\n");
+
+    let setup = match cli_options.fixed_sides() {
+        Some(sides) => GameSetup {
+            sides,
+            delay: Duration::from_millis(
+                cli_options.delay_ms.unwrap_or(SPECTATOR_DEFAULT_DELAY_MS),
+            ),
+            locale,
+        },
+        None => {
+            let Some(setup) = select_setup(cli_options.delay_ms, locale) else {
+                println!("{}", end_of_input(locale));
+                return Ok(());
+            };
+            println!();
+            setup
+        }
+    };
+    let record = play_game(&setup, notation, start, options, evaluator);
+
+    if let Some(path) = &cli_options.record {
+        save_record(path, start, &record).map_err(|e| {
+            tr!(
+                locale,
+                "棋譜を保存できません: {}: {}",
+                "Cannot save the game: {}: {}",
+                path,
+                e
+            )
+        })?;
+        let message = tr!(
+            locale,
+            "棋譜を {} に保存しました",
+            "Saved the game to {}",
+            path
+        );
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+/// 対局の進行（人間の入力と AI の思考を交互に行う）。終わったら棋譜を返す
+fn play_game(
+    setup: &GameSetup,
+    notation: notation::Notation,
+    start: &kifu::Kifu,
+    options: &search::SearchOptions,
+    evaluator: &mut dyn eval::Evaluator,
+) -> selfplay::GameRecord {
+    let locale = setup.locale;
+    let humans = setup
+        .sides
+        .iter()
+        .filter(|s| matches!(s, Side::Human))
+        .count();
+    // 人間が後手だけを持つときは後手から見た盤面にする
+    let mut perspective = match setup.sides {
+        [Side::Ai { .. }, Side::Human] => board::Player::Gote,
+        _ => board::Player::Sente,
+    };
+    let mut record = selfplay::GameRecord {
+        engines: setup.sides.map(|side| match side {
+            Side::Human => "human".to_string(),
+            Side::Ai { engine, .. } => engine.name().to_string(),
+        }),
+        plies: Vec::new(),
+        result: 0.5,
+    };
+
+    // 読み込んだ棋譜の指し手は記録に含め、その続きから指す
+    for (&mv, &(state, player)) in start.moves.iter().zip(&start.positions()) {
+        record.plies.push(selfplay::PlyRecord {
+            state,
+            player,
+            mv,
+            score: None,
+            visits: Vec::new(),
+        });
+    }
+    let (mut state, mut current_player) = start.position();
+    let mut last_move_to = start.moves.last().map(|&mv| move_destination(mv));
+
+    // 先手から見た結果（勝ち 1、引き分け 0.5、負け 0）
+    let sente_result = |winner: board::Player| match winner {
+        board::Player::Sente => 1.0,
+        board::Player::Gote => 0.0,
+    };
+
+    'game: loop {
+        ui::print_game_state(&state, perspective, last_move_to, locale);
+
+        if let Some(winner) = kifu::winner(&state) {
+            let message = match winner {
+                p if humans == 1 && matches!(setup.side(p), Side::Human) => {
+                    tr!(locale, "あなたの勝ち！", "You win!")
+                }
+                _ if humans == 1 => tr!(locale, "AIの勝ち！", "The AI wins!"),
+                p => tr!(locale, "{}の勝ち！", "{} wins!", locale.player_name(p)),
+            };
+            println!("{}", message);
+            record.result = sente_result(winner);
+            break;
+        }
+        if humans == 0 && record.plies.len() >= SPECTATOR_MAX_PLIES {
+            let message = tr!(
+                locale,
+                "{}手に達したので引き分けです",
+                "Draw after reaching {} moves",
+                SPECTATOR_MAX_PLIES
+            );
+            println!("{}", message);
+            break;
+        }
+
+        let label = setup.label(current_player);
+        let opponent = rules::opponent_of(current_player);
+        let previous = record.plies.last().map(|p| p.mv);
+        let opponent_label = setup.label(opponent);
+        let no_legal_moves = tr!(
+            locale,
+            "{}に合法手がありません。{}の勝ち！",
+            "{} has no legal moves. {} wins!",
+            label,
+            opponent_label
+        );
+
+        let (mv, result) = match setup.side(current_player) {
+            Side::Ai { engine, strength } => {
+                let thinking = tr!(
+                    locale,
+                    "{}の番です。思考中...",
+                    "{} to move. Thinking...",
+                    label
+                );
+                println!("{}", thinking);
+                let builtin = engine::Builtin { engine, strength };
+                let result = builtin.search(&state, current_player, options, evaluator);
+                let Some(mv) = result.best_move else {
+                    println!("{}", no_legal_moves);
+                    record.result = sente_result(opponent);
+                    break;
+                };
+                let text = notation.format_move(&state, current_player, mv, previous);
+                println!("{}", tr!(locale, "{}の手: {}", "{} plays {}", label, text));
+                if humans == 0 {
+                    thread::sleep(setup.delay);
+                }
+                (mv, result)
+            }
+            Side::Human => {
+                let prompt = tr!(
+                    locale,
+                    "{}の番です（help でコマンド一覧）",
+                    "{} to move (type help for commands)",
+                    label
+                );
+                println!("{}", prompt);
+                let legal_moves = rules::generate_legal_moves(&state, current_player);
+                if legal_moves.is_empty() {
+                    println!("{}", no_legal_moves);
+                    record.result = sente_result(opponent);
+                    break;
+                }
+                let mv = loop {
+                    print!("> ");
+                    io::stdout().flush().unwrap();
+                    // 入力が終わったときは quit と同じく投了として終える
+                    let command = match read_input() {
+                        Some(input) => parse_command(
+                            notation,
+                            locale,
+                            input.trim(),
+                            &legal_moves,
+                            &state,
+                            current_player,
+                            previous,
+                        ),
+                        None => {
+                            println!();
+                            Ok(Input::Quit)
+                        }
+                    };
+                    match command {
+                        Ok(Input::Move(mv)) => break mv,
+                        Ok(Input::Quit) => {
+                            // 途中で終えたときは投了として記録する
+                            println!("{}", locale.text("ゲームを終了します", "Game over"));
+                            record.result = sente_result(opponent);
+                            break 'game;
+                        }
+                        Ok(Input::Undo) => match takeback(setup, &record.plies) {
+                            Some(n) => {
+                                let first = record.plies.len() - n;
+                                state = record.plies[first].state;
+                                current_player = record.plies[first].player;
+                                record.plies.truncate(first);
+                                last_move_to = record.plies.last().map(|p| move_destination(p.mv));
+                                let message =
+                                    tr!(locale, "{}手戻しました", "Took back {} move(s)", n);
+                                println!("{}", message);
+                                continue 'game;
+                            }
+                            None => println!(
+                                "{}",
+                                locale.text("戻せる手がありません", "No moves to take back")
+                            ),
+                        },
+                        Ok(Input::Hint) => {
+                            // 相手が AI ならその設定で、そうでなければ既定の Alpha-Beta で探す
+                            let builtin = match setup.side(opponent) {
+                                Side::Ai { engine, strength } => {
+                                    engine::Builtin { engine, strength }
+                                }
+                                Side::Human => engine::Builtin {
+                                    engine: engine::Engine::AlphaBeta,
+                                    strength: None,
+                                },
+                            };
+                            println!("{}", locale.text("思考中...", "Thinking..."));
+                            let result = builtin.search(&state, current_player, options, evaluator);
+                            if let Some(mv) = result.best_move {
+                                let text =
+                                    notation.format_move(&state, current_player, mv, previous);
+                                let hint = match result.score {
+                                    Some(score) => tr!(
+                                        locale,
+                                        "ヒント: {}（評価値 {:+}）",
+                                        "Hint: {} (score {:+})",
+                                        text,
+                                        side_score(score, current_player)
+                                    ),
+                                    None => {
+                                        tr!(locale, "ヒント: {}（定跡）", "Hint: {} (book)", text)
+                                    }
+                                };
+                                println!("{}", hint);
+                            }
+                        }
+                        Ok(Input::Moves(square)) => print_moves(
+                            notation,
+                            locale,
+                            &state,
+                            current_player,
+                            &legal_moves,
+                            square,
+                        ),
+                        Ok(Input::Eval) => {
+                            let score = evaluator.evaluate(&state, current_player);
+                            let message = tr!(
+                                locale,
+                                "評価値: {:+}（{}から見た値）",
+                                "Score: {:+} (from {}'s view)",
+                                side_score(score, current_player),
+                                locale.player_name(current_player)
+                            );
+                            println!("{}", message);
+                        }
+                        Ok(Input::Flip) => {
+                            perspective = rules::opponent_of(perspective);
+                            ui::print_game_state(&state, perspective, last_move_to, locale);
+                        }
+                        Ok(Input::Help) => print_commands(notation, locale),
+                        Err(e) => println!("{}", tr!(locale, "エラー: {}", "Error: {}", e)),
+                    }
+                };
+                (mv, search::SearchResult::default())
+            }
+        };
+
+        record.plies.push(selfplay::PlyRecord {
+            state,
+            player: current_player,
+            mv,
+            score: result.score,
+            visits: result.visits,
+        });
+        last_move_to = Some(move_destination(mv));
+        state = rules::make_move(&state, mv, current_player);
+        current_player = opponent;
+    }
+
+    record
+}
+
+/// 人間の入力
+enum Input {
+    Move(rules::Move),
+    /// 自分の直前の手（と AI の応手）を戻す
+    Undo,
+    /// エンジンに候補手を聞く
+    Hint,
+    /// 合法手の一覧（マスを指定すればそのマスから動く手と、そのマスに打つ手）
+    Moves(Option<rules::Position>),
+    Eval,
+    /// 盤面の向きを反転する
+    Flip,
+    Help,
+    Quit,
+}
+
+fn print_commands(notation: notation::Notation, locale: Locale) {
+    print_move_formats(notation, locale);
+    match locale {
+        Locale::Japanese => {
+            println!("コマンド:");
+            println!("  undo           自分の直前の手と相手の応手を戻す");
+            println!("  hint           エンジンの候補手と評価値を表示する");
+            println!("  moves [<位置>] 合法手の一覧（位置を指定するとその駒の手とそこへ打つ手）");
+            println!("  eval           現局面の評価値を表示する");
+            println!("  flip           盤面の向きを反転する");
+            println!("  help           この一覧を表示する");
+            println!("  quit           終了する（投了）");
+        }
+        Locale::English => {
+            println!("Commands:");
+            println!("  undo           take back your last move and the reply");
+            println!("  hint           show the engine's suggestion and score");
+            println!(
+                "  moves [<sq>]   list legal moves (with a square: moves from and drops to it)"
+            );
+            println!("  eval           show the evaluation of the position");
+            println!("  flip           flip the board");
+            println!("  help           show this list");
+            println!("  quit           quit (resign)");
+        }
+    }
+}
+
+/// 手番側から見た評価値に直す
+fn side_score(sente_score: i32, player: board::Player) -> i32 {
+    match player {
+        board::Player::Sente => sente_score,
+        board::Player::Gote => -sente_score,
+    }
+}
+
+/// `undo` で戻す手数（戻した後の手番が人間になるまで戻す。戻せなければ `None`）
+fn takeback(setup: &GameSetup, plies: &[selfplay::PlyRecord]) -> Option<usize> {
+    let mut n = 1;
+    while n <= plies.len() {
+        if matches!(setup.side(plies[plies.len() - n].player), Side::Human) {
+            return Some(n);
+        }
+        n += 1;
+    }
+    None
+}
+
+fn print_moves(
+    notation: notation::Notation,
+    locale: Locale,
+    state: &board::GameState,
+    player: board::Player,
+    legal_moves: &[rules::Move],
+    square: Option<rules::Position>,
+) {
+    let moves: Vec<String> = legal_moves
+        .iter()
+        .filter(|&&mv| match (square, mv) {
+            (None, _) => true,
+            (Some(sq), rules::Move::To(from, _, _)) => from == sq,
+            (Some(sq), rules::Move::Drop(to, _)) => to == sq,
+        })
+        .map(|&mv| notation.format_move(state, player, mv, None))
+        .collect();
+    let message = if moves.is_empty() {
+        tr!(locale, "合法手がありません", "No legal moves")
+    } else {
+        let (n, list) = (moves.len(), moves.join(", "));
+        tr!(
+            locale,
+            "合法手（{}手）: {}",
+            "Legal moves ({}): {}",
+            n,
+            list
+        )
+    };
+    println!("{}", message);
+}
+
+/// 指し手かコマンドを読む
+fn parse_command(
+    notation: notation::Notation,
+    locale: Locale,
+    input: &str,
+    legal_moves: &[rules::Move],
+    state: &board::GameState,
+    player: board::Player,
+    previous: Option<rules::Move>,
+) -> Result<Input, String> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    match parts[..] {
+        ["quit"] => Ok(Input::Quit),
+        ["undo"] => Ok(Input::Undo),
+        ["hint"] => Ok(Input::Hint),
+        ["moves"] => Ok(Input::Moves(None)),
+        ["moves", square] => Ok(Input::Moves(Some(parse_position(square, locale)?))),
+        ["eval"] => Ok(Input::Eval),
+        ["flip"] => Ok(Input::Flip),
+        ["help"] => Ok(Input::Help),
+        _ => parse_input(
+            notation,
+            locale,
+            input,
+            legal_moves,
+            state,
+            player,
+            previous,
+        )
+        .map(Input::Move),
+    }
+}
+
+/// 対局の種類と対局者を選ぶ。途中で入力が終わったときは None
+fn select_setup(delay_ms: Option<u64>, locale: Locale) -> Option<GameSetup> {
+    let mode = loop {
+        match locale {
+            Locale::Japanese => {
+                println!("対局の種類を選んでください:");
+                println!("  1: 人間 vs AI");
+                println!("  2: 人間 vs 人間");
+                println!("  3: AI vs AI（観戦）");
+            }
+            Locale::English => {
+                println!("Choose a game type:");
+                println!("  1: Human vs AI");
+                println!("  2: Human vs Human");
+                println!("  3: AI vs AI (watch)");
+            }
+        }
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim() {
+            mode @ ("1" | "2" | "3") => break mode.to_string(),
+            _ => println!(
+                "{}",
+                locale.text(
+                    "1、2、3 のいずれかを入力してください\n",
+                    "Enter 1, 2 or 3\n"
+                )
+            ),
+        }
+    };
+    println!();
+
+    let setup = match mode.as_str() {
+        "1" => {
+            let human_player = select_player(locale)?;
+            let ai = select_ai(None, locale)?;
+            let sides = match human_player {
+                board::Player::Sente => [Side::Human, ai],
+                board::Player::Gote => [ai, Side::Human],
+            };
+            GameSetup {
+                sides,
+                delay: Duration::ZERO,
+                locale,
+            }
+        }
+        "2" => GameSetup {
+            sides: [Side::Human, Side::Human],
+            delay: Duration::ZERO,
+            locale,
+        },
+        _ => {
+            let sente = select_ai(Some(board::Player::Sente), locale)?;
+            let gote = select_ai(Some(board::Player::Gote), locale)?;
+            let delay = match delay_ms {
+                Some(ms) => Duration::from_millis(ms),
+                None => select_delay(locale)?,
+            };
+            GameSetup {
+                sides: [sente, gote],
+                delay,
+                locale,
+            }
+        }
+    };
+    Some(setup)
+}
+
+fn select_player(locale: Locale) -> Option<board::Player> {
+    loop {
+        match locale {
+            Locale::Japanese => {
+                println!("あなたの手番を選んでください:");
+                println!("  s: 先手（先攻）");
+                println!("  g: 後手（後攻）");
+            }
+            Locale::English => {
+                println!("Choose your side:");
+                println!("  s: Sente (moves first)");
+                println!("  g: Gote (moves second)");
+            }
+        }
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim() {
+            "s" => return Some(board::Player::Sente),
+            "g" => return Some(board::Player::Gote),
+            _ => println!(
+                "{}",
+                locale.text("s または g を入力してください", "Enter s or g")
+            ),
+        }
+    }
+}
+
+/// AI の対局者をアルゴリズムと強さを選んで作る（`player` は見出しに付ける手番。`None` なら付けない）
+fn select_ai(player: Option<board::Player>, locale: Locale) -> Option<Side> {
+    let engine = select_algorithm(player, locale)?;
+    Some(Side::Ai {
+        engine,
+        strength: select_strength(engine, locale)?,
+    })
+}
+
+/// AI のアルゴリズムを選ぶ（`player` は見出しに付ける手番。`None` なら付けない）
+fn select_algorithm(player: Option<board::Player>, locale: Locale) -> Option<engine::Engine> {
+    loop {
+        let heading = match player {
+            None => tr!(
+                locale,
+                "\nAIアルゴリズムを選んでください:",
+                "\nChoose the AI algorithm:"
+            ),
+            Some(p) => tr!(
+                locale,
+                "\n{}のAIアルゴリズムを選んでください:",
+                "\nChoose the AI algorithm for {}:",
+                locale.player_name(p)
+            ),
+        };
+        println!("{}", heading);
+        match locale {
+            Locale::Japanese => {
+                println!("  1: Alpha-Beta探索（評価関数ベース）");
+                println!("  2: MCTS（モンテカルロ木探索）");
+            }
+            Locale::English => {
+                println!("  1: Alpha-Beta search (evaluation function)");
+                println!("  2: MCTS (Monte Carlo tree search)");
+            }
+        }
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim() {
+            "1" => return Some(engine::Engine::AlphaBeta),
+            "2" => return Some(engine::Engine::Mcts),
+            _ => println!(
+                "{}",
+                locale.text("1 または 2 を入力してください", "Enter 1 or 2")
+            ),
+        }
+    }
+}
+
+/// AI の強さ（Alpha-Beta は探索の深さ、MCTS は反復回数）を選ぶ。空欄なら既定値の `Some(None)`
+fn select_strength(engine: engine::Engine, locale: Locale) -> Option<Option<u32>> {
+    let unit = match engine {
+        engine::Engine::AlphaBeta => locale.text("探索の深さ", "Search depth"),
+        engine::Engine::Mcts => locale.text("反復回数", "Iterations"),
+    };
+    loop {
+        print!(
+            "{}",
+            tr!(
+                locale,
+                "{}（空欄で既定値）> ",
+                "{} (blank for default)> ",
+                unit
+            )
+        );
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim() {
+            "" => return Some(None),
+            s => match s.parse::<u32>() {
+                Ok(n) if n > 0 => return Some(Some(n)),
+                _ => println!(
+                    "{}",
+                    locale.text(
+                        "1 以上の数を入力してください",
+                        "Enter a number of at least 1"
+                    )
+                ),
+            },
+        }
+    }
+}
+
+/// 観戦時に1手ごとに待つ時間を選ぶ
+fn select_delay(locale: Locale) -> Option<Duration> {
+    loop {
+        print!(
+            "{}",
+            tr!(
+                locale,
+                "\n1手ごとの待ち時間（ミリ秒、空欄で {}）> ",
+                "\nDelay per move (ms, blank for {})> ",
+                SPECTATOR_DEFAULT_DELAY_MS
+            )
+        );
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim() {
+            "" => return Some(Duration::from_millis(SPECTATOR_DEFAULT_DELAY_MS)),
+            s => match s.parse::<u64>() {
+                Ok(ms) => return Some(Duration::from_millis(ms)),
+                Err(_) => println!("{}", locale.text("数を入力してください", "Enter a number")),
+            },
+        }
+    }
+}
+
+/// 対局の記録を保存する（`.kif`・`.kifu` は KIF 形式、`.csa` は CSA 形式、
+/// それ以外は学習用の自己対局データの形式）
+fn save_record(path: &str, start: &kifu::Kifu, record: &selfplay::GameRecord) -> io::Result<()> {
+    let kif = has_extension(path, "kif") || has_extension(path, "kifu");
+    if !kif && !has_extension(path, "csa") {
+        return selfplay::save(path, std::slice::from_ref(record));
+    }
+
+    let mut game = kifu::Kifu::new(start.start, start.start_player);
+    game.players = record.engines.clone();
+    game.moves = record.moves();
+    let (state, _) = game.position();
+    // 王を取って終わった対局は指し手から結果が分かる
+    game.ending = if kifu::is_finished(&state) {
+        None
+    } else if record.result == 0.5 {
+        Some(kifu::Ending::Draw)
+    } else {
+        Some(kifu::Ending::Resign)
+    };
+    if kif {
+        kif::save(path, &game)
+    } else {
+        csa::save(path, &game)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::locale::Locale;
+use crate::{engine, search, selfplay, tr};
+use std::time::Duration;
+
+// 学習用の自己対局
+
+const USAGE: &str = "\
+使い方: selfplay <局数> <出力ファイル> [--sente ab[:深さ]|mcts[:反復回数]] \
+[--gote ab[:深さ]|mcts[:反復回数]] [--time <ミリ秒>] [--sente-time <ミリ秒>] \
+[--gote-time <ミリ秒>] [--random <手数>] [--max-plies <手数>] [--seed <値>]";
+
+const USAGE_EN: &str = "\
+Usage: selfplay <games> <output file> [--sente ab[:depth]|mcts[:iterations]] \
+[--gote ab[:depth]|mcts[:iterations]] [--time <ms>] [--sente-time <ms>] \
+[--gote-time <ms>] [--random <plies>] [--max-plies <plies>] [--seed <value>]";
+
+/// 学習用の自己対局:
+///   `selfplay <局数> <出力ファイル> [--sente <エンジン>] [--gote <エンジン>] [--random <手数>] [--max-plies <手数>] [--seed <値>]`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let usage = || locale.text(USAGE, USAGE_EN).to_string();
+    let [games, path, flags @ ..] = args else {
+        return Err(usage());
+    };
+    let games = games.parse::<usize>().map_err(|_| {
+        tr!(
+            locale,
+            "局数が不正です: {}",
+            "Invalid number of games: {}",
+            games
+        )
+    })?;
+
+    let mut config = selfplay::SelfplayConfig::default();
+    let mut rng = search::Rng::new();
+    let mut rest = flags.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .ok_or_else(|| tr!(locale, "{} には値が必要です", "{} needs a value", flag))?;
+        let parsed = match flag.as_str() {
+            "--sente" => engine::Builtin::parse(value).map(|e| config.engines[0] = e),
+            "--gote" => engine::Builtin::parse(value).map(|e| config.engines[1] = e),
+            "--time" | "--sente-time" | "--gote-time" => value
+                .parse()
+                .map(|ms| {
+                    let limit = Some(Duration::from_millis(ms));
+                    match flag.as_str() {
+                        "--sente-time" => config.time_limits[0] = limit,
+                        "--gote-time" => config.time_limits[1] = limit,
+                        _ => config.time_limits = [limit; 2],
+                    }
+                })
+                .map_err(|_| tr!(locale, "時間が不正です: {}", "Invalid time: {}", value)),
+            "--random" => value.parse().map(|n| config.random_plies = n).map_err(|_| {
+                tr!(
+                    locale,
+                    "手数が不正です: {}",
+                    "Invalid number of plies: {}",
+                    value
+                )
+            }),
+            "--max-plies" => value.parse().map(|n| config.max_plies = n).map_err(|_| {
+                tr!(
+                    locale,
+                    "手数が不正です: {}",
+                    "Invalid number of plies: {}",
+                    value
+                )
+            }),
+            "--seed" => value
+                .parse()
+                .map(|n| rng = search::Rng::with_seed(n))
+                .map_err(|_| tr!(locale, "シードが不正です: {}", "Invalid seed: {}", value)),
+            _ => return Err(usage()),
+        };
+        parsed.map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+    }
+
+    let options = search::SearchOptions::default();
+    let mut records = Vec::with_capacity(games);
+    for i in 0..games {
+        let record = selfplay::play_game(&config, &options, &mut rng);
+        let (n, plies) = (i + 1, record.plies.len());
+        let result = record.result_text();
+        println!(
+            "{}",
+            tr!(
+                locale,
+                "{:>4}: {}手 {}",
+                "{:>4}: {} plies {}",
+                n,
+                plies,
+                result
+            )
+        );
+        records.push(record);
+    }
+
+    selfplay::save(path, &records)
+        .map_err(|e| tr!(locale, "保存に失敗しました: {}", "Cannot save: {}", e))?;
+    let n = records.len();
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "{} に保存しました（{}局）",
+            "Saved to {} ({} games)",
+            path,
+            n
+        )
+    );
+    Ok(())
+}
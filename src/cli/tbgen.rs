@@ -0,0 +1,38 @@
+use crate::locale::Locale;
+use crate::{tablebase, tr};
+
+// 終盤データベースの生成
+
+/// 終盤データベース生成: `tbgen <駒> <出力ファイル>`（例: `tbgen GP kkgp.tb`）
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let [material, path] = args else {
+        return Err(tr!(
+            locale,
+            "使い方: tbgen <駒（例: GP）> <出力ファイル>",
+            "Usage: tbgen <pieces (e.g. GP)> <output file>"
+        ));
+    };
+    let material = tablebase::parse_material(material)
+        .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+
+    println!(
+        "{}",
+        locale.text("終盤データベースを生成中...", "Generating the tablebase...")
+    );
+    let tb = tablebase::Tablebase::generate(&material)
+        .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+    tb.save(path)
+        .map_err(|e| tr!(locale, "保存に失敗しました: {}", "Cannot save: {}", e))?;
+    let n = tb.len();
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "{} に保存しました（{}局面）",
+            "Saved to {} ({} positions)",
+            path,
+            n
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::locale::Locale;
+use crate::{engine, tournament, tr};
+use std::fs;
+use std::thread;
+
+// エンジン同士の対局（match・tournament）
+
+const USAGE: &str = "\
+使い方: match <A> <B> [--games N] [--concurrency N] [--openings <ファイル>] \
+[--max-plies N] [--movetime <ミリ秒>] [--sprt <elo0> <elo1>]
+        tournament <エンジン>... [同じオプション（--sprt を除く）]
+エンジン: ab[:深さ] | mcts[:反復回数] | usi:<コマンド>";
+
+const USAGE_EN: &str = "\
+Usage: match <A> <B> [--games N] [--concurrency N] [--openings <file>] \
+[--max-plies N] [--movetime <ms>] [--sprt <elo0> <elo1>]
+       tournament <engine>... [same options (except --sprt)]
+Engines: ab[:depth] | mcts[:iterations] | usi:<command>";
+
+/// エンジン同士の対局:
+///   `match <A> <B> [オプション]` / `tournament <エンジン>... [オプション]`
+///
+/// オプションは `--games N`、`--concurrency N`、`--openings <ファイル>`、`--max-plies N`、`--movetime <ミリ秒>`、
+/// `--sprt <elo0> <elo1>`（`match` のみ）
+pub fn run(round_robin: bool, args: &[String], locale: Locale) -> Result<(), String> {
+    let usage = || locale.text(USAGE, USAGE_EN).to_string();
+    let mut engines = Vec::new();
+    let mut config = tournament::MatchConfig {
+        concurrency: thread::available_parallelism().map_or(1, |n| n.get()),
+        ..tournament::MatchConfig::default()
+    };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with("--") {
+            let spec = engine::EngineSpec::parse(arg)
+                .map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+            engines.push(spec);
+            continue;
+        }
+        let value = rest
+            .next()
+            .ok_or_else(|| tr!(locale, "{} には値が必要です", "{} needs a value", arg))?;
+        let number = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| tr!(locale, "数値が不正です: {}", "Invalid number: {}", value))
+        };
+        let parsed = match arg.as_str() {
+            "--games" => number(value).map(|n| config.games = n),
+            "--concurrency" => number(value).map(|n| config.concurrency = n),
+            "--max-plies" => number(value).map(|n| config.max_plies = n),
+            "--movetime" => number(value).map(|n| config.movetime_ms = n as u64),
+            "--openings" => fs::read_to_string(value)
+                .map_err(|e| format!("{}: {}", value, e))
+                .and_then(|text| tournament::parse_openings(&text))
+                .map(|openings| config.openings = openings),
+            "--sprt" if !round_robin => {
+                let elo1 = rest.next().map(|v| v.parse::<f64>());
+                match (value.parse::<f64>(), elo1) {
+                    (Ok(elo0), Some(Ok(elo1))) => {
+                        config.sprt = Some(tournament::Sprt::new(elo0, elo1));
+                        Ok(())
+                    }
+                    _ => Err(tr!(
+                        locale,
+                        "--sprt には2つの Elo 差が必要です",
+                        "--sprt needs two Elo differences"
+                    )),
+                }
+            }
+            _ => return Err(usage()),
+        };
+        parsed.map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+    }
+
+    if round_robin {
+        if engines.len() < 2 {
+            return Err(usage());
+        }
+        let standings = tournament::run_tournament(&engines, &config, |a, b, report| {
+            let s = report.score;
+            println!(
+                "{} vs {}: +{} ={} -{}",
+                a.name(),
+                b.name(),
+                s.wins,
+                s.draws,
+                s.losses
+            );
+        })
+        .map_err(|e| tr!(locale, "対局に失敗しました: {}", "Game failed: {}", e))?;
+        println!(
+            "\n{}",
+            locale.text(
+                "順位  勝ち点  勝 分 負  エンジン",
+                "Rank  Points   W D L  Engine"
+            )
+        );
+        for (i, standing) in standings.iter().enumerate() {
+            let s = standing.score;
+            println!(
+                "{:>4}  {:>6.1}  {} {} {}  {}",
+                i + 1,
+                f64::from(s.wins) + 0.5 * f64::from(s.draws),
+                s.wins,
+                s.draws,
+                s.losses,
+                standing.name
+            );
+        }
+        return Ok(());
+    }
+
+    let [a, b] = &engines[..] else {
+        return Err(usage());
+    };
+    let (a_name, b_name) = (a.name(), b.name());
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "{} vs {}（{}局）",
+            "{} vs {} ({} games)",
+            a_name,
+            b_name,
+            config.games
+        )
+    );
+    let report = tournament::run_match(a, b, &config, |game, score| {
+        let result = match game.result {
+            tournament::GameResult::Win => locale.text("勝ち", "win"),
+            tournament::GameResult::Draw => locale.text("引き分け", "draw"),
+            tournament::GameResult::Loss => locale.text("負け", "loss"),
+        };
+        let side = if game.a_is_sente {
+            locale.text("先手", "sente")
+        } else {
+            locale.text("後手", "gote")
+        };
+        let (n, plies) = (game.index + 1, game.moves.len());
+        println!(
+            "{}",
+            tr!(
+                locale,
+                "{:>4}: A{} {}手 {}  (+{} ={} -{})",
+                "{:>4}: A as {}, {} moves, {}  (+{} ={} -{})",
+                n,
+                side,
+                plies,
+                result,
+                score.wins,
+                score.draws,
+                score.losses
+            )
+        );
+    })
+    .map_err(|e| tr!(locale, "対局に失敗しました: {}", "Game failed: {}", e))?;
+
+    let s = report.score;
+    let (ratio, elo, elo_error) = (s.ratio() * 100.0, s.elo(), s.elo_error());
+    println!(
+        "\n{}",
+        tr!(
+            locale,
+            "{} 対 {}: +{} ={} -{}  勝率 {:.1}%  Elo {:+.1} ± {:.1}",
+            "{} vs {}: +{} ={} -{}  score {:.1}%  Elo {:+.1} ± {:.1}",
+            a_name,
+            b_name,
+            s.wins,
+            s.draws,
+            s.losses,
+            ratio,
+            elo,
+            elo_error
+        )
+    );
+    if let Some(sprt) = config.sprt {
+        let (lower, upper) = sprt.bounds();
+        let decision = match report.sprt {
+            Some(tournament::SprtDecision::H0) => locale.text("H0 を採択", "H0 accepted"),
+            Some(tournament::SprtDecision::H1) => locale.text("H1 を採択", "H1 accepted"),
+            None => locale.text("結論なし", "inconclusive"),
+        };
+        println!(
+            "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}) {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&s),
+            lower,
+            upper,
+            decision
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,247 @@
+use crate::cli::input::{
+    end_of_input, format_line, move_destination, parse_input, print_move_formats, read_input,
+};
+use crate::cli::invalid_sfen;
+use crate::locale::Locale;
+use crate::{rules, sfen, tr, tsume, ui};
+use std::io::{self, Write};
+
+// 詰将棋モード
+//
+// 収録問題か SFEN で指定した局面を、王手の連続で詰ませるまで対話的に解く。
+
+/// SFEN 指定時に手数を省略した場合の探索手数
+const TSUME_DEFAULT_PLIES: u32 = 7;
+
+/// 詰将棋モード: `tsume [<SFEN> [手数]]`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "=== 5×5 Mini Shogi 詰将棋 ===\n",
+            "=== 5×5 Mini Shogi Tsume ===\n"
+        )
+    );
+
+    let session = match args.first() {
+        Some(sfen) => {
+            let plies = match args.get(1) {
+                None => TSUME_DEFAULT_PLIES,
+                Some(s) => s.parse::<u32>().map_err(|_| {
+                    tr!(
+                        locale,
+                        "手数が不正です: {}",
+                        "Invalid number of moves: {}",
+                        s
+                    )
+                })?,
+            };
+            let (state, attacker) =
+                sfen::parse(sfen).map_err(|e| invalid_sfen(sfen, &e, locale))?;
+            tsume::Session::new(state, attacker, plies)
+        }
+        None => {
+            let Some(index) = select_problem(locale) else {
+                println!("{}", end_of_input(locale));
+                return Ok(());
+            };
+            let problem = tsume::PROBLEMS[index];
+            println!("\n{}", problem_title(index, locale));
+            tsume::Session::from_sfen(problem.sfen, problem.plies)
+        }
+    };
+
+    let mut session = session.map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+
+    let intro = tr!(
+        locale,
+        "{}手詰です。王手の連続で玉を詰ませてください。",
+        "Mate in {}. Checkmate the king with a series of checks.",
+        session.remaining
+    );
+    println!("{}", intro);
+    let attacker = session.attacker;
+    let defender = rules::opponent_of(attacker);
+    let initial = session.state;
+    let mut last_move_to: Option<rules::Position> = None;
+    let mut previous: Option<rules::Move> = None;
+    let notation = locale.default_notation();
+
+    loop {
+        ui::print_game_state(&session.state, attacker, last_move_to, locale);
+        let turn = tr!(
+            locale,
+            "攻め方の番です（残り{}手）",
+            "Attacker to move ({} moves left)",
+            session.remaining
+        );
+        println!("{}", turn);
+
+        let legal_moves = rules::generate_legal_moves(&session.state, attacker);
+
+        let mv = loop {
+            print_move_formats(notation, locale);
+            println!("{}", locale.text("  解答: answer", "  solution: answer"));
+            println!("{}", locale.text("  終了: quit", "  quit: quit"));
+            print!("> ");
+            io::stdout().flush().unwrap();
+
+            // 入力が終わったときは quit として扱う
+            let input = read_input();
+            let input = input.as_deref().map_or("quit", str::trim);
+
+            match input {
+                "quit" => {
+                    println!(
+                        "{}",
+                        locale.text("詰将棋を終了します", "Leaving tsume mode")
+                    );
+                    return Ok(());
+                }
+                "answer" => {
+                    let line = format_line(notation, &initial, attacker, &session.solution);
+                    println!("{}", tr!(locale, "作意: {}", "Solution: {}", line));
+                    continue;
+                }
+                _ => {}
+            }
+
+            match parse_input(
+                notation,
+                locale,
+                input,
+                &legal_moves,
+                &session.state,
+                attacker,
+                previous,
+            ) {
+                Ok(mv) => break mv,
+                Err(e) => println!("{}", tr!(locale, "エラー: {}", "Error: {}", e)),
+            }
+        };
+
+        let before = session.state;
+        let alternatives: Vec<rules::Move> = session
+            .mating_moves()
+            .into_iter()
+            .filter(|&m| m != mv)
+            .collect();
+
+        match session.play(mv) {
+            tsume::Verdict::NotCheck => {
+                println!(
+                    "{}",
+                    locale.text(
+                        "王手ではありません。もう一度考えてみましょう。",
+                        "That is not a check. Try again."
+                    )
+                );
+                last_move_to = None;
+            }
+            tsume::Verdict::NoMate => {
+                println!(
+                    "{}",
+                    locale.text(
+                        "その手では残り手数内に詰みません。もう一度考えてみましょう。",
+                        "That move does not mate within the remaining moves. Try again."
+                    )
+                );
+                last_move_to = None;
+            }
+            tsume::Verdict::Continue { reply, alternative } => {
+                if alternative {
+                    println!(
+                        "{}",
+                        locale.text(
+                            "正解です（作意とは異なる手順です）",
+                            "Correct (a different line from the intended solution)"
+                        )
+                    );
+                } else {
+                    println!("{}", locale.text("正解です", "Correct"));
+                }
+                let after = rules::make_move(&before, mv, attacker);
+                let reply_text = notation.format_move(&after, defender, reply, Some(mv));
+                println!(
+                    "{}",
+                    tr!(locale, "玉方の応手: {}", "Defender replies: {}", reply_text)
+                );
+                previous = Some(reply);
+                last_move_to = Some(move_destination(reply));
+            }
+            tsume::Verdict::Solved { alternative } => {
+                let last_move_to = Some(move_destination(mv));
+                ui::print_game_state(&session.state, attacker, last_move_to, locale);
+                if alternative {
+                    println!(
+                        "{}",
+                        locale.text(
+                            "詰みました！（作意とは異なる手順です）",
+                            "Checkmate! (a different line from the intended solution)"
+                        )
+                    );
+                } else {
+                    println!(
+                        "{}",
+                        locale.text("詰みました！正解です", "Checkmate! Solved")
+                    );
+                }
+                if !alternatives.is_empty() {
+                    let names: Vec<String> = alternatives
+                        .iter()
+                        .map(|&m| notation.format_move(&before, attacker, m, previous))
+                        .collect();
+                    let names = names.join(" / ");
+                    let message = tr!(
+                        locale,
+                        "最終手の別解: {}",
+                        "Other mating last moves: {}",
+                        names
+                    );
+                    println!("{}", message);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// `index` 番目の問題の見出し（英語では問題名の代わりに番号と手数を表示する）
+fn problem_title(index: usize, locale: Locale) -> String {
+    let problem = &tsume::PROBLEMS[index];
+    match locale {
+        Locale::Japanese => problem.name.to_string(),
+        Locale::English => format!("Problem {} (mate in {})", index + 1, problem.plies),
+    }
+}
+
+/// 問題を選び、その番号（0 始まり）を返す。入力が終わったときは None
+fn select_problem(locale: Locale) -> Option<usize> {
+    loop {
+        println!(
+            "{}",
+            locale.text("問題を選んでください:", "Choose a problem:")
+        );
+        for i in 0..tsume::PROBLEMS.len() {
+            println!("  {}: {}", i + 1, problem_title(i, locale));
+        }
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let input = read_input()?;
+        match input.trim().parse::<usize>() {
+            Ok(n) if (1..=tsume::PROBLEMS.len()).contains(&n) => return Some(n - 1),
+            _ => {
+                let n = tsume::PROBLEMS.len();
+                let message = tr!(
+                    locale,
+                    "1 から {} の番号を入力してください",
+                    "Enter a number from 1 to {}",
+                    n
+                );
+                println!("{}", message);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::locale::Locale;
+use crate::{eval, tr, tune};
+use std::fs;
+
+// 評価関数の重みの調整
+
+/// 評価関数の調整で反復回数を省略した場合の値
+const TUNE_DEFAULT_ITERATIONS: usize = 1000;
+
+/// 評価関数の調整: `tune <棋譜ファイル> <出力ファイル> [反復回数]`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let (source, path) = match args {
+        [source, path] | [source, path, _] => (source, path),
+        _ => {
+            return Err(tr!(
+                locale,
+                "使い方: tune <棋譜ファイル> <出力ファイル> [反復回数]",
+                "Usage: tune <record file> <output file> [iterations]"
+            ));
+        }
+    };
+    let iterations = match args.get(2) {
+        None => TUNE_DEFAULT_ITERATIONS,
+        Some(s) => s.parse::<usize>().map_err(|_| {
+            tr!(
+                locale,
+                "反復回数が不正です: {}",
+                "Invalid number of iterations: {}",
+                s
+            )
+        })?,
+    };
+
+    let text = fs::read_to_string(source).map_err(|e| {
+        tr!(
+            locale,
+            "棋譜ファイルを読み込めません: {}: {}",
+            "Cannot read the record file: {}: {}",
+            source,
+            e
+        )
+    })?;
+    let samples =
+        tune::samples_from_records(&text).map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?;
+    if samples.is_empty() {
+        return Err(tr!(
+            locale,
+            "結果付きの対局がありません",
+            "No games with a result"
+        ));
+    }
+
+    let n = samples.len();
+    println!(
+        "{}",
+        tr!(locale, "{}局面で調整します", "Tuning on {} positions", n)
+    );
+    let mut tuner = tune::Tuner::new(&eval::Weights::default());
+    for i in 0..iterations {
+        let loss = tuner.step(&samples);
+        if i % 100 == 0 {
+            println!(
+                "{}",
+                tr!(locale, "{:>6}: 損失 {:.6}", "{:>6}: loss {:.6}", i, loss)
+            );
+        }
+    }
+    let weights = tuner.weights();
+    let loss = tune::loss(&samples, &weights);
+    println!(
+        "{}",
+        tr!(
+            locale,
+            "調整後の損失: {:.6}",
+            "Loss after tuning: {:.6}",
+            loss
+        )
+    );
+
+    weights
+        .save(path)
+        .map_err(|e| tr!(locale, "保存に失敗しました: {}", "Cannot save: {}", e))?;
+    println!("{}", tr!(locale, "{} に保存しました", "Saved to {}", path));
+    Ok(())
+}
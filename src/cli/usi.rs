@@ -0,0 +1,30 @@
+use crate::locale::Locale;
+use crate::{engine, eval, tr, usi};
+use std::io;
+
+// USI エンジンとしての起動
+
+/// USI エンジンとして動く: `usi [ab[:深さ]|mcts[:反復回数]]`
+pub fn run(args: &[String], locale: Locale) -> Result<(), String> {
+    let spec = match args.first() {
+        Some(s) => {
+            engine::EngineSpec::parse(s).map_err(|e| tr!(locale, "エラー: {}", "Error: {}", e))?
+        }
+        None => engine::EngineSpec::AlphaBeta { depth: None },
+    };
+    let builtin = spec.builtin().ok_or_else(|| {
+        tr!(
+            locale,
+            "使い方: usi [ab[:深さ]|mcts[:反復回数]]",
+            "Usage: usi [ab[:depth]|mcts[:iterations]]"
+        )
+    })?;
+
+    let mut evaluator = eval::Positional::default();
+    usi::run_engine(
+        io::BufReader::new(io::stdin()),
+        &mut io::stdout(),
+        |state, player, options| builtin.search(state, player, options, &mut evaluator),
+    )
+    .map_err(|e| tr!(locale, "入出力エラー: {}", "I/O error: {}", e))
+}
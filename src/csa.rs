@@ -0,0 +1,519 @@
+use crate::board::{self, GameState, HAND_TYPES, Hand, MAX_HAND, Piece, PieceType, Player};
+use crate::kifu::{Ending, Kifu};
+use crate::rules::{Move, Position};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// CSA 形式の棋譜
+//
+// ```text
+// V2.2
+// N+human
+// N-alphabeta
+// PI
+// +
+// +1413FU
+// T3
+// -4142KI
+// %TORYO
+// ```
+//
+// - マスは筋・段の数字2桁（`14` は1筋4段）、駒打ちの移動元は `00`。駒は移動後の種類を2文字で書く
+//   （OU 玉・KI 金・GI 銀・KA 角・HI 飛・FU 歩、成駒は NG 成銀・UM 馬・RY 龍・TO と）。
+// - 開始局面は `PI`（五々将棋の初期局面。`PI55OU` のように続けた駒を取り除く）か、`P1`～`P5` の一括表現と
+//   `P+`・`P-` の駒別表現（`00` は持駒、`00AL` は残りの駒すべて）で書き、次の `+`・`-` の行が手番。
+// - `,` で区切って1行に複数の文を書ける。`'` で始まる文（コメント）と `$` の棋譜情報、`T` の消費時間は読み飛ばす。
+// - 終局は `%TORYO` などの特殊な指し手で表す。
+
+/// 駒の種類と成りに対応する CSA の駒の名前
+pub(crate) fn piece_code(piece_type: PieceType, promoted: bool) -> &'static str {
+    match (piece_type, promoted) {
+        (PieceType::King, _) => "OU",
+        (PieceType::Gold, _) => "KI",
+        (PieceType::Silver, false) => "GI",
+        (PieceType::Silver, true) => "NG",
+        (PieceType::Bishop, false) => "KA",
+        (PieceType::Bishop, true) => "UM",
+        (PieceType::Rook, false) => "HI",
+        (PieceType::Rook, true) => "RY",
+        (PieceType::Pawn, false) => "FU",
+        (PieceType::Pawn, true) => "TO",
+    }
+}
+
+/// CSA の駒の名前を駒の種類と成りにする
+pub(crate) fn parse_piece_code(code: &str) -> Option<(PieceType, bool)> {
+    Some(match code {
+        "OU" => (PieceType::King, false),
+        "KI" => (PieceType::Gold, false),
+        "GI" => (PieceType::Silver, false),
+        "NG" => (PieceType::Silver, true),
+        "KA" => (PieceType::Bishop, false),
+        "UM" => (PieceType::Bishop, true),
+        "HI" => (PieceType::Rook, false),
+        "RY" => (PieceType::Rook, true),
+        "FU" => (PieceType::Pawn, false),
+        "TO" => (PieceType::Pawn, true),
+        _ => return None,
+    })
+}
+
+/// 手番の記号（`+` 先手・`-` 後手）
+pub(crate) fn player_sign(player: Player) -> char {
+    match player {
+        Player::Sente => '+',
+        Player::Gote => '-',
+    }
+}
+
+/// 手番の記号を読む
+pub(crate) fn parse_sign(c: char) -> Option<Player> {
+    match c {
+        '+' => Some(Player::Sente),
+        '-' => Some(Player::Gote),
+        _ => None,
+    }
+}
+
+fn format_square(pos: Position) -> String {
+    format!("{}{}", 5 - pos.x, pos.y + 1)
+}
+
+/// `14` のようなマスを読む（`00` は `None`）
+fn parse_square(s: &str) -> Result<Option<Position>, String> {
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    match digits[..] {
+        [0, 0] if s.len() == 2 => Ok(None),
+        [file @ 1..=5, rank @ 1..=5] if s.len() == 2 => {
+            Ok(Some(Position::new(5 - file as usize, rank as usize - 1)))
+        }
+        _ => Err(format!("マスを読めません: {}", s)),
+    }
+}
+
+/// 終局を表す特殊な指し手
+fn ending_word(ending: Ending) -> &'static str {
+    match ending {
+        Ending::Resign => "%TORYO",
+        Ending::Checkmate => "%TSUMI",
+        Ending::Draw => "%HIKIWAKE",
+        Ending::Interrupted => "%CHUDAN",
+    }
+}
+
+/// 特殊な指し手を終局の理由にする（手番側の反則・時間切れは投了と同じく手番側の負け）
+pub(crate) fn parse_ending(word: &str) -> Option<Ending> {
+    Some(match word {
+        "%TORYO" | "%TIME_UP" | "%ILLEGAL_MOVE" => Ending::Resign,
+        "%TSUMI" => Ending::Checkmate,
+        "%HIKIWAKE" | "%SENNICHITE" | "%JISHOGI" | "%MAX_MOVES" => Ending::Draw,
+        "%CHUDAN" => Ending::Interrupted,
+        _ => return None,
+    })
+}
+
+// ---- 書き出し ----
+
+/// 1手を `+1413FU` のような CSA の指し手にする
+pub fn format_move(state: &GameState, mv: Move, player: Player) -> String {
+    let sign = player_sign(player);
+    match mv {
+        Move::Drop(to, piece_type) => {
+            format!(
+                "{}00{}{}",
+                sign,
+                format_square(to),
+                piece_code(piece_type, false)
+            )
+        }
+        Move::To(from, to, promote) => {
+            let piece = state.board[from.y][from.x].expect("移動元には駒がある");
+            format!(
+                "{}{}{}{}",
+                sign,
+                format_square(from),
+                format_square(to),
+                piece_code(piece.piece_type, piece.promoted || promote)
+            )
+        }
+    }
+}
+
+/// 局面を `P1`～`P5` の一括表現と持駒の `P+`・`P-` で書く
+fn format_position(text: &mut String, state: &GameState) {
+    for (y, row) in state.board.iter().enumerate() {
+        write!(text, "P{}", y + 1).unwrap();
+        for cell in row {
+            match cell {
+                Some(p) => {
+                    text.push(player_sign(p.owner));
+                    text.push_str(piece_code(p.piece_type, p.promoted));
+                }
+                None => text.push_str(" * "),
+            }
+        }
+        text.push('\n');
+    }
+    for player in [Player::Sente, Player::Gote] {
+        let hand = state.get_hand(player);
+        if HAND_TYPES.iter().all(|&t| hand.get(t) == 0) {
+            continue;
+        }
+        text.push('P');
+        text.push(player_sign(player));
+        for &t in HAND_TYPES.iter().rev() {
+            for _ in 0..hand.get(t) {
+                write!(text, "00{}", piece_code(t, false)).unwrap();
+            }
+        }
+        text.push('\n');
+    }
+}
+
+/// 開始局面（初期局面なら `PI`）と手番の行を書く
+pub(crate) fn format_start(text: &mut String, state: &GameState, player: Player) {
+    if *state == board::init() {
+        writeln!(text, "PI").unwrap();
+    } else {
+        format_position(text, state);
+    }
+    writeln!(text, "{}", player_sign(player)).unwrap();
+}
+
+/// 棋譜を CSA 形式のテキストにする
+pub fn to_text(kifu: &Kifu) -> String {
+    let mut text = String::new();
+    writeln!(text, "V2.2").unwrap();
+    writeln!(text, "N+{}", kifu.players[0]).unwrap();
+    writeln!(text, "N-{}", kifu.players[1]).unwrap();
+    writeln!(text, "'minishogi-rs").unwrap();
+    format_start(&mut text, &kifu.start, kifu.start_player);
+
+    for (&mv, (state, player)) in kifu.moves.iter().zip(kifu.positions()) {
+        writeln!(text, "{}", format_move(&state, mv, player)).unwrap();
+    }
+    if let Some(ending) = kifu.ending {
+        writeln!(text, "{}", ending_word(ending)).unwrap();
+    }
+    text
+}
+
+// ---- 読み込み ----
+
+/// `+1413FU` のような CSA の指し手を読む（手番と移動元の駒を確かめる）
+pub fn parse_move(token: &str, state: &GameState, player: Player) -> Result<Move, String> {
+    let err = || format!("指し手を読めません: {}", token);
+    if token.len() != 7 || !token.is_ascii() {
+        return Err(err());
+    }
+    let sign = token.chars().next().and_then(parse_sign).ok_or_else(err)?;
+    if sign != player {
+        return Err(format!("手番ではありません: {}", token));
+    }
+    let from = parse_square(&token[1..3]).map_err(|_| err())?;
+    let to = parse_square(&token[3..5])
+        .map_err(|_| err())?
+        .ok_or_else(err)?;
+    let (piece_type, promoted) = parse_piece_code(&token[5..7]).ok_or_else(err)?;
+
+    let Some(from) = from else {
+        if promoted || piece_type == PieceType::King {
+            return Err(err());
+        }
+        return Ok(Move::Drop(to, piece_type));
+    };
+    let piece = state.board[from.y][from.x]
+        .filter(|p| p.owner == player)
+        .ok_or_else(|| format!("{} に手番側の駒がありません: {}", &token[1..3], token))?;
+    if piece.piece_type != piece_type || (piece.promoted && !promoted) {
+        return Err(format!(
+            "移動元の駒は{}です: {}",
+            piece_code(piece.piece_type, piece.promoted),
+            token
+        ));
+    }
+    Ok(Move::To(from, to, promoted && !piece.promoted))
+}
+
+/// 読み込み中の開始局面
+struct Setup {
+    state: GameState,
+    /// 一括表現の段を読んだか
+    rows: [bool; 5],
+}
+
+impl Setup {
+    fn new() -> Self {
+        Self {
+            state: GameState {
+                board: [[None; 5]; 5],
+                sente_hand: Hand::new(),
+                gote_hand: Hand::new(),
+            },
+            rows: [false; 5],
+        }
+    }
+
+    /// 盤上と持駒にある `piece_type` の枚数
+    fn count(&self, piece_type: PieceType) -> usize {
+        let on_board = self
+            .state
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some_and(|p| p.piece_type == piece_type))
+            .count();
+        let in_hands = [self.state.sente_hand, self.state.gote_hand];
+        on_board
+            + in_hands
+                .iter()
+                .map(|h| h.get(piece_type) as usize)
+                .sum::<usize>()
+    }
+
+    /// `PI` に続く駒を取り除いた初期局面
+    fn initial(rest: &str) -> Result<Self, String> {
+        let mut setup = Self::new();
+        setup.state = board::init();
+        setup.rows = [true; 5];
+        for chunk in ascii_chunks(rest, 4)? {
+            let pos = parse_square(&chunk[..2])?
+                .ok_or_else(|| format!("取り除くマスを読めません: {}", chunk))?;
+            let expected = parse_piece_code(&chunk[2..])
+                .ok_or_else(|| format!("駒を読めません: {}", chunk))?;
+            match setup.state.board[pos.y][pos.x] {
+                Some(p) if (p.piece_type, p.promoted) == expected => {
+                    setup.state.board[pos.y][pos.x] = None
+                }
+                _ => return Err(format!("取り除く駒がありません: {}", chunk)),
+            }
+        }
+        Ok(setup)
+    }
+
+    /// `P1-OU-KI-GI-KA-HI` のような一括表現の1段
+    fn row(&mut self, y: usize, cells: &str) -> Result<(), String> {
+        if self.rows[y] {
+            return Err(format!("P{} が2回あります", y + 1));
+        }
+        // 行末の空きマスの空白は削られていることがある
+        let padded = format!("{:<15}", cells);
+        let cells = ascii_chunks(&padded, 3)?;
+        if cells.len() != 5 {
+            return Err(format!("P{} は5マスです", y + 1));
+        }
+        for (x, cell) in cells.iter().enumerate() {
+            if matches!(cell.trim(), "" | "*") {
+                continue;
+            }
+            let owner = cell
+                .chars()
+                .next()
+                .and_then(parse_sign)
+                .ok_or_else(|| format!("マスを読めません: {}", cell))?;
+            let (piece_type, promoted) =
+                parse_piece_code(&cell[1..]).ok_or_else(|| format!("駒を読めません: {}", cell))?;
+            self.state.board[y][x] = Some(Piece {
+                piece_type,
+                owner,
+                promoted,
+            });
+        }
+        self.rows[y] = true;
+        Ok(())
+    }
+
+    /// `P+00KI00FU` のような駒別表現（`00` は持駒、`00AL` は残りの駒すべて）
+    fn pieces(&mut self, owner: Player, rest: &str) -> Result<(), String> {
+        for chunk in ascii_chunks(rest, 4)? {
+            if chunk == "00AL" {
+                for piece_type in HAND_TYPES {
+                    for _ in self.count(piece_type)..MAX_HAND {
+                        self.state.get_hand_mut(owner).add(piece_type);
+                    }
+                }
+                continue;
+            }
+            let (piece_type, promoted) = parse_piece_code(&chunk[2..])
+                .ok_or_else(|| format!("駒を読めません: {}", chunk))?;
+            match parse_square(&chunk[..2])? {
+                Some(pos) => {
+                    self.state.board[pos.y][pos.x] = Some(Piece {
+                        piece_type,
+                        owner,
+                        promoted,
+                    })
+                }
+                None if promoted || piece_type == PieceType::King => {
+                    return Err(format!("持駒にできない駒です: {}", chunk));
+                }
+                // 枚数は読み終えてから確かめるが、持駒の数があふれないようにここでも止める
+                None if self.count(piece_type) >= MAX_HAND => {
+                    return Err(format!(
+                        "{}が{}枚を超えています",
+                        piece_code(piece_type, false),
+                        MAX_HAND
+                    ));
+                }
+                None => self.state.get_hand_mut(owner).add(piece_type),
+            }
+        }
+        Ok(())
+    }
+
+    /// 読み終えた開始局面（駒の枚数を確かめる）
+    fn finish(self) -> Result<GameState, String> {
+        match self.state.excess_piece() {
+            Some(PieceType::King) => Err("OUは先手・後手とも1枚までです".to_string()),
+            Some(piece_type) => Err(format!(
+                "{}が{}枚を超えています",
+                piece_code(piece_type, false),
+                MAX_HAND
+            )),
+            None => Ok(self.state),
+        }
+    }
+}
+
+/// ASCII の文字列を `n` 文字ずつに分ける
+fn ascii_chunks(s: &str, n: usize) -> Result<Vec<&str>, String> {
+    if !s.is_ascii() || !s.len().is_multiple_of(n) {
+        return Err(format!("{}文字ずつに区切れません: {}", n, s));
+    }
+    Ok((0..s.len()).step_by(n).map(|i| &s[i..i + n]).collect())
+}
+
+/// CSA 形式のテキストを読み込み、指し手を合法手か確かめながら進める
+pub fn parse(text: &str) -> Result<Kifu, String> {
+    let mut players = [String::new(), String::new()];
+    let mut setup: Option<Setup> = None;
+    let mut kifu: Option<Kifu> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let err = |e: String| format!("{}行目: {}", i + 1, e);
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        // コメントの行は `,` を含んでいても区切らない
+        if line.starts_with('\'') {
+            continue;
+        }
+        for statement in line.split(',') {
+            let statement = statement.trim();
+            if statement.is_empty() || statement.starts_with(['\'', '$', 'V', 'T']) {
+                continue;
+            }
+
+            // 対局者名
+            if let Some(name) = statement.strip_prefix("N+") {
+                players[0] = name.to_string();
+                continue;
+            }
+            if let Some(name) = statement.strip_prefix("N-") {
+                players[1] = name.to_string();
+                continue;
+            }
+
+            // 開始局面
+            if let Some(rest) = statement.strip_prefix('P') {
+                if kifu.is_some() {
+                    return Err(err(format!("指し手の後に局面があります: {}", statement)));
+                }
+                if let Some(removed) = rest.strip_prefix('I') {
+                    if setup.is_some() {
+                        return Err(err("開始局面が2回あります".to_string()));
+                    }
+                    setup = Some(Setup::initial(removed).map_err(err)?);
+                    continue;
+                }
+                let current = setup.get_or_insert_with(Setup::new);
+                let mut chars = rest.chars();
+                match chars.next() {
+                    Some(c @ '1'..='5') => {
+                        current
+                            .row(c as usize - '1' as usize, chars.as_str())
+                            .map_err(err)?;
+                    }
+                    Some(c @ ('+' | '-')) => {
+                        current
+                            .pieces(parse_sign(c).unwrap(), chars.as_str())
+                            .map_err(err)?;
+                    }
+                    _ => return Err(err(format!("局面の行を読めません: {}", statement))),
+                }
+                continue;
+            }
+
+            // 手番（開始局面の最後）
+            if matches!(statement, "+" | "-") {
+                if kifu.is_some() {
+                    return Err(err(format!("指し手の後に手番があります: {}", statement)));
+                }
+                let player = parse_sign(statement.chars().next().unwrap()).unwrap();
+                kifu = Some(start(&players, setup.take(), player).map_err(err)?);
+                continue;
+            }
+
+            let Some(kifu) = kifu.as_mut() else {
+                return Err(err(format!(
+                    "開始局面と手番より前に指し手があります: {}",
+                    statement
+                )));
+            };
+            if kifu.ending.is_some() {
+                return Err(err("終局の後に指し手があります".to_string()));
+            }
+            if statement.starts_with('%') {
+                let ending = parse_ending(statement)
+                    .ok_or_else(|| err(format!("対応していない終局です: {}", statement)))?;
+                kifu.ending = Some(ending);
+                continue;
+            }
+            let (state, player) = kifu.position();
+            let mv = parse_move(statement, &state, player).map_err(err)?;
+            kifu.push(mv)
+                .map_err(|e| err(format!("{}: {}", statement, e)))?;
+        }
+    }
+
+    match kifu {
+        Some(kifu) => Ok(kifu),
+        None => Err("手番の行（+ か -）がありません".to_string()),
+    }
+}
+
+/// 対局者名と開始局面から指し手のない棋譜を作る
+fn start(players: &[String; 2], setup: Option<Setup>, player: Player) -> Result<Kifu, String> {
+    let state = match setup {
+        Some(setup) if setup.rows.iter().any(|&r| r) && !setup.rows.iter().all(|&r| r) => {
+            return Err("P1～P5 の一部が欠けています".to_string());
+        }
+        Some(setup) => setup.finish()?,
+        None => return Err("開始局面（PI か P1～P5）がありません".to_string()),
+    };
+    for owner in [Player::Sente, Player::Gote] {
+        let kings = state
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| {
+                cell.is_some_and(|p| p.piece_type == PieceType::King && p.owner == owner)
+            })
+            .count();
+        if kings != 1 {
+            return Err(format!("{}の玉が1枚ではありません", player_sign(owner)));
+        }
+    }
+    let mut kifu = Kifu::new(state, player);
+    kifu.players = players.clone();
+    Ok(kifu)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Kifu> {
+    let text = fs::read_to_string(path)?;
+    parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save<P: AsRef<Path>>(path: P, kifu: &Kifu) -> io::Result<()> {
+    fs::write(path, to_text(kifu))
+}
@@ -0,0 +1,612 @@
+use crate::board::{self, GameState, Player, player_index};
+use crate::csa;
+use crate::kifu::{self, Ending, Kifu};
+use crate::rules;
+use crate::search::SearchResult;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+// CSA 通信プロトコル
+//
+// `Client` は CSA サーバーに TCP で接続して対局し、`serve_game` は2つのクライアントを1局対局させる
+// 最小限のサーバー（動作確認用）。やりとりは CSA 通信プロトコル ver.1.2 に従い、局面と指し手は
+// `csa` モジュールの CSA 形式で書く。
+//
+// ```text
+// C: LOGIN <名前> <パスワード>   S: LOGIN:<名前> OK
+//                               S: BEGIN Game_Summary ... END Game_Summary（対局条件・持ち時間・開始局面）
+// C: AGREE                      S: START:<Game_ID>
+// C: +1413FU                    S: +1413FU,T3（両者に送る。T は消費時間）
+// C: %TORYO                     S: %TORYO,T1 / #RESIGN / #WIN か #LOSE
+// C: LOGOUT                     S: LOGOUT:completed
+// ```
+//
+// 五々将棋では王を取った手で対局が終わる。このときサーバーは理由の行を送らず `#WIN`・`#LOSE` だけを送る。
+
+/// 思考時間を決めるときに見込む、残りの自分の手数
+const MOVES_TO_GO: u64 = 20;
+/// 思考時間から差し引く通信の余裕
+const NETWORK_MARGIN: Duration = Duration::from_millis(50);
+/// 思考時間の下限
+const MIN_THINK_TIME: Duration = Duration::from_millis(10);
+/// 終局後に `LOGOUT` を待つ時間
+const LOGOUT_WAIT: Duration = Duration::from_secs(1);
+
+/// 持ち時間（`Time_Unit` を単位とした数で持つ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    /// 時間の単位
+    pub unit: Duration,
+    /// 持ち時間
+    pub total: u64,
+    /// 秒読み
+    pub byoyomi: u64,
+    /// 1手ごとの加算（フィッシャールール）
+    pub increment: u64,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            unit: Duration::from_secs(1),
+            total: 600,
+            byoyomi: 10,
+            increment: 0,
+        }
+    }
+}
+
+impl TimeControl {
+    /// 経過時間を単位の数にする（端数は切り捨て）
+    pub fn units(&self, elapsed: Duration) -> u64 {
+        (elapsed.as_nanos() / self.unit.as_nanos().max(1)) as u64
+    }
+
+    /// `used` を消費した後の持ち時間（持ち時間と秒読みを使い切っていれば `None`）
+    pub fn consume(&self, remaining: u64, used: u64) -> Option<u64> {
+        if used > remaining + self.byoyomi {
+            return None;
+        }
+        Some(remaining.saturating_sub(used) + self.increment)
+    }
+
+    /// 持ち時間が `remaining` のときに1手にかける思考時間
+    pub fn think_time(&self, remaining: u64) -> Duration {
+        let units = remaining / MOVES_TO_GO + self.byoyomi;
+        self.duration(units)
+            .saturating_sub(NETWORK_MARGIN)
+            .max(MIN_THINK_TIME)
+    }
+
+    fn duration(&self, units: u64) -> Duration {
+        self.unit
+            .saturating_mul(u32::try_from(units).unwrap_or(u32::MAX))
+    }
+}
+
+/// `1sec`・`1min`・`10msec` のような時間の単位を読む
+fn parse_unit(s: &str) -> Option<Duration> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let n: u64 = if digits == 0 {
+        1
+    } else {
+        s[..digits].parse().ok()?
+    };
+    match &s[digits..] {
+        "msec" => Some(Duration::from_millis(n)),
+        "sec" => Some(Duration::from_secs(n)),
+        "min" => Some(Duration::from_secs(n * 60)),
+        _ => None,
+    }
+    .filter(|d| !d.is_zero())
+}
+
+fn format_unit(unit: Duration) -> String {
+    match (unit.subsec_nanos(), unit.as_secs()) {
+        (0, secs) if secs % 60 == 0 => format!("{}min", secs / 60),
+        (0, secs) => format!("{}sec", secs),
+        _ => format!("{}msec", unit.as_millis()),
+    }
+}
+
+fn protocol_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 対局条件（`Game_Summary`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSummary {
+    pub game_id: String,
+    /// 対局者名・開始局面と、対局条件の時点で指されていた手
+    pub kifu: Kifu,
+    /// 自分の手番
+    pub my_turn: Player,
+    pub time: TimeControl,
+    /// 手数の上限（0 なら上限なし）
+    pub max_moves: u32,
+}
+
+impl GameSummary {
+    /// `BEGIN Game_Summary` から `END Game_Summary` までのテキストにする
+    pub fn to_text(&self) -> String {
+        let sign = csa::player_sign;
+        let (_, to_move) = self.kifu.position();
+        let mut text = String::new();
+        writeln!(text, "BEGIN Game_Summary").unwrap();
+        writeln!(text, "Protocol_Version:1.2").unwrap();
+        writeln!(text, "Protocol_Mode:Server").unwrap();
+        writeln!(text, "Format:Shogi 1.0").unwrap();
+        writeln!(text, "Game_ID:{}", self.game_id).unwrap();
+        writeln!(text, "Name+:{}", self.kifu.players[0]).unwrap();
+        writeln!(text, "Name-:{}", self.kifu.players[1]).unwrap();
+        writeln!(text, "Your_Turn:{}", sign(self.my_turn)).unwrap();
+        writeln!(text, "Rematch_On_Draw:NO").unwrap();
+        writeln!(text, "To_Move:{}", sign(to_move)).unwrap();
+        writeln!(text, "Max_Moves:{}", self.max_moves).unwrap();
+        writeln!(text, "BEGIN Time").unwrap();
+        writeln!(text, "Time_Unit:{}", format_unit(self.time.unit)).unwrap();
+        writeln!(text, "Total_Time:{}", self.time.total).unwrap();
+        writeln!(text, "Byoyomi:{}", self.time.byoyomi).unwrap();
+        if self.time.increment > 0 {
+            writeln!(text, "Increment:{}", self.time.increment).unwrap();
+        }
+        writeln!(text, "END Time").unwrap();
+        writeln!(text, "BEGIN Position").unwrap();
+        csa::format_start(&mut text, &self.kifu.start, self.kifu.start_player);
+        for (&mv, (state, player)) in self.kifu.moves.iter().zip(self.kifu.positions()) {
+            writeln!(text, "{}", csa::format_move(&state, mv, player)).unwrap();
+        }
+        writeln!(text, "END Position").unwrap();
+        writeln!(text, "END Game_Summary").unwrap();
+        text
+    }
+
+    /// `BEGIN Game_Summary` から `END Game_Summary` までの行を読む
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut game_id = String::new();
+        let mut players = [String::new(), String::new()];
+        let mut my_turn = None;
+        let mut to_move = None;
+        let mut time = TimeControl::default();
+        let mut max_moves = 0;
+        let mut position: Option<String> = None;
+        let mut in_position = false;
+
+        for line in text.lines().map(str::trim) {
+            let number = |value: &str| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("数を読めません: {}", line))
+            };
+            match line {
+                "" | "BEGIN Game_Summary" | "END Game_Summary" | "BEGIN Time" | "END Time" => {
+                    continue;
+                }
+                "BEGIN Position" => {
+                    in_position = true;
+                    position = Some(String::new());
+                    continue;
+                }
+                "END Position" => {
+                    in_position = false;
+                    continue;
+                }
+                _ => {}
+            }
+            if in_position {
+                let position = position.as_mut().unwrap();
+                position.push_str(line);
+                position.push('\n');
+                continue;
+            }
+
+            // 知らない項目（Protocol_Version、Declaration など）は読み飛ばす
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let sign = || {
+                let mut chars = value.chars();
+                match (chars.next().and_then(csa::parse_sign), chars.next()) {
+                    (Some(player), None) => Ok(player),
+                    _ => Err(format!("手番を読めません: {}", line)),
+                }
+            };
+            match key {
+                "Game_ID" => game_id = value.to_string(),
+                "Name+" => players[0] = value.to_string(),
+                "Name-" => players[1] = value.to_string(),
+                "Your_Turn" => my_turn = Some(sign()?),
+                "To_Move" => to_move = Some(sign()?),
+                "Max_Moves" => max_moves = number(value)? as u32,
+                "Time_Unit" => {
+                    time.unit = parse_unit(value)
+                        .ok_or_else(|| format!("時間の単位を読めません: {}", line))?
+                }
+                "Total_Time" => time.total = number(value)?,
+                "Byoyomi" => time.byoyomi = number(value)?,
+                "Increment" => time.increment = number(value)?,
+                _ => {}
+            }
+        }
+
+        let position = position.ok_or("開始局面（BEGIN Position）がありません")?;
+        let mut kifu = csa::parse(&position).map_err(|e| format!("開始局面: {}", e))?;
+        kifu.players = players;
+        if to_move.is_some_and(|p| p != kifu.position().1) {
+            return Err("To_Move が開始局面の手番と合いません".to_string());
+        }
+        Ok(Self {
+            game_id,
+            kifu,
+            my_turn: my_turn.ok_or("Your_Turn がありません")?,
+            time,
+            max_moves,
+        })
+    }
+}
+
+/// 終局の結果（`#WIN` など）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Lose,
+    Draw,
+    /// 手数の上限などによる打ち切り
+    Censored,
+    /// 中断
+    Chudan,
+}
+
+impl Outcome {
+    fn parse(line: &str) -> Option<Self> {
+        Some(match line {
+            "#WIN" => Outcome::Win,
+            "#LOSE" => Outcome::Lose,
+            "#DRAW" => Outcome::Draw,
+            "#CENSORED" => Outcome::Censored,
+            "#CHUDAN" => Outcome::Chudan,
+            _ => return None,
+        })
+    }
+
+    fn line(self) -> &'static str {
+        match self {
+            Outcome::Win => "#WIN",
+            Outcome::Lose => "#LOSE",
+            Outcome::Draw => "#DRAW",
+            Outcome::Censored => "#CENSORED",
+            Outcome::Chudan => "#CHUDAN",
+        }
+    }
+}
+
+/// 終局の理由（`#RESIGN` など）を棋譜の終局にする（手番側の負けは投了と同じ扱い）
+fn reason_ending(reason: &str) -> Option<Ending> {
+    match reason {
+        "#RESIGN" | "#TIME_UP" | "#ILLEGAL_MOVE" => Some(Ending::Resign),
+        "#SENNICHITE" | "#JISHOGI" | "#MAX_MOVES" => Some(Ending::Draw),
+        _ => None,
+    }
+}
+
+/// 行単位の TCP 接続
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        // 1行ずつのやりとりなので、送信を溜めずにすぐ送る
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(format!("{}\n", line).as_bytes())
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "接続が切れました",
+            ));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.set_read_timeout(timeout)
+    }
+}
+
+/// 1局の結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameReport {
+    pub summary: GameSummary,
+    /// 対局条件の指し手を含む、終局までの棋譜
+    pub kifu: Kifu,
+    pub outcome: Outcome,
+    /// 終局の理由（`RESIGN`・`TIME_UP` など。王を取って終わったときは `None`）
+    pub reason: Option<String>,
+    /// 終局時の [先手, 後手] の持ち時間
+    pub remaining: [u64; 2],
+}
+
+/// CSA サーバーに接続したクライアント
+pub struct Client {
+    conn: Connection,
+}
+
+impl Client {
+    /// サーバーに接続してログインする
+    pub fn login<A: ToSocketAddrs>(addr: A, name: &str, password: &str) -> io::Result<Self> {
+        let mut conn = Connection::new(TcpStream::connect(addr)?)?;
+        conn.send(&format!("LOGIN {} {}", name, password))?;
+        let line = conn.read_line()?;
+        if line != format!("LOGIN:{} OK", name) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("ログインできません: {}", line),
+            ));
+        }
+        Ok(Self { conn })
+    }
+
+    /// 対局条件を受け取って承諾し、終局まで指す
+    ///
+    /// `think` は局面・手番と思考時間の目安から指し手を探す（指し手がなければ投了する）。
+    pub fn play<F>(&mut self, mut think: F) -> io::Result<GameReport>
+    where
+        F: FnMut(&GameState, Player, Duration) -> SearchResult,
+    {
+        while self.conn.read_line()? != "BEGIN Game_Summary" {}
+        let mut text = String::new();
+        loop {
+            let line = self.conn.read_line()?;
+            if line == "END Game_Summary" {
+                break;
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+        let summary = GameSummary::parse(&text).map_err(protocol_error)?;
+
+        self.conn.send("AGREE")?;
+        loop {
+            let line = self.conn.read_line()?;
+            if line.starts_with("START:") {
+                break;
+            }
+            if line.starts_with("REJECT:") {
+                return Err(protocol_error(format!("対局が拒否されました: {}", line)));
+            }
+        }
+
+        let mut kifu = summary.kifu.clone();
+        let mut remaining = [summary.time.total; 2];
+        let mut reason = None;
+        let mut sent = false;
+        loop {
+            let (state, player) = kifu.position();
+            let my_move = player == summary.my_turn;
+            if my_move && !sent && kifu.ending.is_none() && !kifu::is_finished(&state) {
+                let think_time = summary.time.think_time(remaining[player_index(player)]);
+                let line = match think(&state, player, think_time).best_move {
+                    Some(mv) => csa::format_move(&state, mv, player),
+                    None => "%TORYO".to_string(),
+                };
+                self.conn.send(&line)?;
+                sent = true;
+            }
+
+            let line = self.conn.read_line()?;
+            let (body, used) = match line.split_once(",T") {
+                Some((body, t)) => (body, t.parse::<u64>().ok()),
+                None => (line.as_str(), None),
+            };
+            if let Some(used) = used {
+                let i = player_index(player);
+                remaining[i] = summary.time.consume(remaining[i], used).unwrap_or(0);
+            }
+            match body.chars().next() {
+                Some('+' | '-') => {
+                    let mv = csa::parse_move(body, &state, player).map_err(protocol_error)?;
+                    kifu.push(mv)
+                        .map_err(|e| protocol_error(format!("{}: {}", body, e)))?;
+                    sent = false;
+                }
+                Some('%') => kifu.ending = kifu.ending.or(csa::parse_ending(body)),
+                Some('#') => {
+                    if let Some(outcome) = Outcome::parse(body) {
+                        return Ok(GameReport {
+                            summary,
+                            kifu,
+                            outcome,
+                            reason,
+                            remaining,
+                        });
+                    }
+                    kifu.ending = kifu.ending.or(reason_ending(body));
+                    reason = Some(body[1..].to_string());
+                }
+                // 空行（接続の維持）や知らない行は読み飛ばす
+                _ => {}
+            }
+        }
+    }
+
+    /// ログアウトして接続を閉じる
+    pub fn logout(mut self) -> io::Result<()> {
+        self.conn.send("LOGOUT")?;
+        loop {
+            match self.conn.read_line() {
+                Ok(line) if line == "LOGOUT:completed" => return Ok(()),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// `serve_game` の対局条件
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub game_id: String,
+    pub start: GameState,
+    pub start_player: Player,
+    pub time: TimeControl,
+    /// 手数の上限（0 なら上限なし）
+    pub max_moves: u32,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            game_id: "minishogi-rs".to_string(),
+            start: board::init(),
+            start_player: Player::Sente,
+            time: TimeControl::default(),
+            max_moves: 256,
+        }
+    }
+}
+
+/// `listener` にログインした2人（先にログインした方が先手）を1局対局させ、棋譜を返す
+///
+/// パスワードは確かめない。持ち時間は指し手を受け取るまでの時間で計り、使い切った側の負けにする。
+pub fn serve_game(listener: &TcpListener, config: &ServerConfig) -> io::Result<Kifu> {
+    let mut conns = Vec::with_capacity(2);
+    let mut kifu = Kifu::new(config.start, config.start_player);
+    while conns.len() < 2 {
+        let mut conn = Connection::new(listener.accept()?.0)?;
+        let line = conn.read_line()?;
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["LOGIN", name, _password] => {
+                conn.send(&format!("LOGIN:{} OK", name))?;
+                kifu.players[conns.len()] = name.to_string();
+                conns.push(conn);
+            }
+            _ => conn.send("LOGIN:incorrect")?,
+        }
+    }
+
+    for (i, conn) in conns.iter_mut().enumerate() {
+        let summary = GameSummary {
+            game_id: config.game_id.clone(),
+            kifu: kifu.clone(),
+            my_turn: if i == 0 { Player::Sente } else { Player::Gote },
+            time: config.time,
+            max_moves: config.max_moves,
+        };
+        conn.send(summary.to_text().trim_end())?;
+    }
+    for i in 0..2 {
+        let line = conns[i].read_line()?;
+        if line != "AGREE" {
+            let message = format!("REJECT:{} by {}", config.game_id, kifu.players[i]);
+            for conn in &mut conns {
+                conn.send(&message)?;
+            }
+            return Err(io::Error::other(format!(
+                "対局が拒否されました: {}",
+                kifu.players[i]
+            )));
+        }
+    }
+    for conn in &mut conns {
+        conn.send(&format!("START:{}", config.game_id))?;
+    }
+
+    let mut remaining = [config.time.total; 2];
+    let (reason, winner) = loop {
+        let (state, player) = kifu.position();
+        let i = player_index(player);
+        let started = Instant::now();
+        // 持ち時間と秒読み（端数の切り捨てを含む）を過ぎたら時間切れ
+        let limit = config.time.duration(remaining[i] + config.time.byoyomi + 1);
+
+        let line = loop {
+            let left = limit.saturating_sub(started.elapsed());
+            conns[i].set_timeout(Some(left.max(Duration::from_millis(1))))?;
+            match conns[i].read_line() {
+                Ok(line) if line.is_empty() => continue,
+                Ok(line) => break Some(line),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    break None;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        conns[i].set_timeout(None)?;
+        let used = config.time.units(started.elapsed());
+        let opponent = Some(rules::opponent_of(player));
+
+        let Some(left) = line.as_ref().and(config.time.consume(remaining[i], used)) else {
+            kifu.ending = Some(Ending::Resign);
+            break (Some("#TIME_UP"), opponent);
+        };
+        let line = line.unwrap();
+        if line == "%TORYO" {
+            broadcast(&mut conns, &format!("%TORYO,T{}", used))?;
+            kifu.ending = Some(Ending::Resign);
+            break (Some("#RESIGN"), opponent);
+        }
+        let pushed = csa::parse_move(&line, &state, player).and_then(|mv| kifu.push(mv));
+        if pushed.is_err() {
+            kifu.ending = Some(Ending::Resign);
+            break (Some("#ILLEGAL_MOVE"), opponent);
+        }
+        remaining[i] = left;
+        broadcast(&mut conns, &format!("{},T{}", line, used))?;
+
+        if kifu::is_finished(&kifu.position().0) {
+            break (None, Some(player));
+        }
+        if config.max_moves > 0 && kifu.moves.len() >= config.max_moves as usize {
+            kifu.ending = Some(Ending::Draw);
+            break (Some("#MAX_MOVES"), None);
+        }
+    };
+
+    if let Some(reason) = reason {
+        broadcast(&mut conns, reason)?;
+    }
+    for (i, conn) in conns.iter_mut().enumerate() {
+        let outcome = match winner {
+            Some(w) if player_index(w) == i => Outcome::Win,
+            Some(_) => Outcome::Lose,
+            None => Outcome::Censored,
+        };
+        conn.send(outcome.line())?;
+    }
+
+    // 終局後の LOGOUT に応える（来なければ待たない）
+    for conn in &mut conns {
+        conn.set_timeout(Some(LOGOUT_WAIT))?;
+        if conn.read_line().is_ok_and(|line| line == "LOGOUT") {
+            let _ = conn.send("LOGOUT:completed");
+        }
+    }
+    Ok(kifu)
+}
+
+fn broadcast(conns: &mut [Connection], line: &str) -> io::Result<()> {
+    for conn in conns {
+        conn.send(line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use crate::board::{GameState, Player};
+use crate::eval::{Evaluator, Positional};
+use crate::search::{self, SearchOptions, SearchResult};
+
+// 探索エンジンの指定
+//
+// 対局・検討・自己対局・エンジン同士の対局で共通に使う。内蔵エンジンは Alpha-Beta 探索と MCTS で、
+// エンジン同士の対局では外部の USI エンジンも指定できる。
+
+/// 内蔵の探索エンジン
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    AlphaBeta,
+    Mcts,
+}
+
+impl Engine {
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::AlphaBeta => "alphabeta",
+            Engine::Mcts => "mcts",
+        }
+    }
+
+    /// `alphabeta`（`ab`）か `mcts`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "alphabeta" | "ab" => Ok(Engine::AlphaBeta),
+            "mcts" => Ok(Engine::Mcts),
+            _ => Err(format!("不明なエンジンです: {}", s)),
+        }
+    }
+
+    /// 標準の評価関数で探索する
+    pub fn search(
+        self,
+        state: &GameState,
+        player: Player,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        let mut evaluator = Positional::default();
+        match self {
+            Engine::AlphaBeta => search::search_alpha_beta(state, player, options, &mut evaluator),
+            Engine::Mcts => search::search_mcts(state, player, options, &mut evaluator),
+        }
+    }
+}
+
+/// 強さを指定した内蔵エンジン
+///
+/// `ab[:深さ]`（`alphabeta` とも書ける）か `mcts[:反復回数]` で指定する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    pub engine: Engine,
+    /// Alpha-Beta なら探索の深さ、MCTS なら反復回数（`None` なら探索の設定に従う）
+    pub strength: Option<u32>,
+}
+
+impl Builtin {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (engine, strength) = match s.split_once(':') {
+            Some((engine, n)) => {
+                let n = n
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("強さが不正です: {}", s))?;
+                (engine, Some(n))
+            }
+            None => (s, None),
+        };
+        Ok(Builtin {
+            engine: Engine::parse(engine)?,
+            strength,
+        })
+    }
+
+    /// 棋譜に残す名前（強さを指定していれば `alphabeta:3` のように付ける）
+    pub fn name(self) -> String {
+        match self.strength {
+            Some(n) => format!("{}:{}", self.engine.name(), n),
+            None => self.engine.name().to_string(),
+        }
+    }
+
+    /// 強さを `options` の深さ・反復回数より優先して探索する
+    pub fn search(
+        self,
+        state: &GameState,
+        player: Player,
+        options: &SearchOptions,
+        evaluator: &mut dyn Evaluator,
+    ) -> SearchResult {
+        match self.engine {
+            Engine::AlphaBeta => {
+                let options = SearchOptions {
+                    depth: self.strength.or(options.depth),
+                    ..*options
+                };
+                search::search_alpha_beta(state, player, &options, evaluator)
+            }
+            Engine::Mcts => {
+                let options = SearchOptions {
+                    iterations: self.strength.or(options.iterations),
+                    ..*options
+                };
+                search::search_mcts(state, player, &options, evaluator)
+            }
+        }
+    }
+}
+
+/// 対局させるエンジンの指定
+///
+/// - `ab` / `ab:<深さ>`: Alpha-Beta 探索
+/// - `mcts` / `mcts:<反復回数>`: MCTS
+/// - `usi:<コマンド>`: 外部の USI エンジン（コマンドは空白で引数に分ける）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineSpec {
+    AlphaBeta { depth: Option<u32> },
+    Mcts { iterations: Option<u32> },
+    Usi { program: String, args: Vec<String> },
+}
+
+impl EngineSpec {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if let Some(command) = s.strip_prefix("usi:") {
+            let mut words = command.split_whitespace();
+            let program = words
+                .next()
+                .ok_or_else(|| "usi: の後にコマンドが必要です".to_string())?;
+            return Ok(EngineSpec::Usi {
+                program: program.to_string(),
+                args: words.map(str::to_string).collect(),
+            });
+        }
+        let builtin = Builtin::parse(s)?;
+        Ok(match builtin.engine {
+            Engine::AlphaBeta => EngineSpec::AlphaBeta {
+                depth: builtin.strength,
+            },
+            Engine::Mcts => EngineSpec::Mcts {
+                iterations: builtin.strength,
+            },
+        })
+    }
+
+    /// 内蔵エンジンならその指定（外部エンジンなら `None`）
+    pub fn builtin(&self) -> Option<Builtin> {
+        match *self {
+            EngineSpec::AlphaBeta { depth } => Some(Builtin {
+                engine: Engine::AlphaBeta,
+                strength: depth,
+            }),
+            EngineSpec::Mcts { iterations } => Some(Builtin {
+                engine: Engine::Mcts,
+                strength: iterations,
+            }),
+            EngineSpec::Usi { .. } => None,
+        }
+    }
+
+    /// 表示用の名前
+    pub fn name(&self) -> String {
+        match self {
+            EngineSpec::AlphaBeta { depth: None } => "ab".to_string(),
+            EngineSpec::AlphaBeta { depth: Some(d) } => format!("ab:{}", d),
+            EngineSpec::Mcts { iterations: None } => "mcts".to_string(),
+            EngineSpec::Mcts {
+                iterations: Some(n),
+            } => format!("mcts:{}", n),
+            EngineSpec::Usi { program, args } if args.is_empty() => format!("usi:{}", program),
+            EngineSpec::Usi { program, args } => format!("usi:{} {}", program, args.join(" ")),
+        }
+    }
+
+    /// 内蔵エンジンが標準の評価関数で探索する（外部エンジンなら `None`）
+    pub fn search(
+        &self,
+        state: &GameState,
+        player: Player,
+        options: &SearchOptions,
+    ) -> Option<SearchResult> {
+        let builtin = self.builtin()?;
+        Some(builtin.search(state, player, options, &mut Positional::default()))
+    }
+}
@@ -0,0 +1,602 @@
+use crate::board::{GameState, HAND_TYPES, Piece, PieceType, Player, player_index};
+use crate::rules::{self, Move, Position, SearchState, Undo};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// 評価関数
+// 評価値は先手から見た点数（正なら先手有利）。歩1枚をおよそ100点とする。
+// 玉が取られた局面（終局）の判定は探索側で行い、評価関数には渡さない。
+//
+// 探索は根の局面で `reset` を呼んだあと、局面を1手進めるたびに `on_do_move`、戻すたびに
+// `on_undo_move` を呼ぶ。差分計算をする評価関数はこれらで内部状態を更新し、
+// `evaluate` では探索中の局面（`reset` 以降の指し手を適用した局面）が渡される前提で値を返してよい。
+// 探索は葉で `evaluate_position` を呼び、既定ではそのまま `evaluate` に委ねる。
+
+/// 局面の評価
+pub trait Evaluator {
+    /// `player` は手番（手番の有利を評価するために使う）
+    fn evaluate(&self, state: &GameState, player: Player) -> i32;
+
+    /// 探索中の局面を評価する。差分計算した結果を `pos.hash` で引ける評価関数はこちらを上書きする
+    fn evaluate_position(&self, pos: &SearchState) -> i32 {
+        self.evaluate(&pos.state, pos.player)
+    }
+
+    /// 探索の開始時に根の局面で呼ばれる
+    fn reset(&mut self, _pos: &SearchState) {}
+
+    /// `pos` を1手進めた直後に呼ばれる（`pos` は指した後の局面）
+    fn on_do_move(&mut self, _pos: &SearchState, _undo: &Undo) {}
+
+    /// `pos` を1手戻した直後に呼ばれる（`pos` は戻した後の局面）
+    fn on_undo_move(&mut self, _pos: &SearchState, _undo: &Undo) {}
+}
+
+impl<E: Evaluator + ?Sized> Evaluator for Box<E> {
+    fn evaluate(&self, state: &GameState, player: Player) -> i32 {
+        (**self).evaluate(state, player)
+    }
+
+    fn evaluate_position(&self, pos: &SearchState) -> i32 {
+        (**self).evaluate_position(pos)
+    }
+
+    fn reset(&mut self, pos: &SearchState) {
+        (**self).reset(pos);
+    }
+
+    fn on_do_move(&mut self, pos: &SearchState, undo: &Undo) {
+        (**self).on_do_move(pos, undo);
+    }
+
+    fn on_undo_move(&mut self, pos: &SearchState, undo: &Undo) {
+        (**self).on_undo_move(pos, undo);
+    }
+}
+
+/// 成りを区別した駒の種類の数
+pub const KIND_COUNT: usize = 10;
+
+/// 駒の種類の添字（歩・銀・金・角・飛・玉・と・成銀・馬・龍）
+pub fn kind_index(piece_type: PieceType, promoted: bool) -> usize {
+    match (piece_type, promoted) {
+        (PieceType::Pawn, false) => 0,
+        (PieceType::Silver, false) => 1,
+        (PieceType::Gold, _) => 2,
+        (PieceType::Bishop, false) => 3,
+        (PieceType::Rook, false) => 4,
+        (PieceType::King, _) => 5,
+        (PieceType::Pawn, true) => 6,
+        (PieceType::Silver, true) => 7,
+        (PieceType::Bishop, true) => 8,
+        (PieceType::Rook, true) => 9,
+    }
+}
+
+/// 先手から見たマスの添字（後手の駒は盤を180度回して引く）
+fn pst_index(piece: Piece, x: usize, y: usize) -> usize {
+    match piece.owner {
+        Player::Sente => y * 5 + x,
+        Player::Gote => (4 - y) * 5 + (4 - x),
+    }
+}
+
+/// 駒の価値の標準値（`kind_index` の順）
+const PIECE_VALUES: [i32; KIND_COUNT] = [100, 400, 500, 600, 700, 0, 500, 500, 800, 900];
+
+/// 駒割りだけの評価（盤上の駒と持ち駒を同じ価値で数える）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Material;
+
+impl Evaluator for Material {
+    fn evaluate(&self, state: &GameState, _player: Player) -> i32 {
+        let mut score = 0;
+
+        for row in &state.board {
+            for piece in row.iter().flatten() {
+                score +=
+                    sign(piece.owner) * PIECE_VALUES[kind_index(piece.piece_type, piece.promoted)];
+            }
+        }
+        for pt in HAND_TYPES {
+            let value = PIECE_VALUES[kind_index(pt, false)];
+            score += value * i32::from(state.sente_hand.get(pt));
+            score -= value * i32::from(state.gote_hand.get(pt));
+        }
+
+        score
+    }
+}
+
+/// `Material` と同じ値を差分計算で求める評価
+/// 取った駒と成りによる増減だけを足し引きし、`evaluate` では局面を走査しない
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IncrementalMaterial {
+    score: i32,
+}
+
+impl IncrementalMaterial {
+    /// `moved` の駒を動かした指し手による駒割りの変化（先手から見た値）
+    fn delta(moved: Piece, undo: &Undo) -> i32 {
+        let value = |pt, promoted| PIECE_VALUES[kind_index(pt, promoted)];
+
+        // 駒打ちは持ち駒と盤上の駒の価値が同じなので変化しない
+        let mut delta = 0;
+        if let Some(captured) = undo.captured {
+            // 盤上の駒（成駒なら成駒の価値）が持ち駒（成る前の価値）になる
+            delta +=
+                value(captured.piece_type, captured.promoted) + value(captured.piece_type, false);
+        }
+        if undo.promoted {
+            delta += value(moved.piece_type, true) - value(moved.piece_type, false);
+        }
+        sign(moved.owner) * delta
+    }
+}
+
+impl Evaluator for IncrementalMaterial {
+    fn evaluate(&self, _state: &GameState, _player: Player) -> i32 {
+        self.score
+    }
+
+    fn reset(&mut self, pos: &SearchState) {
+        self.score = Material.evaluate(&pos.state, pos.player);
+    }
+
+    fn on_do_move(&mut self, pos: &SearchState, undo: &Undo) {
+        if let Move::To(_, to, _) = undo.mv {
+            let moved = pos.state.board[to.y][to.x].expect("指した駒がある");
+            self.score += Self::delta(moved, undo);
+        }
+    }
+
+    fn on_undo_move(&mut self, pos: &SearchState, undo: &Undo) {
+        if let Move::To(from, _, _) = undo.mv {
+            let moved = pos.state.board[from.y][from.x].expect("指した駒がある");
+            self.score -= Self::delta(moved, undo);
+        }
+    }
+}
+
+/// 評価関数の重み
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weights {
+    /// 盤上の駒の価値（`kind_index` の順）
+    pub piece: [i32; KIND_COUNT],
+    /// 持ち駒の価値に加える点（`HAND_TYPES` の順）
+    pub hand: [i32; 5],
+    /// 駒の位置の点（先手から見たマス y * 5 + x、y = 0 が敵陣の奥）
+    pub pst: [[i32; 25]; KIND_COUNT],
+    /// 玉の周囲8マスにいる味方の駒1枚あたり
+    pub king_defender: i32,
+    /// 玉から2マス以内にいる敵の駒1枚あたり
+    pub king_attacker: i32,
+    /// 玉と周囲8マスのうち敵の利きがあるマス1つあたり
+    pub king_attacked_square: i32,
+    /// 盤上の駒（玉を除く）の移動可能なマス1つあたり
+    pub mobility: i32,
+    /// 手番側の有利
+    pub tempo: i32,
+}
+
+#[rustfmt::skip]
+const DEFAULT_PST: [[i32; 25]; KIND_COUNT] = [
+    // 歩
+    [
+          0,   0,   0,   0,   0,
+         20,  20,  20,  20,  20,
+         10,  10,  15,  10,  10,
+          0,   0,   5,   0,   0,
+          0,   0,   0,   0,   0,
+    ],
+    // 銀
+    [
+          5,  10,  10,  10,   5,
+         10,  20,  20,  20,  10,
+          5,  15,  20,  15,   5,
+          0,   5,  10,   5,   0,
+         -5,   0,   0,   0,  -5,
+    ],
+    // 金
+    [
+          0,   5,   5,   5,   0,
+          5,  10,  15,  10,   5,
+          0,  10,  15,  10,   5,
+          0,   5,  10,  10,  10,
+         -5,   0,   5,  10,   5,
+    ],
+    // 角
+    [
+          0,   5,   5,   5,   0,
+          5,  10,  10,  10,   5,
+          5,  10,  15,  10,   5,
+          0,   5,  10,   5,   0,
+          0,   0,   0,   0,   0,
+    ],
+    // 飛
+    [
+         10,  10,  10,  10,  10,
+         15,  15,  15,  15,  15,
+          0,   5,   5,   5,   0,
+          0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,
+    ],
+    // 玉
+    [
+        -40, -40, -40, -40, -40,
+        -30, -30, -30, -30, -30,
+        -20, -20, -20, -20, -20,
+          0,   0,   0,   5,   5,
+          5,   0,   0,  10,  15,
+    ],
+    // と
+    [
+         10,  15,  15,  15,  10,
+         15,  20,  20,  20,  15,
+         10,  15,  15,  15,  10,
+          0,   5,   5,   5,   0,
+          0,   0,   0,   0,   0,
+    ],
+    // 成銀
+    [
+         10,  15,  15,  15,  10,
+         15,  20,  20,  20,  15,
+         10,  15,  15,  15,  10,
+          0,   5,   5,   5,   0,
+          0,   0,   0,   0,   0,
+    ],
+    // 馬
+    [
+          5,  10,  10,  10,   5,
+         10,  15,  15,  15,  10,
+         10,  15,  20,  15,  10,
+          5,  10,  15,  10,   5,
+          0,   5,   5,   5,   0,
+    ],
+    // 龍
+    [
+         15,  15,  15,  15,  15,
+         20,  20,  20,  20,  20,
+         10,  10,  10,  10,  10,
+          5,   5,   5,   5,   5,
+          0,   0,   0,   0,   0,
+    ],
+];
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            piece: PIECE_VALUES,
+            hand: [10, 30, 40, 40, 50],
+            pst: DEFAULT_PST,
+            king_defender: 15,
+            king_attacker: 12,
+            king_attacked_square: 20,
+            mobility: 4,
+            tempo: 20,
+        }
+    }
+}
+
+// ---- パラメータ ----
+// 重みを1列に並べたもの。評価値はパラメータについて線形（各項の係数との内積）になる。
+
+const PIECE_PARAM: usize = 0;
+const HAND_PARAM: usize = PIECE_PARAM + KIND_COUNT;
+const PST_PARAM: usize = HAND_PARAM + 5;
+const KING_DEFENDER_PARAM: usize = PST_PARAM + KIND_COUNT * 25;
+const KING_ATTACKER_PARAM: usize = KING_DEFENDER_PARAM + 1;
+const KING_ATTACKED_SQUARE_PARAM: usize = KING_ATTACKER_PARAM + 1;
+const MOBILITY_PARAM: usize = KING_ATTACKED_SQUARE_PARAM + 1;
+const TEMPO_PARAM: usize = MOBILITY_PARAM + 1;
+
+/// パラメータの数
+pub const PARAM_COUNT: usize = TEMPO_PARAM + 1;
+
+/// パラメータファイルでの駒の種類の表記（`kind_index` の順）
+const KIND_LABELS: [&str; KIND_COUNT] = ["P", "S", "G", "B", "R", "K", "+P", "+S", "+B", "+R"];
+
+/// パラメータファイルでの1つの値の項目名
+const SCALAR_LABELS: [(&str, usize); 5] = [
+    ("king_defender", KING_DEFENDER_PARAM),
+    ("king_attacker", KING_ATTACKER_PARAM),
+    ("king_attacked_square", KING_ATTACKED_SQUARE_PARAM),
+    ("mobility", MOBILITY_PARAM),
+    ("tempo", TEMPO_PARAM),
+];
+
+impl Weights {
+    /// パラメータの列に変換する
+    pub fn to_params(&self) -> Vec<i32> {
+        let mut params = Vec::with_capacity(PARAM_COUNT);
+        params.extend_from_slice(&self.piece);
+        params.extend_from_slice(&self.hand);
+        for table in &self.pst {
+            params.extend_from_slice(table);
+        }
+        params.extend_from_slice(&[
+            self.king_defender,
+            self.king_attacker,
+            self.king_attacked_square,
+            self.mobility,
+            self.tempo,
+        ]);
+        params
+    }
+
+    /// `to_params` の `index` 番目の値
+    fn param(&self, index: usize) -> i32 {
+        match index {
+            PIECE_PARAM..HAND_PARAM => self.piece[index - PIECE_PARAM],
+            HAND_PARAM..PST_PARAM => self.hand[index - HAND_PARAM],
+            PST_PARAM..KING_DEFENDER_PARAM => {
+                self.pst[(index - PST_PARAM) / 25][(index - PST_PARAM) % 25]
+            }
+            KING_DEFENDER_PARAM => self.king_defender,
+            KING_ATTACKER_PARAM => self.king_attacker,
+            KING_ATTACKED_SQUARE_PARAM => self.king_attacked_square,
+            MOBILITY_PARAM => self.mobility,
+            TEMPO_PARAM => self.tempo,
+            _ => panic!("パラメータの添字が範囲外です: {}", index),
+        }
+    }
+
+    /// パラメータの列から作る（長さは `PARAM_COUNT`）
+    pub fn from_params(params: &[i32]) -> Self {
+        assert_eq!(params.len(), PARAM_COUNT, "パラメータの数が違います");
+        let mut weights = Self::default();
+        weights
+            .piece
+            .copy_from_slice(&params[PIECE_PARAM..HAND_PARAM]);
+        weights.hand.copy_from_slice(&params[HAND_PARAM..PST_PARAM]);
+        for (kind, table) in weights.pst.iter_mut().enumerate() {
+            let start = PST_PARAM + kind * 25;
+            table.copy_from_slice(&params[start..start + 25]);
+        }
+        weights.king_defender = params[KING_DEFENDER_PARAM];
+        weights.king_attacker = params[KING_ATTACKER_PARAM];
+        weights.king_attacked_square = params[KING_ATTACKED_SQUARE_PARAM];
+        weights.mobility = params[MOBILITY_PARAM];
+        weights.tempo = params[TEMPO_PARAM];
+        weights
+    }
+
+    /// テキスト形式のパラメータを読み込む。書かれていない項目は標準値のまま
+    ///
+    /// 1行に「項目名 値...」を書く。`piece`（駒の価値10個）、`hand`（持ち駒の加点5個）、
+    /// `pst <駒>`（25マス分）と、`king_defender` などの1つの値の項目がある。
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut params = Self::default().to_params();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let err = |msg: String| format!("{}行目: {}", i + 1, msg);
+            let mut tokens = line.split_whitespace();
+            let name = tokens.next().unwrap_or_default();
+            let (start, len) = match name {
+                "piece" => (PIECE_PARAM, KIND_COUNT),
+                "hand" => (HAND_PARAM, 5),
+                "pst" => {
+                    let label = tokens.next().unwrap_or_default();
+                    let kind = KIND_LABELS
+                        .iter()
+                        .position(|&l| l == label)
+                        .ok_or_else(|| err(format!("駒の表記が不正です: {}", label)))?;
+                    (PST_PARAM + kind * 25, 25)
+                }
+                _ => SCALAR_LABELS
+                    .iter()
+                    .find(|&&(label, _)| label == name)
+                    .map(|&(_, index)| (index, 1))
+                    .ok_or_else(|| err(format!("不明な項目です: {}", name)))?,
+            };
+
+            let values = tokens
+                .map(|t| {
+                    t.parse::<i32>()
+                        .map_err(|_| err(format!("値が不正です: {}", t)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.len() != len {
+                return Err(err(format!(
+                    "{} には {} 個の値が必要です（{} 個）",
+                    name,
+                    len,
+                    values.len()
+                )));
+            }
+            params[start..start + len].copy_from_slice(&values);
+        }
+
+        Ok(Self::from_params(&params))
+    }
+
+    /// テキスト形式に変換する
+    pub fn to_text(&self) -> String {
+        let join = |values: &[i32]| {
+            values
+                .iter()
+                .map(i32::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let params = self.to_params();
+
+        let mut text = String::from("# minishogi-rs evaluation weights\n");
+        text.push_str(&format!("piece {}\n", join(&self.piece)));
+        text.push_str(&format!("hand {}\n", join(&self.hand)));
+        for (label, table) in KIND_LABELS.iter().zip(&self.pst) {
+            text.push_str(&format!("pst {} {}\n", label, join(table)));
+        }
+        for (label, index) in SCALAR_LABELS {
+            text.push_str(&format!("{} {}\n", label, params[index]));
+        }
+        text
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
+
+/// 駒の位置・玉の安全度・駒の働き・持ち駒・手番を加えた評価
+#[derive(Debug, Clone)]
+pub struct Positional {
+    pub weights: Weights,
+}
+
+impl Positional {
+    pub fn new(weights: Weights) -> Self {
+        Self { weights }
+    }
+
+    /// 評価値の各項の係数（パラメータの添字と係数の組、係数0の項は含まない）
+    /// `Weights::to_params` との内積が `evaluate` と一致する
+    pub fn features(state: &GameState, player: Player) -> Vec<(usize, i32)> {
+        let mut dense = [0; PARAM_COUNT];
+        terms(state, player, |index, coefficient| {
+            dense[index] += coefficient
+        });
+        dense
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c != 0)
+            .map(|(i, &c)| (i, c))
+            .collect()
+    }
+}
+
+impl Default for Positional {
+    fn default() -> Self {
+        Self::new(Weights::default())
+    }
+}
+
+impl Evaluator for Positional {
+    fn evaluate(&self, state: &GameState, player: Player) -> i32 {
+        let mut score = 0;
+        terms(state, player, |index, coefficient| {
+            score += self.weights.param(index) * coefficient
+        });
+        score
+    }
+}
+
+/// 評価値の各項を（パラメータの添字, 係数）として `add` に渡す
+fn terms(state: &GameState, player: Player, mut add: impl FnMut(usize, i32)) {
+    let mut kings = [None; 2];
+
+    // 駒割り・駒の位置・駒の働き
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Some(piece) = *cell else {
+                continue;
+            };
+            let s = sign(piece.owner);
+            let kind = kind_index(piece.piece_type, piece.promoted);
+            add(PIECE_PARAM + kind, s);
+            add(PST_PARAM + kind * 25 + pst_index(piece, x, y), s);
+
+            if piece.piece_type == PieceType::King {
+                kings[player_index(piece.owner)] = Some(Position::new(x, y));
+            } else {
+                let moves = rules::collect_piece_destinations(
+                    &state.board,
+                    piece.owner,
+                    piece.piece_type,
+                    piece.promoted,
+                    Position::new(x, y),
+                );
+                add(MOBILITY_PARAM, s * moves.into_iter().count() as i32);
+            }
+        }
+    }
+
+    // 持ち駒
+    for (i, &pt) in HAND_TYPES.iter().enumerate() {
+        let count = i32::from(state.sente_hand.get(pt)) - i32::from(state.gote_hand.get(pt));
+        if count != 0 {
+            add(PIECE_PARAM + kind_index(pt, false), count);
+            add(HAND_PARAM + i, count);
+        }
+    }
+
+    // 玉の安全度
+    for owner in [Player::Sente, Player::Gote] {
+        if let Some(king) = kings[player_index(owner)] {
+            let (defenders, attackers, attacked) = king_safety(state, owner, king);
+            let s = sign(owner);
+            add(KING_DEFENDER_PARAM, s * defenders);
+            add(KING_ATTACKER_PARAM, -s * attackers);
+            add(KING_ATTACKED_SQUARE_PARAM, -s * attacked);
+        }
+    }
+
+    add(TEMPO_PARAM, sign(player));
+}
+
+/// `owner` の玉の周囲の守り駒・攻め駒・敵の利きがあるマスの数
+fn king_safety(state: &GameState, owner: Player, king: Position) -> (i32, i32, i32) {
+    let enemy = rules::opponent_of(owner);
+    let mut attacked = [[false; 5]; 5];
+    let mut defenders = 0;
+    let mut attackers = 0;
+
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Some(piece) = *cell else {
+                continue;
+            };
+            let distance = king.x.abs_diff(x).max(king.y.abs_diff(y));
+            if piece.owner == owner {
+                if distance == 1 {
+                    defenders += 1;
+                }
+                continue;
+            }
+
+            if distance <= 2 {
+                attackers += 1;
+            }
+            let targets = rules::collect_piece_destinations(
+                &state.board,
+                enemy,
+                piece.piece_type,
+                piece.promoted,
+                Position::new(x, y),
+            );
+            for to in targets {
+                attacked[to.y][to.x] = true;
+            }
+        }
+    }
+
+    // 玉のマスと周囲8マス（敵の駒がいるマスは含まれない）
+    let mut attacked_squares = 0;
+    for (y, row) in attacked.iter().enumerate() {
+        for (x, &hit) in row.iter().enumerate() {
+            if hit && king.x.abs_diff(x) <= 1 && king.y.abs_diff(y) <= 1 {
+                attacked_squares += 1;
+            }
+        }
+    }
+
+    (defenders, attackers, attacked_squares)
+}
+
+fn sign(player: Player) -> i32 {
+    match player {
+        Player::Sente => 1,
+        Player::Gote => -1,
+    }
+}
@@ -0,0 +1,558 @@
+use crate::board::{self, Board, GameState, HAND_TYPES, Hand, MAX_HAND, Piece, PieceType, Player};
+use crate::kifu::{Ending, Kifu};
+use crate::rules::{self, Move, Position};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// KIF 形式の棋譜
+//
+// ```text
+// 手合割：五々将棋
+// 先手：human
+// 後手：alphabeta
+// 手数----指手---------消費時間--
+//    1 ２四歩(25)
+//    2 同　角(44)
+//    3 ２三歩打
+//    4 投了
+// まで3手で先手の勝ち
+// ```
+//
+// - マスは全角数字の筋と漢数字の段（１一～５五）、移動元は半角数字で `(25)` のように書く。
+// - 直前の手と同じマスへの手は「同　」、成る手は「成」、成れるのに成らない手は「不成」、駒打ちは「打」を付ける。
+// - 初期局面以外から始まる棋譜は、盤面図（`|v玉v金 ・ ・ ・|一` のような5段）と持駒の行で開始局面を書く。
+// - 読み込みでは `#`・`*`・`&` で始まる行と消費時間、変化手順（`変化：` 以降）を読み飛ばす。
+// - 文字コードは UTF-8 で読み書きする。
+
+/// 五々将棋の手合割
+const HANDICAP: &str = "五々将棋";
+/// 筋（1～5）の全角数字
+pub(crate) const FILE_DIGITS: [char; 5] = ['１', '２', '３', '４', '５'];
+/// 段（一～五）の漢数字
+pub(crate) const RANK_KANJI: [char; 5] = ['一', '二', '三', '四', '五'];
+/// 持駒の枚数に使う漢数字（1～10）
+const COUNT_KANJI: [char; 10] = ['一', '二', '三', '四', '五', '六', '七', '八', '九', '十'];
+
+/// マスを「２四」のような表記にする
+pub(crate) fn format_square(pos: Position) -> String {
+    format!("{}{}", FILE_DIGITS[4 - pos.x], RANK_KANJI[pos.y])
+}
+
+/// 全角・半角の筋の数字と漢数字・数字の段を読む
+pub(crate) fn parse_square(file: char, rank: char) -> Option<Position> {
+    let file = FILE_DIGITS.iter().position(|&c| c == file).or_else(|| {
+        file.to_digit(10)
+            .map(|d| d as usize)
+            .filter(|&d| (1..=5).contains(&d))
+            .map(|d| d - 1)
+    })?;
+    let rank = RANK_KANJI.iter().position(|&c| c == rank).or_else(|| {
+        rank.to_digit(10)
+            .map(|d| d as usize)
+            .filter(|&d| (1..=5).contains(&d))
+            .map(|d| d - 1)
+    })?;
+    Some(Position::new(4 - file, rank))
+}
+
+/// 指し手で使う駒の名前（成銀は2文字）
+pub(crate) fn piece_name(piece_type: PieceType, promoted: bool) -> &'static str {
+    match (piece_type, promoted) {
+        (PieceType::King, _) => "玉",
+        (PieceType::Gold, _) => "金",
+        (PieceType::Silver, false) => "銀",
+        (PieceType::Silver, true) => "成銀",
+        (PieceType::Bishop, false) => "角",
+        (PieceType::Bishop, true) => "馬",
+        (PieceType::Rook, false) => "飛",
+        (PieceType::Rook, true) => "龍",
+        (PieceType::Pawn, false) => "歩",
+        (PieceType::Pawn, true) => "と",
+    }
+}
+
+/// 駒の名前（別表記を含む）と駒の種類・成り。長い名前から順に照合する
+pub(crate) const PIECE_NAMES: [(&str, PieceType, bool); 13] = [
+    ("成銀", PieceType::Silver, true),
+    ("全", PieceType::Silver, true),
+    ("玉", PieceType::King, false),
+    ("王", PieceType::King, false),
+    ("金", PieceType::Gold, false),
+    ("銀", PieceType::Silver, false),
+    ("角", PieceType::Bishop, false),
+    ("馬", PieceType::Bishop, true),
+    ("飛", PieceType::Rook, false),
+    ("龍", PieceType::Rook, true),
+    ("竜", PieceType::Rook, true),
+    ("歩", PieceType::Pawn, false),
+    ("と", PieceType::Pawn, true),
+];
+
+/// 盤面図で使う1文字の駒の名前
+fn board_char(piece_type: PieceType, promoted: bool) -> char {
+    match (piece_type, promoted) {
+        (PieceType::Silver, true) => '全',
+        _ => piece_name(piece_type, promoted).chars().next().unwrap(),
+    }
+}
+
+// ---- 書き出し ----
+
+/// 1手を KIF の指し手の表記にする（`previous` は直前の手）
+pub fn format_move(state: &GameState, mv: Move, previous: Option<Move>) -> String {
+    match mv {
+        Move::Drop(to, piece_type) => {
+            format!("{}{}打", format_square(to), piece_name(piece_type, false))
+        }
+        Move::To(from, to, promote) => {
+            let piece = state.board[from.y][from.x].expect("移動元には駒がある");
+            let square = match previous {
+                Some(Move::To(_, prev_to, _) | Move::Drop(prev_to, _)) if prev_to == to => {
+                    "同　".to_string()
+                }
+                _ => format_square(to),
+            };
+            // 成れる手で成らなかったときは「不成」を付ける
+            let promotable =
+                rules::generate_legal_moves(state, piece.owner).contains(&Move::To(from, to, true));
+            let suffix = if promote {
+                "成"
+            } else if promotable {
+                "不成"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}({}{})",
+                square,
+                piece_name(piece.piece_type, piece.promoted),
+                suffix,
+                5 - from.x,
+                from.y + 1
+            )
+        }
+    }
+}
+
+fn format_hand(hand: &Hand) -> String {
+    let items: Vec<String> = HAND_TYPES
+        .iter()
+        .rev()
+        .filter(|&&t| hand.get(t) > 0)
+        .map(|&t| {
+            let name = piece_name(t, false);
+            match hand.get(t) {
+                1 => name.to_string(),
+                n => format!("{}{}", name, COUNT_KANJI[usize::from(n).min(10) - 1]),
+            }
+        })
+        .collect();
+    if items.is_empty() {
+        "なし".to_string()
+    } else {
+        items.join("　")
+    }
+}
+
+/// 開始局面の盤面図
+fn format_board(text: &mut String, state: &GameState, player: Player) {
+    writeln!(text, "後手の持駒：{}", format_hand(&state.gote_hand)).unwrap();
+    writeln!(text, "  ５ ４ ３ ２ １").unwrap();
+    writeln!(text, "+---------------+").unwrap();
+    for (y, row) in state.board.iter().enumerate() {
+        text.push('|');
+        for cell in row {
+            match cell {
+                Some(p) => {
+                    text.push(if p.owner == Player::Gote { 'v' } else { ' ' });
+                    text.push(board_char(p.piece_type, p.promoted));
+                }
+                None => text.push_str(" ・"),
+            }
+        }
+        writeln!(text, "|{}", RANK_KANJI[y]).unwrap();
+    }
+    writeln!(text, "+---------------+").unwrap();
+    writeln!(text, "先手の持駒：{}", format_hand(&state.sente_hand)).unwrap();
+    if player == Player::Gote {
+        writeln!(text, "後手番").unwrap();
+    }
+}
+
+/// 棋譜を KIF 形式のテキストにする
+pub fn to_text(kifu: &Kifu) -> String {
+    let mut text = String::new();
+    writeln!(text, "# ---- minishogi-rs 棋譜ファイル ----").unwrap();
+    writeln!(text, "手合割：{}", HANDICAP).unwrap();
+    writeln!(text, "先手：{}", kifu.players[0]).unwrap();
+    writeln!(text, "後手：{}", kifu.players[1]).unwrap();
+    if !kifu.is_standard_start() {
+        format_board(&mut text, &kifu.start, kifu.start_player);
+    }
+    writeln!(text, "手数----指手---------消費時間--").unwrap();
+
+    let positions = kifu.positions();
+    let mut previous = None;
+    for (i, (&mv, (state, _))) in kifu.moves.iter().zip(&positions).enumerate() {
+        writeln!(text, "{:>4} {}", i + 1, format_move(state, mv, previous)).unwrap();
+        previous = Some(mv);
+    }
+
+    let n = kifu.moves.len();
+    if let Some(ending) = kifu.ending {
+        let word = match ending {
+            Ending::Resign => "投了",
+            Ending::Checkmate => "詰み",
+            Ending::Draw => "持将棋",
+            Ending::Interrupted => "中断",
+        };
+        writeln!(text, "{:>4} {}", n + 1, word).unwrap();
+    }
+    match (kifu.winner(), kifu.ending) {
+        (Some(Player::Sente), _) => writeln!(text, "まで{}手で先手の勝ち", n).unwrap(),
+        (Some(Player::Gote), _) => writeln!(text, "まで{}手で後手の勝ち", n).unwrap(),
+        (None, Some(Ending::Draw)) => writeln!(text, "まで{}手で持将棋", n).unwrap(),
+        (None, Some(_)) => writeln!(text, "まで{}手で中断", n).unwrap(),
+        (None, None) => {}
+    }
+    text
+}
+
+// ---- 読み込み ----
+
+/// KIF の指し手の表記を読む（`previous` は直前の手）
+pub fn parse_move(
+    token: &str,
+    state: &GameState,
+    player: Player,
+    previous: Option<Move>,
+) -> Result<Move, String> {
+    let err = || format!("指し手を読めません: {}", token);
+
+    let (to, rest) = if let Some(rest) = token.strip_prefix('同') {
+        let to = match previous {
+            Some(Move::To(_, to, _) | Move::Drop(to, _)) => to,
+            None => return Err("「同」の前に指し手がありません".to_string()),
+        };
+        (to, rest.trim_start_matches([' ', '　']))
+    } else {
+        let mut chars = token.chars();
+        let (Some(file), Some(rank)) = (chars.next(), chars.next()) else {
+            return Err(err());
+        };
+        (parse_square(file, rank).ok_or_else(err)?, chars.as_str())
+    };
+
+    let &(name, piece_type, promoted) = PIECE_NAMES
+        .iter()
+        .find(|(name, _, _)| rest.starts_with(name))
+        .ok_or_else(err)?;
+    let rest = &rest[name.len()..];
+
+    let (promote, drop, rest) = if let Some(rest) = rest.strip_prefix("打") {
+        (false, true, rest)
+    } else if let Some(rest) = rest.strip_prefix("不成") {
+        (false, false, rest)
+    } else if let Some(rest) = rest.strip_prefix("成") {
+        (true, false, rest)
+    } else {
+        (false, false, rest)
+    };
+
+    let from = match rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+        Some(digits) => {
+            let mut chars = digits.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(file), Some(rank), None) => Some(parse_square(file, rank).ok_or_else(err)?),
+                _ => return Err(err()),
+            }
+        }
+        None if rest.is_empty() => None,
+        None => return Err(err()),
+    };
+
+    match from {
+        Some(from) if !drop => {
+            let piece = state.board[from.y][from.x]
+                .filter(|p| p.owner == player)
+                .ok_or_else(|| {
+                    format!(
+                        "{}({}{}) に手番側の駒がありません",
+                        name,
+                        5 - from.x,
+                        from.y + 1
+                    )
+                })?;
+            if piece.piece_type != piece_type || piece.promoted != promoted {
+                return Err(format!(
+                    "移動元の駒は{}です: {}",
+                    piece_name(piece.piece_type, piece.promoted),
+                    token
+                ));
+            }
+            Ok(Move::To(from, to, promote))
+        }
+        Some(_) => Err(err()),
+        // 「打」は盤上の同じ駒が動けないときには省略されることがある
+        None if !promoted && piece_type != PieceType::King => Ok(Move::Drop(to, piece_type)),
+        None => Err(err()),
+    }
+}
+
+fn parse_hand(value: &str) -> Result<Hand, String> {
+    let mut hand = Hand::new();
+    for item in value
+        .split([' ', '　'])
+        .filter(|s| !s.is_empty() && *s != "なし")
+    {
+        let mut chars = item.chars();
+        let name = chars.next().unwrap();
+        let &(_, piece_type, _) = PIECE_NAMES
+            .iter()
+            .find(|(n, _, promoted)| !promoted && n.starts_with(name))
+            .filter(|(_, t, _)| *t != PieceType::King)
+            .ok_or_else(|| format!("持駒を読めません: {}", item))?;
+        let count = match chars.as_str() {
+            "" => 1,
+            n => COUNT_KANJI
+                .iter()
+                .position(|&c| n.starts_with(c) && n.chars().count() == 1)
+                .map(|i| i + 1)
+                .or_else(|| n.parse().ok())
+                .ok_or_else(|| format!("持駒の枚数を読めません: {}", item))?,
+        };
+        if count > MAX_HAND - hand.get(piece_type) as usize {
+            return Err(format!(
+                "{}が{}枚を超えています",
+                piece_name(piece_type, false),
+                MAX_HAND
+            ));
+        }
+        for _ in 0..count {
+            hand.add(piece_type);
+        }
+    }
+    Ok(hand)
+}
+
+/// 盤面図の1段（`|v玉v金 ・ ・ ・|一`）を読む
+fn parse_board_row(line: &str) -> Result<[Option<Piece>; 5], String> {
+    let cells: Vec<char> = line
+        .strip_prefix('|')
+        .and_then(|r| r.split('|').next())
+        .ok_or_else(|| format!("盤面図の段を読めません: {}", line))?
+        .chars()
+        .collect();
+    if cells.len() != 10 {
+        return Err(format!("盤面図の段は5マスです: {}", line));
+    }
+
+    let mut row = [None; 5];
+    for (x, cell) in cells.chunks(2).enumerate() {
+        let owner = match cell[0] {
+            'v' | 'V' => Player::Gote,
+            _ => Player::Sente,
+        };
+        if cell[1] == '・' {
+            continue;
+        }
+        let &(_, piece_type, promoted) = PIECE_NAMES
+            .iter()
+            .find(|(name, _, _)| name.chars().count() == 1 && name.starts_with(cell[1]))
+            .ok_or_else(|| format!("盤面図の駒を読めません: {}", cell[1]))?;
+        row[x] = Some(Piece {
+            piece_type,
+            owner,
+            promoted,
+        });
+    }
+    Ok(row)
+}
+
+/// KIF 形式のテキストを読み込み、指し手を合法手か確かめながら進める
+pub fn parse(text: &str) -> Result<Kifu, String> {
+    let mut players = [String::new(), String::new()];
+    let mut board: Option<(Board, usize)> = None;
+    let mut hands = (Hand::new(), Hand::new());
+    let mut start_player = Player::Sente;
+    let mut kifu: Option<Kifu> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let err = |e: String| format!("{}行目: {}", i + 1, e);
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with(['#', '*', '&']) {
+            continue;
+        }
+        if trimmed.starts_with("変化：") {
+            break;
+        }
+        if trimmed.starts_with("まで") {
+            continue;
+        }
+
+        // 指し手の行
+        if trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            if kifu.is_none() {
+                kifu = Some(start(&players, board, hands, start_player).map_err(err)?);
+            }
+            let kifu = kifu.as_mut().unwrap();
+            let digits = trimmed
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(trimmed.len());
+            if kifu.ending.is_some() {
+                return Err(err("終局の後に指し手があります".to_string()));
+            }
+            let number: usize = trimmed[..digits]
+                .parse()
+                .map_err(|_| err("手数を読めません".to_string()))?;
+            if number != kifu.moves.len() + 1 {
+                return Err(err(format!(
+                    "手数が {} ではありません: {}",
+                    kifu.moves.len() + 1,
+                    number
+                )));
+            }
+
+            let body = trimmed[digits..].trim_start();
+            let token = match body.strip_prefix('同') {
+                Some(rest) => {
+                    let rest = rest.trim_start_matches([' ', '　']);
+                    format!("同{}", rest.split_whitespace().next().unwrap_or_default())
+                }
+                None => body
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+
+            let ending = match token.as_str() {
+                "投了" | "切れ負け" | "反則負け" => Some(Ending::Resign),
+                "詰み" => Some(Ending::Checkmate),
+                "持将棋" | "千日手" => Some(Ending::Draw),
+                "中断" => Some(Ending::Interrupted),
+                _ => None,
+            };
+            if ending.is_some() {
+                kifu.ending = ending;
+                continue;
+            }
+
+            let (state, player) = kifu.position();
+            let mv = parse_move(&token, &state, player, kifu.moves.last().copied()).map_err(err)?;
+            kifu.push(mv)
+                .map_err(|e| err(format!("{}: {}", token, e)))?;
+            continue;
+        }
+        if kifu.is_some() {
+            return Err(err(format!(
+                "指し手の後に解釈できない行があります: {}",
+                trimmed
+            )));
+        }
+
+        // 盤面図
+        if trimmed.starts_with('|') {
+            let (b, rows) = board.get_or_insert(([[None; 5]; 5], 0));
+            if *rows >= 5 {
+                return Err(err("盤面図の段が 5 を超えています".to_string()));
+            }
+            b[*rows] = parse_board_row(trimmed).map_err(err)?;
+            *rows += 1;
+            continue;
+        }
+        if trimmed.starts_with(['+', '５']) {
+            continue;
+        }
+        match trimmed {
+            "先手番" => {
+                start_player = Player::Sente;
+                continue;
+            }
+            "後手番" => {
+                start_player = Player::Gote;
+                continue;
+            }
+            _ => {}
+        }
+        if trimmed.starts_with("手数") {
+            continue;
+        }
+
+        // ヘッダ
+        let Some((key, value)) = trimmed.split_once(['：', ':']) else {
+            return Err(err(format!("解釈できない行です: {}", trimmed)));
+        };
+        let value = value.trim();
+        match key {
+            "手合割" if !matches!(value, "五々将棋" | "五五将棋" | "5五将棋" | "5五") =>
+            {
+                return Err(err(format!("対応していない手合割です: {}", value)));
+            }
+            "先手" | "下手" => players[0] = value.to_string(),
+            "後手" | "上手" => players[1] = value.to_string(),
+            "先手の持駒" | "下手の持駒" => hands.0 = parse_hand(value).map_err(err)?,
+            "後手の持駒" | "上手の持駒" => hands.1 = parse_hand(value).map_err(err)?,
+            "手番" => {
+                start_player = match value {
+                    "先手" | "下手" => Player::Sente,
+                    "後手" | "上手" => Player::Gote,
+                    _ => return Err(err(format!("手番を読めません: {}", value))),
+                }
+            }
+            // 開始日時・棋戦などは読み飛ばす
+            _ => {}
+        }
+    }
+
+    match kifu {
+        Some(kifu) => Ok(kifu),
+        None => start(&players, board, hands, start_player),
+    }
+}
+
+/// ヘッダと盤面図から指し手のない棋譜を作る
+fn start(
+    players: &[String; 2],
+    board: Option<(Board, usize)>,
+    hands: (Hand, Hand),
+    player: Player,
+) -> Result<Kifu, String> {
+    let state = match board {
+        Some((_, rows)) if rows != 5 => {
+            return Err(format!("盤面図の段が {} しかありません", rows));
+        }
+        Some((board, _)) => GameState {
+            board,
+            sente_hand: hands.0,
+            gote_hand: hands.1,
+        },
+        None => board::init(),
+    };
+    match state.excess_piece() {
+        Some(PieceType::King) => return Err("玉は先手・後手とも1枚までです".to_string()),
+        Some(piece_type) => {
+            return Err(format!(
+                "{}が{}枚を超えています",
+                piece_name(piece_type, false),
+                MAX_HAND
+            ));
+        }
+        None => {}
+    }
+    let mut kifu = Kifu::new(state, player);
+    kifu.players = players.clone();
+    Ok(kifu)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Kifu> {
+    let text = fs::read_to_string(path)?;
+    parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save<P: AsRef<Path>>(path: P, kifu: &Kifu) -> io::Result<()> {
+    fs::write(path, to_text(kifu))
+}
@@ -0,0 +1,108 @@
+use crate::board::{self, GameState, Player};
+use crate::rules::{self, Move};
+
+// 棋譜
+//
+// KIF・CSA 形式で読み書きする1局分の記録。開始局面と手番、対局者名、指し手、終局の理由を持つ。
+// 指し手は開始局面から `rules` で進めて確かめたものだけを入れる。
+
+/// 終局の理由（指し手で王を取った場合は指し手から分かるので持たない）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    /// 手番側の投了
+    Resign,
+    /// 手番側が詰んでいる
+    Checkmate,
+    /// 引き分け（手数の上限など）
+    Draw,
+    /// 対局の中断
+    Interrupted,
+}
+
+/// 1局分の棋譜
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kifu {
+    /// [先手, 後手] の対局者名
+    pub players: [String; 2],
+    /// 開始局面
+    pub start: GameState,
+    /// 開始局面の手番
+    pub start_player: Player,
+    pub moves: Vec<Move>,
+    /// 終局の理由（対局中なら `None`）
+    pub ending: Option<Ending>,
+}
+
+impl Kifu {
+    /// 指し手のない棋譜
+    pub fn new(start: GameState, start_player: Player) -> Self {
+        Self {
+            players: [String::new(), String::new()],
+            start,
+            start_player,
+            moves: Vec::new(),
+            ending: None,
+        }
+    }
+
+    /// 初期局面から始まるか
+    pub fn is_standard_start(&self) -> bool {
+        self.start == board::init() && self.start_player == Player::Sente
+    }
+
+    /// 合法手かどうかを確かめて1手進める
+    pub fn push(&mut self, mv: Move) -> Result<(), String> {
+        let (state, player) = self.position();
+        if is_finished(&state) {
+            return Err("王が取られた後の指し手です".to_string());
+        }
+        if !rules::generate_legal_moves(&state, player).contains(&mv) {
+            return Err("合法手ではありません".to_string());
+        }
+        self.moves.push(mv);
+        Ok(())
+    }
+
+    /// 指す前の局面と手番を、開始局面から順に返す（最後に現局面を加える）
+    pub fn positions(&self) -> Vec<(GameState, Player)> {
+        let mut positions = Vec::with_capacity(self.moves.len() + 1);
+        let (mut state, mut player) = (self.start, self.start_player);
+        for &mv in &self.moves {
+            positions.push((state, player));
+            state = rules::make_move(&state, mv, player);
+            player = rules::opponent_of(player);
+        }
+        positions.push((state, player));
+        positions
+    }
+
+    /// 現局面と手番
+    pub fn position(&self) -> (GameState, Player) {
+        self.positions().pop().expect("開始局面は必ずある")
+    }
+
+    /// 勝者（引き分け・対局中なら `None`）
+    pub fn winner(&self) -> Option<Player> {
+        let (state, player) = self.position();
+        if let Some(w) = winner(&state) {
+            return Some(w);
+        }
+        match self.ending {
+            Some(Ending::Resign | Ending::Checkmate) => Some(rules::opponent_of(player)),
+            Some(Ending::Draw | Ending::Interrupted) | None => None,
+        }
+    }
+}
+
+/// どちらかの王が取られているか
+pub fn is_finished(state: &GameState) -> bool {
+    winner(state).is_some()
+}
+
+/// 相手の王を取った側（どちらの王も盤上にあれば `None`）
+pub fn winner(state: &GameState) -> Option<Player> {
+    [Player::Sente, Player::Gote]
+        .into_iter()
+        .find(|&p| !rules::has_king(state, p))
+        .map(rules::opponent_of)
+}
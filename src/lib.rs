@@ -1,4 +1,28 @@
+pub mod alphazero;
+pub mod bitboard;
 pub mod board;
+pub mod book;
+pub mod cli;
+pub mod csa;
+pub mod csaprotocol;
+pub mod engine;
+pub mod eval;
+pub mod kif;
+pub mod kifu;
+pub mod locale;
+pub mod mate;
+pub mod nnue;
+pub mod notation;
+pub mod perft;
+pub mod rl;
 pub mod rules;
 pub mod search;
+pub mod selfplay;
+pub mod sfen;
+pub mod tablebase;
+pub mod tournament;
+pub mod tsume;
+pub mod tune;
 pub mod ui;
+pub mod usi;
+pub mod zobrist;
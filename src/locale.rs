@@ -0,0 +1,93 @@
+use crate::board::{PieceType, Player};
+use crate::notation::Notation;
+use std::env;
+
+// 表示言語
+//
+// 対局画面のメッセージ・駒の名前・持ち駒を日本語か英語で表示する。英語では CJK フォントがなくても読めるよう、
+// 盤上の駒は英字（`K`・`G`・`+S` など）、指し手は Hodges 表記にする。
+// 言語は `--lang` か環境変数 `MINISHOGI_LANG` で選ぶ。
+
+/// 表示言語を選ぶ環境変数
+pub const LANG_ENV: &str = "MINISHOGI_LANG";
+
+/// 表示言語
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Japanese,
+    English,
+}
+
+impl Locale {
+    /// `ja`（`japanese`）か `en`（`english`）。`en_US.UTF-8` のような地域・文字コード付きも読む
+    ///
+    /// 読めなければ `None`（エラーの文は呼び出し側が表示言語で作る）。
+    pub fn parse(s: &str) -> Option<Self> {
+        let language = s.split(['_', '-', '.']).next().unwrap_or("");
+        match language.to_ascii_lowercase().as_str() {
+            "ja" | "japanese" => Some(Locale::Japanese),
+            "en" | "english" => Some(Locale::English),
+            _ => None,
+        }
+    }
+
+    /// 環境変数 `MINISHOGI_LANG` の言語（未設定か読めなければ日本語）
+    pub fn from_env() -> Self {
+        env::var(LANG_ENV)
+            .ok()
+            .and_then(|s| Locale::parse(&s))
+            .unwrap_or_default()
+    }
+
+    /// 日本語と英語の文字列のうち、この言語の方
+    pub fn text<'a>(self, ja: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Locale::Japanese => ja,
+            Locale::English => en,
+        }
+    }
+
+    /// 指定がないときの指し手の表記（英語では英字だけで書ける Hodges 表記）
+    pub fn default_notation(self) -> Notation {
+        match self {
+            Locale::Japanese => Notation::Japanese,
+            Locale::English => Notation::Western,
+        }
+    }
+
+    /// 手番の名前
+    pub fn player_name(self, player: Player) -> &'static str {
+        match player {
+            Player::Sente => self.text("先手", "Sente"),
+            Player::Gote => self.text("後手", "Gote"),
+        }
+    }
+
+    /// 盤面と持ち駒に表示する駒の名前（英語では `K`・`+S` のような英字）
+    pub fn piece_name(self, piece_type: PieceType, promoted: bool) -> &'static str {
+        match (piece_type, promoted) {
+            (PieceType::King, _) => self.text("王", "K"),
+            (PieceType::Gold, _) => self.text("金", "G"),
+            (PieceType::Silver, false) => self.text("銀", "S"),
+            (PieceType::Silver, true) => self.text("全", "+S"),
+            (PieceType::Bishop, false) => self.text("角", "B"),
+            (PieceType::Bishop, true) => self.text("馬", "+B"),
+            (PieceType::Rook, false) => self.text("飛", "R"),
+            (PieceType::Rook, true) => self.text("龍", "+R"),
+            (PieceType::Pawn, false) => self.text("歩", "P"),
+            (PieceType::Pawn, true) => self.text("と", "+P"),
+        }
+    }
+}
+
+/// 表示言語に合わせて文字列を作る: `tr!(locale, "{}の番です", "{} to move", label)`
+#[macro_export]
+macro_rules! tr {
+    ($locale:expr, $ja:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $locale {
+            $crate::locale::Locale::Japanese => format!($ja $(, $arg)*),
+            $crate::locale::Locale::English => format!($en $(, $arg)*),
+        }
+    };
+}
@@ -0,0 +1,134 @@
+use crate::board::{GameState, Player};
+use crate::rules::{self, Move};
+
+// 詰み探索（詰将棋ソルバー）
+// 攻め方は王手のみ、玉方は王手を回避する手のみを読む AND/OR 探索。
+// 手数はすべて攻め方・玉方の手を合わせた数（plies）で表す。
+
+/// 攻め方の王手になる手（自玉を王手に晒す手は除く）
+fn check_moves(state: &GameState, attacker: Player) -> Vec<Move> {
+    let mut moves = rules::generate_checks(state, attacker);
+    moves.retain(|&mv| !rules::is_in_check(&rules::make_move(state, mv, attacker), attacker));
+    moves
+}
+
+/// 攻め方手番で `depth` 手以内に詰むなら最短手数を返す
+fn attack(state: &GameState, attacker: Player, depth: u32) -> Option<u32> {
+    if depth == 0 {
+        return None;
+    }
+
+    let mut best: Option<u32> = None;
+    for mv in check_moves(state, attacker) {
+        let limit = best.map_or(depth, |b| b - 1);
+        if limit == 0 {
+            break;
+        }
+        let next = rules::make_move(state, mv, attacker);
+        if let Some(d) = defend(&next, attacker, limit - 1) {
+            best = Some(d + 1);
+        }
+    }
+    best
+}
+
+/// 玉方手番（王手がかかっている）で `depth` 手以内に詰むなら最長抵抗の手数を返す
+fn defend(state: &GameState, attacker: Player, depth: u32) -> Option<u32> {
+    let defender = rules::opponent_of(attacker);
+    let evasions = rules::generate_evasions(state, defender);
+    if evasions.is_empty() {
+        return Some(0);
+    }
+    if depth < 2 {
+        return None;
+    }
+
+    let mut worst = 0;
+    for mv in evasions {
+        let next = rules::make_move(state, mv, defender);
+        let d = attack(&next, attacker, depth - 1)?;
+        worst = worst.max(d + 1);
+    }
+    Some(worst)
+}
+
+/// `max_plies` 手以内の最短の詰み手数を返す
+pub fn mate_length(state: &GameState, attacker: Player, max_plies: u32) -> Option<u32> {
+    // 反復深化で短い詰みから探す
+    (1..=max_plies)
+        .step_by(2)
+        .find_map(|depth| attack(state, attacker, depth))
+}
+
+/// `max_plies` 手以内に詰む攻め方の初手と、その手からの詰み手数を列挙する
+pub fn mating_moves(state: &GameState, attacker: Player, max_plies: u32) -> Vec<(Move, u32)> {
+    if max_plies == 0 {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    for mv in check_moves(state, attacker) {
+        let next = rules::make_move(state, mv, attacker);
+        let found = (0..max_plies)
+            .step_by(2)
+            .find_map(|depth| defend(&next, attacker, depth));
+        if let Some(d) = found {
+            result.push((mv, d + 1));
+        }
+    }
+    result.sort_by_key(|&(_, plies)| plies);
+    result
+}
+
+/// 玉方の最長抵抗となる応手を返す。`max_plies` 以内で詰まない応手があればそれを優先する
+pub fn best_defence(state: &GameState, attacker: Player, max_plies: u32) -> Option<Move> {
+    let defender = rules::opponent_of(attacker);
+    let mut best: Option<(Move, Option<u32>)> = None;
+
+    for mv in rules::generate_evasions(state, defender) {
+        let next = rules::make_move(state, mv, defender);
+        let length = mate_length(&next, attacker, max_plies.saturating_sub(1));
+        let is_better = match (best, length) {
+            (None, _) => true,
+            (Some((_, Some(_))), None) => true,
+            (Some((_, Some(b))), Some(l)) => l > b,
+            (Some((_, None)), _) => false,
+        };
+        if is_better {
+            best = Some((mv, length));
+        }
+    }
+
+    best.map(|(mv, _)| mv)
+}
+
+/// 最短の詰み手順（玉方は最長抵抗）を返す
+pub fn solve(state: &GameState, attacker: Player, max_plies: u32) -> Option<Vec<Move>> {
+    let length = mate_length(state, attacker, max_plies)?;
+    let defender = rules::opponent_of(attacker);
+
+    let mut line = Vec::new();
+    let mut current = *state;
+    let mut remaining = length;
+
+    loop {
+        let (mv, plies) = mating_moves(&current, attacker, remaining)
+            .into_iter()
+            .next()
+            .expect("mate_length で詰みを確認済み");
+        line.push(mv);
+        current = rules::make_move(&current, mv, attacker);
+        if plies == 1 {
+            break;
+        }
+        remaining = plies - 1;
+
+        let reply =
+            best_defence(&current, attacker, remaining).expect("詰みまでの途中で応手が存在する");
+        line.push(reply);
+        current = rules::make_move(&current, reply, defender);
+        remaining -= 1;
+    }
+
+    Some(line)
+}
@@ -0,0 +1,693 @@
+use crate::board::{GameState, HAND_TYPES, MAX_HAND, Piece, PieceType, Player, player_index};
+use crate::eval::{Evaluator, kind_index};
+use crate::rules::{self, Move, SearchState, Undo};
+use crate::search::Rng;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// NNUE 風のニューラルネットワーク評価関数
+//
+// 入力は手番ごとの視点で見た疎な特徴で、
+//   - 自玉のマス × 駒（自分・相手 × 成りを区別した10種）× マス
+//   - 持ち駒（自分・相手 × 5種 × 枚数。n 枚持っていれば 1..=n 枚目の特徴がすべて立つ）
+// 後手の視点では盤を180度回して数える。
+//
+// ネットワークは 入力 → 特徴変換層(HIDDEN, 両視点で共有) → [手番側, 相手側] を連結 → 中間層(HIDDEN2) → 出力 で、
+// 活性化は 0..1 でのクリップ。出力は手番側から見た勝率のロジットで、×400 して評価値にする
+// （MCTS のプレイアウトと同じ sigmoid のスケール）。
+//
+// 推論は整数で行う。特徴変換層は i16（1.0 = ACTIVATION_SCALE）、中間層と出力層の重みは i8（1.0 = WEIGHT_SCALE）。
+// 特徴変換層の出力（アキュムレータ）は指し手ごとに差分で更新する。
+
+/// 特徴変換層の出力の数（1視点あたり）
+pub const HIDDEN: usize = 32;
+/// 中間層の出力の数
+pub const HIDDEN2: usize = 16;
+
+const PIECE_FEATURES: usize = 2 * 10;
+const BOARD_FEATURES: usize = 25 * PIECE_FEATURES * 25;
+/// 入力の特徴の数
+pub const INPUTS: usize = BOARD_FEATURES + 2 * 5 * MAX_HAND;
+
+/// 活性化 1.0 に対応する整数値
+const ACTIVATION_SCALE: i32 = 127;
+/// 中間層・出力層の重み 1.0 に対応する整数値
+const WEIGHT_SCALE: i32 = 64;
+/// 出力（ロジット）を評価値に変換する係数
+const OUTPUT_SCALE: i32 = 400;
+
+/// 重みファイルの先頭
+const MAGIC: &[u8; 4] = b"MSNN";
+const VERSION: u32 = 1;
+
+/// 視点側から見たマス（後手視点は盤を180度回す）
+fn orient(perspective: Player, sq: usize) -> usize {
+    match perspective {
+        Player::Sente => sq,
+        Player::Gote => 24 - sq,
+    }
+}
+
+fn relative(perspective: Player, owner: Player) -> usize {
+    usize::from(perspective != owner)
+}
+
+fn board_feature(perspective: Player, king_sq: usize, piece: Piece, sq: usize) -> usize {
+    let piece_index =
+        relative(perspective, piece.owner) * 10 + kind_index(piece.piece_type, piece.promoted);
+    (king_sq * PIECE_FEATURES + piece_index) * 25 + orient(perspective, sq)
+}
+
+/// `owner` の `piece_type` の持ち駒の `n` 枚目（1 始まり）の特徴
+fn hand_feature(perspective: Player, owner: Player, piece_type: PieceType, n: u8) -> Option<usize> {
+    let n = usize::from(n);
+    let hand_index = HAND_TYPES.iter().position(|&pt| pt == piece_type)?;
+    (1..=MAX_HAND).contains(&n).then(|| {
+        BOARD_FEATURES + (relative(perspective, owner) * 5 + hand_index) * MAX_HAND + (n - 1)
+    })
+}
+
+/// 視点側の玉のマス（視点側から見た向き。玉がなければ 0）
+fn king_square(state: &GameState, perspective: Player) -> usize {
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if cell.is_some_and(|p| p.piece_type == PieceType::King && p.owner == perspective) {
+                return orient(perspective, y * 5 + x);
+            }
+        }
+    }
+    0
+}
+
+/// 視点 `perspective` で立っている特徴
+pub fn active_features(state: &GameState, perspective: Player) -> Vec<usize> {
+    let king_sq = king_square(state, perspective);
+    let mut features = Vec::with_capacity(32);
+
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Some(piece) = *cell {
+                features.push(board_feature(perspective, king_sq, piece, y * 5 + x));
+            }
+        }
+    }
+    for owner in [Player::Sente, Player::Gote] {
+        let hand = state.get_hand(owner);
+        for pt in HAND_TYPES {
+            for n in 1..=hand.get(pt) {
+                features.extend(hand_feature(perspective, owner, pt, n));
+            }
+        }
+    }
+
+    features
+}
+
+// ---- 量子化したネットワーク ----
+
+/// 特徴変換層の出力（1視点分）
+type Accumulator = [i16; HIDDEN];
+
+/// 整数で推論するネットワーク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    ft_bias: Vec<i16>,
+    /// [入力][HIDDEN]
+    ft_weights: Vec<i16>,
+    /// [HIDDEN2][2 * HIDDEN]
+    l2_weights: Vec<i8>,
+    l2_bias: Vec<i32>,
+    out_weights: Vec<i8>,
+    out_bias: i32,
+}
+
+impl Network {
+    /// すべての重みが 0 のネットワーク（評価値は常に 0）
+    pub fn zeroed() -> Self {
+        Self {
+            ft_bias: vec![0; HIDDEN],
+            ft_weights: vec![0; INPUTS * HIDDEN],
+            l2_weights: vec![0; HIDDEN2 * 2 * HIDDEN],
+            l2_bias: vec![0; HIDDEN2],
+            out_weights: vec![0; HIDDEN2],
+            out_bias: 0,
+        }
+    }
+
+    fn refresh(&self, state: &GameState, perspective: Player) -> Accumulator {
+        let mut acc = [0; HIDDEN];
+        acc.copy_from_slice(&self.ft_bias);
+        for f in active_features(state, perspective) {
+            self.add_feature(&mut acc, f);
+        }
+        acc
+    }
+
+    fn add_feature(&self, acc: &mut Accumulator, feature: usize) {
+        let weights = &self.ft_weights[feature * HIDDEN..(feature + 1) * HIDDEN];
+        for (a, &w) in acc.iter_mut().zip(weights) {
+            *a += w;
+        }
+    }
+
+    fn sub_feature(&self, acc: &mut Accumulator, feature: usize) {
+        let weights = &self.ft_weights[feature * HIDDEN..(feature + 1) * HIDDEN];
+        for (a, &w) in acc.iter_mut().zip(weights) {
+            *a -= w;
+        }
+    }
+
+    /// アキュムレータから手番側の評価値を求める
+    fn output(&self, own: &Accumulator, other: &Accumulator) -> i32 {
+        let clipped = |a: i16| i32::from(a).clamp(0, ACTIVATION_SCALE);
+        let mut input = [0; 2 * HIDDEN];
+        for (x, &a) in input.iter_mut().zip(own.iter().chain(other)) {
+            *x = clipped(a);
+        }
+
+        let mut out = self.out_bias;
+        for j in 0..HIDDEN2 {
+            let weights = &self.l2_weights[j * 2 * HIDDEN..(j + 1) * 2 * HIDDEN];
+            let sum: i32 = self.l2_bias[j]
+                + weights
+                    .iter()
+                    .zip(&input)
+                    .map(|(&w, &a)| i32::from(w) * a)
+                    .sum::<i32>();
+            let hidden = (sum / WEIGHT_SCALE).clamp(0, ACTIVATION_SCALE);
+            out += i32::from(self.out_weights[j]) * hidden;
+        }
+
+        out * OUTPUT_SCALE / (ACTIVATION_SCALE * WEIGHT_SCALE)
+    }
+
+    /// 差分計算を使わずに評価する（先手から見た評価値）
+    pub fn evaluate_full(&self, state: &GameState, player: Player) -> i32 {
+        let own = self.refresh(state, player);
+        let other = self.refresh(state, rules::opponent_of(player));
+        sente_score(self.output(&own, &other), player)
+    }
+
+    /// バイナリ形式に変換する（リトルエンディアン）
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        for n in [VERSION, INPUTS as u32, HIDDEN as u32, HIDDEN2 as u32] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        for &w in self.ft_bias.iter().chain(&self.ft_weights) {
+            bytes.extend_from_slice(&w.to_le_bytes());
+        }
+        bytes.extend(self.l2_weights.iter().map(|&w| w as u8));
+        for &b in &self.l2_bias {
+            bytes.extend_from_slice(&b.to_le_bytes());
+        }
+        bytes.extend(self.out_weights.iter().map(|&w| w as u8));
+        bytes.extend_from_slice(&self.out_bias.to_le_bytes());
+        bytes
+    }
+
+    /// バイナリ形式の重みを読み込む
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err("NNUE の重みファイルではありません".to_string());
+        }
+        let header = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+        let expected = [VERSION, INPUTS as u32, HIDDEN as u32, HIDDEN2 as u32];
+        if header != expected {
+            return Err(format!(
+                "形式が違います（版・入力・中間層: {:?}、対応しているのは {:?}）",
+                header, expected
+            ));
+        }
+
+        let mut network = Self::zeroed();
+        for w in network.ft_bias.iter_mut().chain(&mut network.ft_weights) {
+            *w = reader.i16()?;
+        }
+        for w in &mut network.l2_weights {
+            *w = reader.take(1)?[0] as i8;
+        }
+        for b in &mut network.l2_bias {
+            *b = reader.i32()?;
+        }
+        for w in &mut network.out_weights {
+            *w = reader.take(1)?[0] as i8;
+        }
+        network.out_bias = reader.i32()?;
+
+        if reader.pos != bytes.len() {
+            return Err("ファイルの末尾に余分なデータがあります".to_string());
+        }
+        Ok(network)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + n;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or_else(|| "ファイルが途中で終わっています".to_string())?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+}
+
+fn sente_score(score: i32, player: Player) -> i32 {
+    match player {
+        Player::Sente => score,
+        Player::Gote => -score,
+    }
+}
+
+// ---- 差分計算する評価関数 ----
+
+/// 探索中の1局面分のアキュムレータ
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    hash: u64,
+    /// 先手視点・後手視点
+    acc: [Accumulator; 2],
+}
+
+/// アキュムレータを指し手ごとに差分更新する評価関数
+///
+/// 探索中の局面は `evaluate_position` で `SearchState::hash` を照合して差分の結果を使い、
+/// それ以外の局面はその場で全体を計算する。
+#[derive(Debug, Clone)]
+pub struct Nnue {
+    network: Network,
+    stack: Vec<Entry>,
+}
+
+impl Nnue {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    fn full_entry(&self, pos: &SearchState) -> Entry {
+        Entry {
+            hash: pos.hash,
+            acc: [
+                self.network.refresh(&pos.state, Player::Sente),
+                self.network.refresh(&pos.state, Player::Gote),
+            ],
+        }
+    }
+}
+
+impl Evaluator for Nnue {
+    fn evaluate(&self, state: &GameState, player: Player) -> i32 {
+        self.network.evaluate_full(state, player)
+    }
+
+    fn evaluate_position(&self, pos: &SearchState) -> i32 {
+        let own = player_index(pos.player);
+        match self.stack.last() {
+            Some(entry) if entry.hash == pos.hash => {
+                let score = self.network.output(&entry.acc[own], &entry.acc[1 - own]);
+                sente_score(score, pos.player)
+            }
+            _ => self.network.evaluate_full(&pos.state, pos.player),
+        }
+    }
+
+    fn reset(&mut self, pos: &SearchState) {
+        self.stack.clear();
+        let entry = self.full_entry(pos);
+        self.stack.push(entry);
+    }
+
+    fn on_do_move(&mut self, pos: &SearchState, undo: &Undo) {
+        let Some(&prev) = self.stack.last() else {
+            return;
+        };
+        let state = &pos.state;
+        let mover = rules::opponent_of(pos.player);
+
+        // 玉が動くか取られたら、玉のマスが変わった視点を作り直す
+        let king_moved = match undo.mv {
+            Move::To(_, to, _) => {
+                state.board[to.y][to.x].is_some_and(|p| p.piece_type == PieceType::King)
+                    || undo
+                        .captured
+                        .is_some_and(|p| p.piece_type == PieceType::King)
+            }
+            Move::Drop(..) => false,
+        };
+        if king_moved {
+            let entry = self.full_entry(pos);
+            self.stack.push(entry);
+            return;
+        }
+
+        let mut entry = Entry {
+            hash: pos.hash,
+            acc: prev.acc,
+        };
+        for perspective in [Player::Sente, Player::Gote] {
+            let king_sq = king_square(state, perspective);
+            let acc = &mut entry.acc[player_index(perspective)];
+            let network = &self.network;
+
+            match undo.mv {
+                Move::To(from, to, _) => {
+                    let after = state.board[to.y][to.x].expect("指した駒がある");
+                    let before = Piece {
+                        promoted: after.promoted && !undo.promoted,
+                        ..after
+                    };
+                    network.sub_feature(
+                        acc,
+                        board_feature(perspective, king_sq, before, from.y * 5 + from.x),
+                    );
+                    network.add_feature(
+                        acc,
+                        board_feature(perspective, king_sq, after, to.y * 5 + to.x),
+                    );
+                    if let Some(captured) = undo.captured {
+                        network.sub_feature(
+                            acc,
+                            board_feature(perspective, king_sq, captured, to.y * 5 + to.x),
+                        );
+                        let n = state.get_hand(mover).get(captured.piece_type);
+                        if let Some(f) = hand_feature(perspective, mover, captured.piece_type, n) {
+                            network.add_feature(acc, f);
+                        }
+                    }
+                }
+                Move::Drop(to, piece_type) => {
+                    let piece = state.board[to.y][to.x].expect("打った駒がある");
+                    network.add_feature(
+                        acc,
+                        board_feature(perspective, king_sq, piece, to.y * 5 + to.x),
+                    );
+                    let n = state.get_hand(mover).get(piece_type) + 1;
+                    if let Some(f) = hand_feature(perspective, mover, piece_type, n) {
+                        network.sub_feature(acc, f);
+                    }
+                }
+            }
+        }
+        self.stack.push(entry);
+    }
+
+    fn on_undo_move(&mut self, _pos: &SearchState, _undo: &Undo) {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+    }
+}
+
+// ---- 学習 ----
+
+/// 学習用の局面（特徴は先に求めておく）
+#[derive(Debug, Clone)]
+pub struct TrainingSample {
+    /// [手番側, 相手側] の視点の特徴
+    features: [Vec<usize>; 2],
+    /// 手番側から見た結果（勝ち 1、引き分け 0.5、負け 0）
+    result: f32,
+}
+
+impl TrainingSample {
+    /// `result` は先手から見た結果
+    pub fn new(state: &GameState, player: Player, result: f64) -> Self {
+        let result = match player {
+            Player::Sente => result,
+            Player::Gote => 1.0 - result,
+        };
+        Self {
+            features: [
+                active_features(state, player),
+                active_features(state, rules::opponent_of(player)),
+            ],
+            result: result as f32,
+        }
+    }
+}
+
+/// 浮動小数点で学習するネットワーク（Adam）
+pub struct Trainer {
+    /// [ft_bias, ft_weights, l2_weights, l2_bias, out_weights, out_bias] を1列に並べたもの
+    params: Vec<f32>,
+    m: Vec<f32>,
+    v: Vec<f32>,
+    steps: i32,
+    /// 学習率
+    pub learning_rate: f32,
+}
+
+const FT_BIAS: usize = 0;
+const FT_WEIGHTS: usize = FT_BIAS + HIDDEN;
+const L2_WEIGHTS: usize = FT_WEIGHTS + INPUTS * HIDDEN;
+const L2_BIAS: usize = L2_WEIGHTS + HIDDEN2 * 2 * HIDDEN;
+const OUT_WEIGHTS: usize = L2_BIAS + HIDDEN2;
+const OUT_BIAS: usize = OUT_WEIGHTS + HIDDEN2;
+const PARAMS: usize = OUT_BIAS + 1;
+
+/// 量子化したときに整数の範囲に収まるよう、学習中の重みを制限する
+const FT_LIMIT: f32 = 2.0;
+const WEIGHT_LIMIT: f32 = 127.0 / WEIGHT_SCALE as f32;
+
+/// 順伝播の途中の値
+struct Forward {
+    /// 連結した特徴変換層の出力（クリップ前）
+    acc: [f32; 2 * HIDDEN],
+    /// 中間層の出力（クリップ前）
+    hidden: [f32; HIDDEN2],
+    /// 出力（ロジット）
+    out: f32,
+}
+
+fn clip(x: f32) -> f32 {
+    x.clamp(0.0, 1.0)
+}
+
+impl Trainer {
+    const BETA1: f32 = 0.9;
+    const BETA2: f32 = 0.999;
+    const EPSILON: f32 = 1e-8;
+
+    /// 乱数で初期化する
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::with_seed(seed);
+        let mut uniform = |limit: f32| (rng.next_u64() % 2001) as f32 / 1000.0 * limit - limit;
+
+        let mut params = vec![0.0; PARAMS];
+        for w in &mut params[FT_WEIGHTS..L2_WEIGHTS] {
+            *w = uniform(0.1);
+        }
+        for w in &mut params[L2_WEIGHTS..L2_BIAS] {
+            *w = uniform(0.3);
+        }
+        for w in &mut params[OUT_WEIGHTS..OUT_BIAS] {
+            *w = uniform(0.5);
+        }
+
+        Self {
+            params,
+            m: vec![0.0; PARAMS],
+            v: vec![0.0; PARAMS],
+            steps: 0,
+            learning_rate: 0.001,
+        }
+    }
+
+    fn forward(&self, sample: &TrainingSample) -> Forward {
+        let p = &self.params;
+        let mut acc = [0.0; 2 * HIDDEN];
+        for (side, features) in sample.features.iter().enumerate() {
+            let acc = &mut acc[side * HIDDEN..(side + 1) * HIDDEN];
+            acc.copy_from_slice(&p[FT_BIAS..FT_WEIGHTS]);
+            for &f in features {
+                let weights = &p[FT_WEIGHTS + f * HIDDEN..FT_WEIGHTS + (f + 1) * HIDDEN];
+                for (a, &w) in acc.iter_mut().zip(weights) {
+                    *a += w;
+                }
+            }
+        }
+
+        let mut hidden = [0.0; HIDDEN2];
+        let mut out = p[OUT_BIAS];
+        for (j, h) in hidden.iter_mut().enumerate() {
+            let weights = &p[L2_WEIGHTS + j * 2 * HIDDEN..L2_WEIGHTS + (j + 1) * 2 * HIDDEN];
+            *h = p[L2_BIAS + j]
+                + weights
+                    .iter()
+                    .zip(&acc)
+                    .map(|(&w, &a)| w * clip(a))
+                    .sum::<f32>();
+            out += p[OUT_WEIGHTS + j] * clip(*h);
+        }
+
+        Forward { acc, hidden, out }
+    }
+
+    /// 手番側から見た評価値（浮動小数点のまま）
+    pub fn evaluate(&self, state: &GameState, player: Player) -> f32 {
+        let sample = TrainingSample::new(state, player, 0.5);
+        self.forward(&sample).out * OUTPUT_SCALE as f32
+    }
+
+    /// 平均ロジスティック損失
+    pub fn loss(&self, samples: &[TrainingSample]) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = samples
+            .iter()
+            .map(|s| cross_entropy(sigmoid(self.forward(s).out), s.result))
+            .sum();
+        total / samples.len() as f32
+    }
+
+    /// ミニバッチ1つ分の勾配で更新し、更新前の平均損失を返す
+    pub fn step(&mut self, batch: &[TrainingSample]) -> f32 {
+        if batch.is_empty() {
+            return 0.0;
+        }
+
+        let mut grad = vec![0.0; PARAMS];
+        let mut total = 0.0;
+        for sample in batch {
+            let fwd = self.forward(sample);
+            let predicted = sigmoid(fwd.out);
+            total += cross_entropy(predicted, sample.result);
+
+            // 交差エントロピーのロジットに対する勾配
+            let d_out = predicted - sample.result;
+            grad[OUT_BIAS] += d_out;
+
+            let mut d_acc = [0.0; 2 * HIDDEN];
+            for j in 0..HIDDEN2 {
+                let h = fwd.hidden[j];
+                grad[OUT_WEIGHTS + j] += d_out * clip(h);
+                if !(0.0..1.0).contains(&h) {
+                    continue;
+                }
+                let d_hidden = d_out * self.params[OUT_WEIGHTS + j];
+                grad[L2_BIAS + j] += d_hidden;
+                let row = L2_WEIGHTS + j * 2 * HIDDEN;
+                for i in 0..2 * HIDDEN {
+                    grad[row + i] += d_hidden * clip(fwd.acc[i]);
+                    d_acc[i] += d_hidden * self.params[row + i];
+                }
+            }
+
+            // 特徴変換層は両視点で共有している
+            for (side, features) in sample.features.iter().enumerate() {
+                for i in 0..HIDDEN {
+                    let a = fwd.acc[side * HIDDEN + i];
+                    if !(0.0..1.0).contains(&a) {
+                        d_acc[side * HIDDEN + i] = 0.0;
+                    }
+                }
+                let d = &d_acc[side * HIDDEN..(side + 1) * HIDDEN];
+                for (g, &di) in grad[FT_BIAS..FT_WEIGHTS].iter_mut().zip(d) {
+                    *g += di;
+                }
+                for &f in features {
+                    let start = FT_WEIGHTS + f * HIDDEN;
+                    for (g, &di) in grad[start..start + HIDDEN].iter_mut().zip(d) {
+                        *g += di;
+                    }
+                }
+            }
+        }
+
+        let n = batch.len() as f32;
+        self.steps += 1;
+        let bias1 = 1.0 - Self::BETA1.powi(self.steps);
+        let bias2 = 1.0 - Self::BETA2.powi(self.steps);
+        for (i, g) in grad.into_iter().enumerate() {
+            let g = g / n;
+            self.m[i] = Self::BETA1 * self.m[i] + (1.0 - Self::BETA1) * g;
+            self.v[i] = Self::BETA2 * self.v[i] + (1.0 - Self::BETA2) * g * g;
+            let update = self.learning_rate * (self.m[i] / bias1)
+                / ((self.v[i] / bias2).sqrt() + Self::EPSILON);
+            let limit = if i < L2_WEIGHTS {
+                FT_LIMIT
+            } else {
+                WEIGHT_LIMIT
+            };
+            self.params[i] = (self.params[i] - update).clamp(-limit, limit);
+        }
+
+        total / n
+    }
+
+    /// 全局面をミニバッチに分けて1周学習し、平均損失を返す
+    pub fn epoch(&mut self, samples: &[TrainingSample], batch_size: usize) -> f32 {
+        let mut total = 0.0;
+        for batch in samples.chunks(batch_size.max(1)) {
+            total += self.step(batch) * batch.len() as f32;
+        }
+        total / samples.len().max(1) as f32
+    }
+
+    /// 整数のネットワークに変換する
+    pub fn to_network(&self) -> Network {
+        let p = &self.params;
+        let a = ACTIVATION_SCALE as f32;
+        let w = WEIGHT_SCALE as f32;
+        let q16 = |x: f32| (x * a).round() as i16;
+        let q8 = |x: f32| (x * w).round().clamp(-127.0, 127.0) as i8;
+        let q32 = |x: f32| (x * a * w).round() as i32;
+
+        Network {
+            ft_bias: p[FT_BIAS..FT_WEIGHTS].iter().map(|&x| q16(x)).collect(),
+            ft_weights: p[FT_WEIGHTS..L2_WEIGHTS].iter().map(|&x| q16(x)).collect(),
+            l2_weights: p[L2_WEIGHTS..L2_BIAS].iter().map(|&x| q8(x)).collect(),
+            l2_bias: p[L2_BIAS..OUT_WEIGHTS].iter().map(|&x| q32(x)).collect(),
+            out_weights: p[OUT_WEIGHTS..OUT_BIAS].iter().map(|&x| q8(x)).collect(),
+            out_bias: q32(p[OUT_BIAS]),
+        }
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn cross_entropy(predicted: f32, target: f32) -> f32 {
+    let p = predicted.clamp(1e-6, 1.0 - 1e-6);
+    -(target * p.ln() + (1.0 - target) * (1.0 - p).ln())
+}
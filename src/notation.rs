@@ -0,0 +1,443 @@
+use crate::board::{GameState, Piece, PieceType, Player};
+use crate::kif;
+use crate::rules::{self, Move, Position};
+use crate::sfen;
+use std::fmt;
+
+// 指し手の表記
+//
+// 棋譜で使う日本式の表記（`２三銀成`・`同銀`・`３四歩打`）を読み書きする。表記は日本将棋連盟の棋譜の表記方法に従う。
+//
+// - マスは全角数字の筋と漢数字の段。直前の手と同じマスへの手は「同」と書く。
+// - 成る手は「成」、成れるのに成らない手は「不成」を付ける。「打」は盤上の同じ駒もそのマスに動けるときだけ付ける。
+// - 同じ駒が2枚以上そのマスに動けるときは、動作（上・引・寄）、真っ直ぐ上がる手（直）、位置（右・左）の順で区別する。
+//   左右は指す側から見た向きで、龍・馬には「直」を使わない。
+//
+// 欧米式の Hodges 表記（`S-2b+`・`P*3c`・`Bx4d=`）も読み書きする。
+//
+// - 駒は英字（成駒は `+B` のように `+` を前に付ける）、マスは USI と同じ `<筋><段>`（`2b`）。
+// - 移動は `-`、駒を取る手は `x`、駒打ちは `*` でつなぐ。成る手は `+`、成れるのに成らない手は `=` を後ろに付ける。
+// - 同じ駒が2枚以上そのマスに動けるときは、`S3c-2b` のように移動元のマスを書く。
+
+/// 画面に表示し、入力として読む指し手の表記
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// 日本式（`２三銀成`）
+    #[default]
+    Japanese,
+    /// 欧米式の Hodges 表記（`S-2b+`）
+    Western,
+}
+
+impl Notation {
+    /// `ja`（`japanese`）か `western`（`hodges`）
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "ja" | "japanese" => Ok(Notation::Japanese),
+            "western" | "hodges" => Ok(Notation::Western),
+            _ => Err(format!("不明な表記です: {}", s)),
+        }
+    }
+
+    /// 1手をこの表記にする（`previous` は直前の手）
+    pub fn format_move(
+        self,
+        state: &GameState,
+        player: Player,
+        mv: Move,
+        previous: Option<Move>,
+    ) -> String {
+        match self {
+            Notation::Japanese => format_japanese(state, player, mv, previous),
+            Notation::Western => format_western(state, player, mv),
+        }
+    }
+
+    /// この表記の指し手を読み、合法手にする（`previous` は直前の手）
+    pub fn parse_move(
+        self,
+        input: &str,
+        state: &GameState,
+        player: Player,
+        previous: Option<Move>,
+    ) -> Result<Move, ParseError> {
+        match self {
+            Notation::Japanese => parse_japanese(input, state, player, previous),
+            Notation::Western => parse_western(input, state, player),
+        }
+    }
+}
+
+/// 指し手を読めなかった理由（表示は日本語。他の言語にするのは呼び出し側）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// 表記として読めない
+    Unreadable(String),
+    /// 読めたが合法手ではない
+    Illegal(String),
+    /// 合法手が2つ以上当てはまり、どの駒の手か決まらない（区別の書き方は表記による）
+    Ambiguous(String, Notation),
+    /// 直前の手がないのに「同」と書いた
+    NoPrevious,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Unreadable(input) => write!(f, "指し手を読めません: {}", input),
+            ParseError::Illegal(input) => write!(f, "合法手ではありません: {}", input),
+            ParseError::Ambiguous(input, Notation::Japanese) => write!(
+                f,
+                "どの駒の手か決まりません（右・左・上・引・寄・直・打を付けてください）: {}",
+                input
+            ),
+            ParseError::Ambiguous(input, Notation::Western) => write!(
+                f,
+                "どの駒の手か決まりません（移動元のマスを付けてください）: {}",
+                input
+            ),
+            ParseError::NoPrevious => write!(f, "「同」の前に指し手がありません"),
+        }
+    }
+}
+
+/// 手番の記号（▲ 先手・△ 後手）
+pub fn turn_mark(player: Player) -> char {
+    match player {
+        Player::Sente => '▲',
+        Player::Gote => '△',
+    }
+}
+
+/// 手番側から見た前方向と右方向の移動量
+fn direction(player: Player, from: Position, to: Position) -> (i32, i32) {
+    let dx = to.x as i32 - from.x as i32;
+    let dy = to.y as i32 - from.y as i32;
+    match player {
+        Player::Sente => (-dy, dx),
+        Player::Gote => (dy, -dx),
+    }
+}
+
+/// 動作（上・引・寄）
+fn motion(player: Player, from: Position, to: Position) -> char {
+    match direction(player, from, to).0 {
+        1.. => '上',
+        0 => '寄',
+        _ => '引',
+    }
+}
+
+/// 手番側から見てどれだけ右にあるか（大きいほど右）
+fn rightness(player: Player, pos: Position) -> usize {
+    match player {
+        Player::Sente => pos.x,
+        Player::Gote => 4 - pos.x,
+    }
+}
+
+/// `piece` と同じ駒で `to` に動ける手番側の駒の位置
+fn rivals(state: &GameState, player: Player, piece: Piece, to: Position) -> Vec<Position> {
+    let mut rivals = Vec::new();
+    for mv in rules::generate_legal_moves(state, player) {
+        if let Move::To(from, dest, _) = mv
+            && dest == to
+            && state.board[from.y][from.x] == Some(piece)
+            && !rivals.contains(&from)
+        {
+            rivals.push(from);
+        }
+    }
+    rivals
+}
+
+/// 同じ駒が複数 `to` に動けるときに `from` の駒を区別する語（区別がいらなければ空）
+fn disambiguator(state: &GameState, player: Player, from: Position, to: Position) -> String {
+    let piece = state.board[from.y][from.x].expect("移動元には駒がある");
+    let others: Vec<Position> = rivals(state, player, piece, to)
+        .into_iter()
+        .filter(|&p| p != from)
+        .collect();
+    if others.is_empty() {
+        return String::new();
+    }
+
+    let mine = motion(player, from, to);
+    if others.iter().all(|&p| motion(player, p, to) != mine) {
+        return mine.to_string();
+    }
+    let dragon_or_horse =
+        piece.promoted && matches!(piece.piece_type, PieceType::Rook | PieceType::Bishop);
+    if !dragon_or_horse && from.x == to.x && mine == '上' {
+        return "直".to_string();
+    }
+
+    let side = |candidates: &[Position]| {
+        let me = rightness(player, from);
+        if candidates.iter().all(|&p| rightness(player, p) < me) {
+            Some('右')
+        } else if candidates.iter().all(|&p| rightness(player, p) > me) {
+            Some('左')
+        } else {
+            None
+        }
+    };
+    if let Some(side) = side(&others) {
+        return side.to_string();
+    }
+    // 位置だけで決まらなければ、同じ動作の駒の中での位置と動作を組み合わせる
+    let same_motion: Vec<Position> = others
+        .into_iter()
+        .filter(|&p| motion(player, p, to) == mine)
+        .collect();
+    match side(&same_motion) {
+        Some(side) => format!("{}{}", side, mine),
+        None => mine.to_string(),
+    }
+}
+
+/// 1手を `２三銀成` のような表記にする（`previous` は直前の手）
+pub fn format_japanese(
+    state: &GameState,
+    player: Player,
+    mv: Move,
+    previous: Option<Move>,
+) -> String {
+    let square = |to: Position| match previous {
+        Some(Move::To(_, prev_to, _) | Move::Drop(prev_to, _)) if prev_to == to => "同".to_string(),
+        _ => kif::format_square(to),
+    };
+    match mv {
+        Move::Drop(to, piece_type) => {
+            let piece = Piece {
+                piece_type,
+                owner: player,
+                promoted: false,
+            };
+            // 盤上の同じ駒も動けるときだけ「打」を付ける
+            let suffix = if rivals(state, player, piece, to).is_empty() {
+                ""
+            } else {
+                "打"
+            };
+            format!(
+                "{}{}{}",
+                square(to),
+                kif::piece_name(piece_type, false),
+                suffix
+            )
+        }
+        Move::To(from, to, promote) => {
+            let piece = state.board[from.y][from.x].expect("移動元には駒がある");
+            let suffix = if promote {
+                "成"
+            } else if promotable(state, player, from, to) {
+                "不成"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}{}",
+                square(to),
+                kif::piece_name(piece.piece_type, piece.promoted),
+                disambiguator(state, player, from, to),
+                suffix
+            )
+        }
+    }
+}
+
+/// 駒の名前の後に書く語に使える文字
+const MODIFIERS: &str = "右左上引寄直成不打";
+
+/// `２三銀成`・`同　銀`・`▲３四歩打` のような表記を読み、合法手にする（`previous` は直前の手）
+///
+/// 筋は半角数字でもよく、王・竜・全も読む。「打」や「不成」を省いても1手に決まれば受け付ける。
+pub fn parse_japanese(
+    input: &str,
+    state: &GameState,
+    player: Player,
+    previous: Option<Move>,
+) -> Result<Move, ParseError> {
+    let err = || ParseError::Unreadable(input.to_string());
+    let text: String = input
+        .trim()
+        .trim_start_matches(['▲', '△', '☗', '☖'])
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let (to, rest) = if let Some(rest) = text.strip_prefix('同') {
+        match previous {
+            Some(Move::To(_, to, _) | Move::Drop(to, _)) => (to, rest),
+            None => return Err(ParseError::NoPrevious),
+        }
+    } else {
+        let mut chars = text.chars();
+        let (Some(file), Some(rank)) = (chars.next(), chars.next()) else {
+            return Err(err());
+        };
+        (
+            kif::parse_square(file, rank).ok_or_else(err)?,
+            chars.as_str(),
+        )
+    };
+    let &(name, piece_type, promoted) = kif::PIECE_NAMES
+        .iter()
+        .find(|(name, _, _)| rest.starts_with(name))
+        .ok_or_else(err)?;
+    let modifiers = &rest[name.len()..];
+    if !modifiers.chars().all(|c| MODIFIERS.contains(c)) {
+        return Err(err());
+    }
+
+    // 同じ駒でそのマスに行く合法手を、正しい表記の駒の名前より後ろと比べる
+    let piece_name = kif::piece_name(piece_type, promoted);
+    let candidates: Vec<Move> = rules::generate_legal_moves(state, player)
+        .into_iter()
+        .filter(|&mv| match mv {
+            Move::To(from, dest, _) => {
+                dest == to
+                    && state.board[from.y][from.x]
+                        .is_some_and(|p| p.piece_type == piece_type && p.promoted == promoted)
+            }
+            Move::Drop(dest, t) => dest == to && t == piece_type && !promoted,
+        })
+        .collect();
+    let matches: Vec<Move> = candidates
+        .iter()
+        .copied()
+        .filter(|&mv| {
+            let formatted = format_japanese(state, player, mv, None);
+            let expected = formatted
+                .split_once(piece_name)
+                .map_or("", |(_, suffix)| suffix);
+            modifiers == expected
+                || (matches!(mv, Move::Drop(..)) && modifiers == "打")
+                || expected.strip_suffix("不成") == Some(modifiers)
+        })
+        .collect();
+
+    match matches[..] {
+        [mv] => Ok(mv),
+        // 区別する語を省いた手は、候補が複数あれば決まらない
+        [] if !(modifiers.is_empty() && candidates.len() > 1) => {
+            Err(ParseError::Illegal(input.to_string()))
+        }
+        _ => Err(ParseError::Ambiguous(input.to_string(), Notation::Japanese)),
+    }
+}
+
+/// 成れる手か（成る手も合法手にある）
+fn promotable(state: &GameState, player: Player, from: Position, to: Position) -> bool {
+    rules::generate_legal_moves(state, player).contains(&Move::To(from, to, true))
+}
+
+/// 1手を `S-2b+`・`P*3c`・`Bx4d=` のような Hodges 表記にする
+pub fn format_western(state: &GameState, player: Player, mv: Move) -> String {
+    match mv {
+        Move::Drop(to, piece_type) => {
+            format!(
+                "{}*{}",
+                sfen::piece_char(piece_type),
+                sfen::format_square(to)
+            )
+        }
+        Move::To(from, to, promote) => {
+            let piece = state.board[from.y][from.x].expect("移動元には駒がある");
+            // 同じ駒が他にも動けるときだけ移動元を書く
+            let origin = if rivals(state, player, piece, to).len() > 1 {
+                sfen::format_square(from)
+            } else {
+                String::new()
+            };
+            let suffix = if promote {
+                "+"
+            } else if promotable(state, player, from, to) {
+                "="
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}{}{}{}",
+                if piece.promoted { "+" } else { "" },
+                sfen::piece_char(piece.piece_type),
+                origin,
+                if state.board[to.y][to.x].is_some() {
+                    'x'
+                } else {
+                    '-'
+                },
+                sfen::format_square(to),
+                suffix
+            )
+        }
+    }
+}
+
+/// `S-2b+`・`P*3c`・`+Bx4d`・`G3c-2b` のような Hodges 表記を読み、合法手にする
+///
+/// 移動元は省いても1手に決まれば受け付け、1手に決まっても書いてよい。`=` は省いてもよい。
+pub fn parse_western(input: &str, state: &GameState, player: Player) -> Result<Move, ParseError> {
+    let err = || ParseError::Unreadable(input.to_string());
+    let text = input.trim();
+    let (promoted, text) = match text.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let (letter, rest) = chars.split_first().ok_or_else(err)?;
+    let piece_type = match sfen::parse_piece_char(*letter) {
+        Some((piece_type, Player::Sente)) => piece_type,
+        _ => return Err(err()),
+    };
+    let square = |file: char, rank: char| sfen::parse_square(file, rank).map_err(|_| err());
+    let (origin, rest) = match rest {
+        [file, rank, rest @ ..] if !matches!(file, '-' | 'x' | '*') => {
+            (Some(square(*file, *rank)?), rest)
+        }
+        _ => (None, rest),
+    };
+    let (marker, to, suffix) = match rest {
+        [marker @ ('-' | 'x' | '*'), file, rank, suffix @ ..] if suffix.len() <= 1 => {
+            (*marker, square(*file, *rank)?, suffix.first().copied())
+        }
+        _ => return Err(err()),
+    };
+    if !matches!(suffix, None | Some('+' | '=')) {
+        return Err(err());
+    }
+
+    let matches: Vec<Move> = rules::generate_legal_moves(state, player)
+        .into_iter()
+        .filter(|&mv| match mv {
+            Move::Drop(dest, t) => {
+                marker == '*'
+                    && dest == to
+                    && t == piece_type
+                    && !promoted
+                    && origin.is_none()
+                    && suffix.is_none()
+            }
+            Move::To(from, dest, promote) => {
+                let capture = state.board[to.y][to.x].is_some();
+                dest == to
+                    && state.board[from.y][from.x]
+                        .is_some_and(|p| p.piece_type == piece_type && p.promoted == promoted)
+                    && origin.is_none_or(|o| o == from)
+                    && marker == if capture { 'x' } else { '-' }
+                    && match suffix {
+                        Some('+') => promote,
+                        Some(_) => !promote && promotable(state, player, from, to),
+                        None => !promote,
+                    }
+            }
+        })
+        .collect();
+
+    match matches[..] {
+        [mv] => Ok(mv),
+        [] => Err(ParseError::Illegal(input.to_string())),
+        _ => Err(ParseError::Ambiguous(input.to_string(), Notation::Western)),
+    }
+}
@@ -0,0 +1,48 @@
+use crate::board::{GameState, Player};
+use crate::rules::{self, Move, MoveList};
+
+// 手生成の検証用ノード数計測
+// 自玉を王手に晒す手は数えない（一般的な perft の定義で、公開されている値と比較できる）。
+
+/// 自玉を王手に晒さない手だけを `moves` に残す
+fn strictly_legal_moves(state: &GameState, player: Player, moves: &mut MoveList) {
+    rules::generate_legal_moves_into(state, player, moves);
+    moves.retain(|&mv| !rules::is_in_check(&rules::make_move(state, mv, player), player));
+}
+
+/// `depth` 手先までの末端局面数を数える
+pub fn perft(state: &GameState, player: Player, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut moves = MoveList::new();
+    strictly_legal_moves(state, player, &mut moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let opponent = rules::opponent_of(player);
+    moves
+        .iter()
+        .map(|&mv| perft(&rules::make_move(state, mv, player), opponent, depth - 1))
+        .sum()
+}
+
+/// 初手ごとの perft の内訳
+pub fn divide(state: &GameState, player: Player, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut moves = MoveList::new();
+    strictly_legal_moves(state, player, &mut moves);
+    let opponent = rules::opponent_of(player);
+    moves
+        .iter()
+        .map(|&mv| {
+            let next = rules::make_move(state, mv, player);
+            (mv, perft(&next, opponent, depth - 1))
+        })
+        .collect()
+}
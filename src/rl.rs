@@ -0,0 +1,222 @@
+use crate::bitboard::square;
+use crate::board::{self, GameState, HAND_TYPES, MAX_HAND, Player, player_index};
+use crate::eval::kind_index;
+use crate::rules::{self, Move, Position};
+
+// 強化学習用の環境
+//
+// 行動は指し手を固定の番号に割り当てたもので、番号は手番によらず盤の絶対座標で決める。
+//   - 盤上の駒の移動: (移動元 * 25 + 移動先) * 2 + 成り          （0..1250）
+//   - 駒打ち:         1250 + 駒種(`HAND_TYPES` の順) * 25 + 打つマス （1250..1375）
+// マスは y * 5 + x（`rules::Position` と同じ向き）。
+//
+// 観測は 5×5 の特徴面を [面][y][x] の順に並べたもの。
+//   - 0..20:  盤上の駒（先手・後手 × 10種。種類の順は `eval::kind_index`）。駒があるマスが 1
+//   - 20..30: 持ち駒（先手・後手 × 5種）。面全体が 枚数 / 2
+//   - 30:     手番。先手番なら面全体が 1
+
+/// 行動の数
+pub const ACTION_COUNT: usize = 25 * 25 * 2 + 5 * 25;
+/// 特徴面の数
+pub const PLANES: usize = 2 * 10 + 2 * 5 + 1;
+/// 観測の長さ
+pub const OBSERVATION_SIZE: usize = PLANES * 25;
+
+const DROP_OFFSET: usize = 25 * 25 * 2;
+
+fn position(sq: usize) -> Position {
+    Position::new(sq % 5, sq / 5)
+}
+
+/// 指し手を行動の番号に変換する
+pub fn encode_move(mv: Move) -> usize {
+    match mv {
+        Move::To(from, to, promote) => (square(from) * 25 + square(to)) * 2 + usize::from(promote),
+        Move::Drop(to, piece_type) => {
+            let hand_index = HAND_TYPES
+                .iter()
+                .position(|&pt| pt == piece_type)
+                .expect("持ち駒にできる駒種");
+            DROP_OFFSET + hand_index * 25 + square(to)
+        }
+    }
+}
+
+/// 行動の番号を指し手に戻す（範囲外なら `None`。合法かどうかは調べない）
+pub fn decode_action(action: usize) -> Option<Move> {
+    if action < DROP_OFFSET {
+        let promote = action % 2 == 1;
+        let from = action / 2 / 25;
+        let to = action / 2 % 25;
+        Some(Move::To(position(from), position(to), promote))
+    } else if action < ACTION_COUNT {
+        let index = action - DROP_OFFSET;
+        Some(Move::Drop(position(index % 25), HAND_TYPES[index / 25]))
+    } else {
+        None
+    }
+}
+
+/// 合法手に当たる行動を true にしたマスク（長さ `ACTION_COUNT`）
+pub fn legal_action_mask(state: &GameState, player: Player) -> Vec<bool> {
+    let mut mask = vec![false; ACTION_COUNT];
+    for mv in rules::generate_legal_moves(state, player) {
+        mask[encode_move(mv)] = true;
+    }
+    mask
+}
+
+/// 局面を特徴面に変換する（長さ `OBSERVATION_SIZE`）
+pub fn observe(state: &GameState, player: Player) -> Vec<f32> {
+    let mut planes = vec![0.0; OBSERVATION_SIZE];
+
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Some(piece) = cell {
+                let owner = player_index(piece.owner);
+                let plane = owner * 10 + kind_index(piece.piece_type, piece.promoted);
+                planes[plane * 25 + y * 5 + x] = 1.0;
+            }
+        }
+    }
+    for (owner, who) in [Player::Sente, Player::Gote].into_iter().enumerate() {
+        let hand = state.get_hand(who);
+        for (i, &pt) in HAND_TYPES.iter().enumerate() {
+            let plane = 20 + owner * 5 + i;
+            planes[plane * 25..(plane + 1) * 25].fill(f32::from(hand.get(pt)) / MAX_HAND as f32);
+        }
+    }
+    if player == Player::Sente {
+        planes[30 * 25..].fill(1.0);
+    }
+
+    planes
+}
+
+/// `step` の結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// 指した後の局面の観測（手番は相手側）
+    pub observation: Vec<f32>,
+    /// 指した側から見た報酬（この手で勝てば 1、それ以外は 0）
+    pub reward: f32,
+    /// 対局が終わったか
+    pub done: bool,
+}
+
+/// 1局分の環境
+///
+/// 玉を取るか、相手の指し手をなくせば勝ち。`max_plies` 手に達すると報酬 0 で終わる。
+#[derive(Debug, Clone)]
+pub struct Env {
+    state: GameState,
+    player: Player,
+    plies: usize,
+    max_plies: usize,
+    done: bool,
+}
+
+impl Env {
+    pub fn new(max_plies: usize) -> Self {
+        Self {
+            state: board::init(),
+            player: Player::Sente,
+            plies: 0,
+            max_plies,
+            done: false,
+        }
+    }
+
+    /// 初期局面に戻し、観測を返す
+    pub fn reset(&mut self) -> Vec<f32> {
+        self.reset_to(board::init(), Player::Sente)
+    }
+
+    /// 指定した局面から始め直し、観測を返す
+    pub fn reset_to(&mut self, state: GameState, player: Player) -> Vec<f32> {
+        self.state = state;
+        self.player = player;
+        self.plies = 0;
+        self.done = rules::generate_legal_moves(&state, player).is_empty();
+        self.observation()
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// 手番
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn plies(&self) -> usize {
+        self.plies
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn observation(&self) -> Vec<f32> {
+        observe(&self.state, self.player)
+    }
+
+    pub fn legal_action_mask(&self) -> Vec<bool> {
+        if self.done {
+            return vec![false; ACTION_COUNT];
+        }
+        legal_action_mask(&self.state, self.player)
+    }
+
+    pub fn legal_actions(&self) -> Vec<usize> {
+        if self.done {
+            return Vec::new();
+        }
+        rules::generate_legal_moves(&self.state, self.player)
+            .into_iter()
+            .map(encode_move)
+            .collect()
+    }
+
+    /// 行動 `action` の手を指す
+    ///
+    /// 対局が終わった後や、合法手でない行動を渡すとエラーになる。
+    pub fn step(&mut self, action: usize) -> Result<Step, String> {
+        if self.done {
+            return Err("対局は終わっています".to_string());
+        }
+        let mv =
+            decode_action(action).ok_or_else(|| format!("行動の番号が範囲外です: {}", action))?;
+        if !rules::generate_legal_moves(&self.state, self.player).contains(&mv) {
+            return Err(format!("合法手ではありません: {}", action));
+        }
+
+        let captures_king = rules::captures_king(&self.state, mv);
+        self.state = rules::make_move(&self.state, mv, self.player);
+        self.player = rules::opponent_of(self.player);
+        self.plies += 1;
+
+        let reward =
+            if captures_king || rules::generate_legal_moves(&self.state, self.player).is_empty() {
+                self.done = true;
+                1.0
+            } else {
+                self.done = self.plies >= self.max_plies;
+                0.0
+            };
+
+        Ok(Step {
+            observation: self.observation(),
+            reward,
+            done: self.done,
+        })
+    }
+}
+
+impl Default for Env {
+    /// 200 手で打ち切る環境
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
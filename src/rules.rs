@@ -1,4 +1,5 @@
-use crate::board::{Board, GameState, Piece, PieceType, Player};
+use crate::board::{Board, GameState, HAND_TYPES, Piece, PieceType, Player, player_index};
+use crate::zobrist;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
@@ -18,27 +19,96 @@ pub enum Move {
     Drop(Position, PieceType),    // (to, type)
 }
 
-pub fn generate_legal_moves(state: &GameState, player: Player) -> Vec<Move> {
-    let mut moves = generate_moves_fast(state, player);
+/// 1局面の指し手数の上限
+/// 盤上の駒（玉8 + 他の駒10枚 × 成・不成込みで最大16）と駒打ち（5種 × 空きマス最大23）の和
+pub const MAX_MOVES: usize = 8 + 10 * 16 + 5 * 23;
 
-    // 打ち歩詰めチェック: 歩のdropで相手が詰みになる手を除外
-    let opponent = opponent_of(player);
-    moves.retain(|mv| {
-        if let Move::Drop(_, PieceType::Pawn) = mv {
-            let new_state = make_move(state, *mv, player);
-            !is_checkmate(&new_state, opponent)
-        } else {
-            true
+/// 固定容量の指し手リスト（ヒープ確保なし）
+#[derive(Debug, Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [Move::Drop(Position::new(0, 0), PieceType::Pawn); MAX_MOVES],
+            len: 0,
         }
-    });
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// 条件を満たす手だけを残す（順序は保つ）
+    pub fn retain(&mut self, mut keep: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let mv = self.moves[i];
+            if keep(&mv) {
+                self.moves[kept] = mv;
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::ops::Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
 
-    moves
+pub fn generate_legal_moves(state: &GameState, player: Player) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_legal_moves_into(state, player, &mut moves);
+    moves.to_vec()
+}
+
+/// `generate_legal_moves` の結果を `moves` に書き込む（`moves` は先に空にする）
+pub fn generate_legal_moves_into(state: &GameState, player: Player, moves: &mut MoveList) {
+    generate_moves_fast_into(state, player, moves);
+
+    // 打ち歩詰めチェック: 歩のdropで相手が詰みになる手を除外
+    moves.retain(|&mv| !is_pawn_drop_mate(state, mv, player));
 }
 
 /// 二歩チェック付きの手生成（打ち歩詰めチェックなし）
 /// プレイアウト等の高速処理用に公開
 pub fn generate_moves_fast(state: &GameState, player: Player) -> Vec<Move> {
-    let mut moves = Vec::new();
+    let mut moves = MoveList::new();
+    generate_moves_fast_into(state, player, &mut moves);
+    moves.to_vec()
+}
+
+/// `generate_moves_fast` の結果を `moves` に書き込む（`moves` は先に空にする）
+pub fn generate_moves_fast_into(state: &GameState, player: Player, moves: &mut MoveList) {
+    moves.clear();
 
     for y in 0..5 {
         for x in 0..5 {
@@ -47,7 +117,7 @@ pub fn generate_moves_fast(state: &GameState, player: Player) -> Vec<Move> {
             {
                 let from = Position::new(x, y);
                 add_move_for_piece(
-                    &mut moves,
+                    moves,
                     &state.board,
                     player,
                     piece.piece_type,
@@ -59,13 +129,6 @@ pub fn generate_moves_fast(state: &GameState, player: Player) -> Vec<Move> {
     }
 
     let hand = state.get_hand(player);
-    let piece_types = [
-        PieceType::Pawn,
-        PieceType::Silver,
-        PieceType::Gold,
-        PieceType::Bishop,
-        PieceType::Rook,
-    ];
 
     // 二歩チェック用: 各列に自分の未成り歩があるか事前計算
     let mut pawn_columns = [false; 5];
@@ -81,12 +144,15 @@ pub fn generate_moves_fast(state: &GameState, player: Player) -> Vec<Move> {
         }
     }
 
-    for &piece_type in &piece_types {
+    for &piece_type in &HAND_TYPES {
         if hand.get(piece_type) > 0 {
             for (y, row) in state.board.iter().enumerate() {
                 for (x, cell) in row.iter().enumerate() {
                     if cell.is_none() {
-                        if piece_type == PieceType::Pawn && pawn_columns[x] {
+                        // 二歩・最奥段への歩打ち（行き所のない駒）は禁止
+                        if piece_type == PieceType::Pawn
+                            && (pawn_columns[x] || is_in_promotion_zone(player, y))
+                        {
                             continue;
                         }
                         moves.push(Move::Drop(Position::new(x, y), piece_type));
@@ -95,12 +161,10 @@ pub fn generate_moves_fast(state: &GameState, player: Player) -> Vec<Move> {
             }
         }
     }
-
-    moves
 }
 
 fn add_move_for_piece(
-    moves: &mut Vec<Move>,
+    moves: &mut MoveList,
     board: &Board,
     player: Player,
     p_type: PieceType,
@@ -111,7 +175,8 @@ fn add_move_for_piece(
     let can_promote = !promoted && can_piece_promote(p_type);
 
     for to in destinations {
-        if can_promote && (is_in_promotion_zone(player, from.y) || is_in_promotion_zone(player, to.y))
+        if can_promote
+            && (is_in_promotion_zone(player, from.y) || is_in_promotion_zone(player, to.y))
         {
             moves.push(Move::To(from, to, true));
             // 歩が最奥段に到達した場合は強制成り（不成の選択肢なし）
@@ -124,6 +189,161 @@ fn add_move_for_piece(
     }
 }
 
+/// 打ち歩詰めになる歩打ちか
+fn is_pawn_drop_mate(state: &GameState, mv: Move, player: Player) -> bool {
+    matches!(mv, Move::Drop(_, PieceType::Pawn))
+        && is_checkmate(&make_move(state, mv, player), opponent_of(player))
+}
+
+// ---- 段階的な手生成 ----
+// 探索の用途ごとに合法手の一部だけを生成する。いずれも `generate_legal_moves` の部分集合。
+
+/// 駒を取る手と成る手
+pub fn generate_captures(state: &GameState, player: Player) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_captures_into(state, player, &mut moves);
+    moves.to_vec()
+}
+
+/// `generate_captures` の結果を `moves` に書き込む（`moves` は先に空にする）
+pub fn generate_captures_into(state: &GameState, player: Player, moves: &mut MoveList) {
+    moves.clear();
+
+    for y in 0..5 {
+        for x in 0..5 {
+            let Some(piece) = state.board[y][x] else {
+                continue;
+            };
+            if piece.owner != player {
+                continue;
+            }
+            let from = Position::new(x, y);
+            let can_promote = !piece.promoted && can_piece_promote(piece.piece_type);
+            let destinations = collect_piece_destinations(
+                &state.board,
+                player,
+                piece.piece_type,
+                piece.promoted,
+                from,
+            );
+
+            for to in destinations {
+                let capture = state.board[to.y][to.x].is_some();
+                let promotion = can_promote
+                    && (is_in_promotion_zone(player, from.y) || is_in_promotion_zone(player, to.y));
+                if promotion {
+                    moves.push(Move::To(from, to, true));
+                }
+                if capture && !(promotion && must_promote(player, piece.piece_type, to.y)) {
+                    moves.push(Move::To(from, to, false));
+                }
+            }
+        }
+    }
+}
+
+/// 相手玉に王手をかける手（駒打ちの王手を含む）
+pub fn generate_checks(state: &GameState, player: Player) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_checks_into(state, player, &mut moves);
+    moves.to_vec()
+}
+
+/// `generate_checks` の結果を `moves` に書き込む（`moves` は先に空にする）
+pub fn generate_checks_into(state: &GameState, player: Player, moves: &mut MoveList) {
+    generate_moves_fast_into(state, player, moves);
+
+    let opponent = opponent_of(player);
+    let Some(king) = find_king(state, opponent) else {
+        moves.clear();
+        return;
+    };
+
+    // 駒打ちは、玉から相手の駒として見た利き（逆向きの利き）の届くマスだけが王手になる
+    let drop_checks =
+        |piece_type| collect_piece_destinations(&state.board, opponent, piece_type, false, king);
+    let drop_squares = HAND_TYPES.map(|piece_type| (piece_type, drop_checks(piece_type)));
+
+    moves.retain(|&mv| match mv {
+        Move::Drop(to, piece_type) => {
+            drop_squares
+                .iter()
+                .any(|(pt, squares)| *pt == piece_type && squares.contains(to))
+                && !is_pawn_drop_mate(state, mv, player)
+        }
+        // 移動による王手は空き王手があるため、指した後の局面で判定する
+        Move::To(..) => is_in_check(&make_move(state, mv, player), opponent),
+    });
+}
+
+/// 王手を回避する手（王手されていなければ空）
+pub fn generate_evasions(state: &GameState, player: Player) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_evasions_into(state, player, &mut moves);
+    moves.to_vec()
+}
+
+/// `generate_evasions` の結果を `moves` に書き込む（`moves` は先に空にする）
+pub fn generate_evasions_into(state: &GameState, player: Player, moves: &mut MoveList) {
+    moves.clear();
+
+    let Some(king) = find_king(state, player) else {
+        return;
+    };
+    let opponent = opponent_of(player);
+
+    // 王手している駒
+    let mut checkers = 0;
+    let mut checker = king;
+    for y in 0..5 {
+        for x in 0..5 {
+            if let Some(piece) = state.board[y][x]
+                && piece.owner == opponent
+                && collect_piece_destinations(
+                    &state.board,
+                    opponent,
+                    piece.piece_type,
+                    piece.promoted,
+                    Position::new(x, y),
+                )
+                .contains(king)
+            {
+                checkers += 1;
+                checker = Position::new(x, y);
+            }
+        }
+    }
+    if checkers == 0 {
+        return;
+    }
+
+    // 両王手でなければ、王手した駒を取る手と合駒も候補になる
+    let mut targets = [[false; 5]; 5];
+    if checkers == 1 {
+        targets[checker.y][checker.x] = true;
+        let dx = (king.x as i8 - checker.x as i8).signum();
+        let dy = (king.y as i8 - checker.y as i8).signum();
+        let mut x = checker.x as i8 + dx;
+        let mut y = checker.y as i8 + dy;
+        while (x as usize, y as usize) != (king.x, king.y) {
+            targets[y as usize][x as usize] = true;
+            x += dx;
+            y += dy;
+        }
+    }
+
+    generate_moves_fast_into(state, player, moves);
+    moves.retain(|&mv| {
+        let candidate = match mv {
+            Move::To(from, to, _) => from == king || targets[to.y][to.x],
+            Move::Drop(to, _) => targets[to.y][to.x],
+        };
+        candidate
+            && !is_in_check(&make_move(state, mv, player), player)
+            && !is_pawn_drop_mate(state, mv, player)
+    });
+}
+
 fn can_piece_promote(p_type: PieceType) -> bool {
     matches!(
         p_type,
@@ -142,56 +362,202 @@ fn must_promote(player: Player, p_type: PieceType, to_y: usize) -> bool {
     p_type == PieceType::Pawn && is_in_promotion_zone(player, to_y)
 }
 
+// ---- 利きテーブル ----
+// マスごとの1マスの移動先と、盤端までの直線を事前計算しておく。
+
+/// マス番号（y * 5 + x）の短い列
+#[derive(Debug, Clone, Copy)]
+struct SquareList {
+    squares: [u8; 8],
+    len: u8,
+}
+
+impl SquareList {
+    const EMPTY: Self = Self {
+        squares: [0; 8],
+        len: 0,
+    };
+
+    const fn push(&mut self, sq: usize) {
+        self.squares[self.len as usize] = sq as u8;
+        self.len += 1;
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.squares[..self.len as usize]
+            .iter()
+            .map(|&sq| Position::new(sq as usize % 5, sq as usize / 5))
+    }
+}
+
+/// 8方向（先手から見た前・後・左・右・左前・右前・左後・右後）
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+const ORTHOGONAL: [usize; 4] = [0, 1, 2, 3];
+const DIAGONAL: [usize; 4] = [4, 5, 6, 7];
+
+/// 1マスだけ動く利きの種類
+const STEP_KING: usize = 0;
+const STEP_GOLD: usize = 1;
+const STEP_SILVER: usize = 2;
+const STEP_PAWN: usize = 3;
+const STEP_ORTHOGONAL: usize = 4; // 馬の縦横
+const STEP_DIAGONAL: usize = 5; // 龍の斜め
+const STEP_KINDS: usize = 6;
+
+/// 先手の1マスの動き（DIRECTIONS の添字）
+const fn step_directions(kind: usize) -> &'static [usize] {
+    match kind {
+        STEP_KING => &[0, 1, 2, 3, 4, 5, 6, 7],
+        STEP_GOLD => &[0, 1, 2, 3, 4, 5],
+        STEP_SILVER => &[0, 4, 5, 6, 7],
+        STEP_PAWN => &[0],
+        STEP_ORTHOGONAL => &ORTHOGONAL,
+        _ => &DIAGONAL,
+    }
+}
+
+const fn offset_square(sq: usize, dx: i8, dy: i8) -> Option<usize> {
+    let x = (sq % 5) as i8 + dx;
+    let y = (sq / 5) as i8 + dy;
+    if x >= 0 && x < 5 && y >= 0 && y < 5 {
+        Some((y * 5 + x) as usize)
+    } else {
+        None
+    }
+}
+
+struct AttackTables {
+    /// [手番][利きの種類][マス] の1マスの移動先
+    steps: [[[SquareList; 25]; STEP_KINDS]; 2],
+    /// [方向][マス] の盤端までのマス（近い順）
+    rays: [[SquareList; 25]; 8],
+}
+
+const fn make_attack_tables() -> AttackTables {
+    let mut tables = AttackTables {
+        steps: [[[SquareList::EMPTY; 25]; STEP_KINDS]; 2],
+        rays: [[SquareList::EMPTY; 25]; 8],
+    };
+
+    let mut sq = 0;
+    while sq < 25 {
+        let mut kind = 0;
+        while kind < STEP_KINDS {
+            let dirs = step_directions(kind);
+            let mut i = 0;
+            while i < dirs.len() {
+                let (dx, dy) = DIRECTIONS[dirs[i]];
+                // 後手は先手の動きを反転する
+                if let Some(to) = offset_square(sq, dx, dy) {
+                    tables.steps[0][kind][sq].push(to);
+                }
+                if let Some(to) = offset_square(sq, -dx, -dy) {
+                    tables.steps[1][kind][sq].push(to);
+                }
+                i += 1;
+            }
+            kind += 1;
+        }
+
+        let mut dir = 0;
+        while dir < 8 {
+            let (dx, dy) = DIRECTIONS[dir];
+            let mut curr = sq;
+            while let Some(next) = offset_square(curr, dx, dy) {
+                tables.rays[dir][sq].push(next);
+                curr = next;
+            }
+            dir += 1;
+        }
+        sq += 1;
+    }
+
+    tables
+}
+
+static ATTACK_TABLES: AttackTables = make_attack_tables();
+
+/// 1つの駒の移動先の上限（龍・馬の縦横斜め 8 + 4）
+const MAX_DESTINATIONS: usize = 12;
+
+/// 1つの駒の移動先（固定容量）
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Destinations {
+    squares: [Position; MAX_DESTINATIONS],
+    len: usize,
+}
+
+impl Destinations {
+    fn new() -> Self {
+        Self {
+            squares: [Position::new(0, 0); MAX_DESTINATIONS],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, pos: Position) {
+        self.squares[self.len] = pos;
+        self.len += 1;
+    }
+
+    pub(crate) fn contains(&self, pos: Position) -> bool {
+        self.squares[..self.len].contains(&pos)
+    }
+}
+
+impl IntoIterator for Destinations {
+    type Item = Position;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Position, MAX_DESTINATIONS>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.squares.into_iter().take(self.len)
+    }
+}
+
 fn collect_stepping_moves(
-    destinations: &mut Vec<Position>,
+    destinations: &mut Destinations,
     board: &Board,
     player: Player,
     from: Position,
-    offsets: &[(i8, i8)],
+    kind: usize,
 ) {
-    for &(dx, dy) in offsets {
-        if let Some(to) = apply_offset(from, dx, dy)
-            && !is_friendly_piece(board, to, player)
-        {
+    let steps = &ATTACK_TABLES.steps[player_index(player)][kind][from.y * 5 + from.x];
+    for to in steps.positions() {
+        if !is_friendly_piece(board, to, player) {
             destinations.push(to);
         }
     }
 }
 
 fn collect_sliding_moves(
-    destinations: &mut Vec<Position>,
+    destinations: &mut Destinations,
     board: &Board,
     player: Player,
     from: Position,
-    dirs: &[(i8, i8)],
+    dirs: &[usize],
 ) {
-    for &(dx, dy) in dirs {
-        let mut curr = from;
-        while let Some(next) = apply_offset(curr, dx, dy) {
+    for &dir in dirs {
+        for next in ATTACK_TABLES.rays[dir][from.y * 5 + from.x].positions() {
             if let Some(target_piece) = board[next.y][next.x] {
                 if target_piece.owner != player {
                     destinations.push(next);
                 }
                 break;
-            } else {
-                destinations.push(next);
-                curr = next;
             }
+            destinations.push(next);
         }
     }
 }
 
-fn apply_offset(pos: Position, dx: i8, dy: i8) -> Option<Position> {
-    let nx = pos.x as i8 + dx;
-    let ny = pos.y as i8 + dy;
-
-    if (0..5).contains(&nx) && (0..5).contains(&ny) {
-        Some(Position::new(nx as usize, ny as usize))
-    } else {
-        None
-    }
-}
-
 fn is_friendly_piece(board: &Board, pos: Position, player: Player) -> bool {
     if let Some(piece) = board[pos.y][pos.x] {
         piece.owner == player
@@ -200,56 +566,6 @@ fn is_friendly_piece(board: &Board, pos: Position, player: Player) -> bool {
     }
 }
 
-fn get_stepping_offsets(player: Player, p_type: PieceType) -> Vec<(i8, i8)> {
-    let forward = (0, -1);
-    let back = (0, 1);
-    let side_left = (-1, 0);
-    let side_right = (1, 0);
-    let diag_f_left = (-1, -1);
-    let diag_f_right = (1, -1);
-    let diag_b_left = (-1, 1);
-    let diag_b_right = (1, 1);
-
-    let mut offsets = match p_type {
-        PieceType::King => vec![
-            forward,
-            back,
-            side_left,
-            side_right,
-            diag_f_left,
-            diag_f_right,
-            diag_b_left,
-            diag_b_right,
-        ],
-        PieceType::Gold => vec![
-            forward,
-            back,
-            side_left,
-            side_right,
-            diag_f_left,
-            diag_f_right,
-        ],
-        PieceType::Silver => vec![
-            forward,
-            diag_f_left,
-            diag_f_right,
-            diag_b_left,
-            diag_b_right,
-        ],
-        PieceType::Pawn => vec![forward],
-        _ => vec![],
-    };
-
-    if player == Player::Gote {
-        for p in &mut offsets {
-            p.0 = -p.0;
-            p.1 = -p.1;
-        }
-    }
-
-    offsets
-}
-
 pub fn opponent_of(player: Player) -> Player {
     match player {
         Player::Sente => Player::Gote,
@@ -271,56 +587,61 @@ fn find_king(state: &GameState, player: Player) -> Option<Position> {
     None
 }
 
-fn collect_piece_destinations(
+/// `player` の王が盤上にあるか（取られていれば false）
+pub fn has_king(state: &GameState, player: Player) -> bool {
+    find_king(state, player).is_some()
+}
+
+/// 王を取る手か
+pub fn captures_king(state: &GameState, mv: Move) -> bool {
+    match mv {
+        Move::To(_, to, _) => {
+            state.board[to.y][to.x].is_some_and(|p| p.piece_type == PieceType::King)
+        }
+        Move::Drop(..) => false,
+    }
+}
+
+pub(crate) fn collect_piece_destinations(
     board: &Board,
     player: Player,
     p_type: PieceType,
     promoted: bool,
     from: Position,
-) -> Vec<Position> {
-    let mut destinations = Vec::new();
-
-    if promoted {
-        match p_type {
-            PieceType::Rook => {
-                let dirs = [(0, -1), (0, 1), (-1, 0), (1, 0)];
-                collect_sliding_moves(&mut destinations, board, player, from, &dirs);
-                let diag = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-                collect_stepping_moves(&mut destinations, board, player, from, &diag);
-            }
-            PieceType::Bishop => {
-                let dirs = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-                collect_sliding_moves(&mut destinations, board, player, from, &dirs);
-                let ortho = [(0, -1), (0, 1), (-1, 0), (1, 0)];
-                collect_stepping_moves(&mut destinations, board, player, from, &ortho);
+) -> Destinations {
+    let mut destinations = Destinations::new();
+
+    match (p_type, promoted) {
+        (PieceType::Rook, _) => {
+            collect_sliding_moves(&mut destinations, board, player, from, &ORTHOGONAL);
+            if promoted {
+                collect_stepping_moves(&mut destinations, board, player, from, STEP_DIAGONAL);
             }
-            PieceType::Pawn | PieceType::Silver => {
-                let offsets = get_stepping_offsets(player, PieceType::Gold);
-                collect_stepping_moves(&mut destinations, board, player, from, &offsets);
-            }
-            _ => {}
         }
-    } else {
-        match p_type {
-            PieceType::Rook => {
-                let dirs = [(0, -1), (0, 1), (-1, 0), (1, 0)];
-                collect_sliding_moves(&mut destinations, board, player, from, &dirs);
-            }
-            PieceType::Bishop => {
-                let dirs = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-                collect_sliding_moves(&mut destinations, board, player, from, &dirs);
-            }
-            _ => {
-                let offsets = get_stepping_offsets(player, p_type);
-                collect_stepping_moves(&mut destinations, board, player, from, &offsets);
+        (PieceType::Bishop, _) => {
+            collect_sliding_moves(&mut destinations, board, player, from, &DIAGONAL);
+            if promoted {
+                collect_stepping_moves(&mut destinations, board, player, from, STEP_ORTHOGONAL);
             }
         }
+        (PieceType::King, _) => {
+            collect_stepping_moves(&mut destinations, board, player, from, STEP_KING);
+        }
+        (PieceType::Gold, _) | (PieceType::Silver | PieceType::Pawn, true) => {
+            collect_stepping_moves(&mut destinations, board, player, from, STEP_GOLD);
+        }
+        (PieceType::Silver, false) => {
+            collect_stepping_moves(&mut destinations, board, player, from, STEP_SILVER);
+        }
+        (PieceType::Pawn, false) => {
+            collect_stepping_moves(&mut destinations, board, player, from, STEP_PAWN);
+        }
     }
 
     destinations
 }
 
-fn is_in_check(state: &GameState, player: Player) -> bool {
+pub fn is_in_check(state: &GameState, player: Player) -> bool {
     let king_pos = match find_king(state, player) {
         Some(pos) => pos,
         None => return false,
@@ -341,7 +662,7 @@ fn is_in_check(state: &GameState, player: Player) -> bool {
                     piece.promoted,
                     from,
                 );
-                if dests.iter().any(|d| d.x == king_pos.x && d.y == king_pos.y) {
+                if dests.contains(king_pos) {
                     return true;
                 }
             }
@@ -351,13 +672,14 @@ fn is_in_check(state: &GameState, player: Player) -> bool {
     false
 }
 
-fn is_checkmate(state: &GameState, player: Player) -> bool {
+pub fn is_checkmate(state: &GameState, player: Player) -> bool {
     if !is_in_check(state, player) {
         return false;
     }
 
-    let moves = generate_moves_fast(state, player);
-    for mv in moves {
+    let mut moves = MoveList::new();
+    generate_moves_fast_into(state, player, &mut moves);
+    for &mv in &moves {
         let new_state = make_move(state, mv, player);
         if !is_in_check(&new_state, player) {
             return false;
@@ -400,3 +722,118 @@ pub fn make_move(state: &GameState, mv: Move, player: Player) -> GameState {
 
     new_state
 }
+
+/// `do_move` で変更した内容（`undo_move` で元に戻すための記録）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    pub mv: Move,
+    /// 取った駒
+    pub captured: Option<Piece>,
+    /// この手で成ったか
+    pub promoted: bool,
+    /// 指す前のハッシュ値
+    pub prev_hash: u64,
+}
+
+/// 探索用の可変局面
+/// `do_move` / `undo_move` で局面をコピーせずに進め・戻し、手番とハッシュ値も差分更新する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchState {
+    pub state: GameState,
+    pub player: Player,
+    pub hash: u64,
+}
+
+impl SearchState {
+    pub fn new(state: GameState, player: Player) -> Self {
+        Self {
+            state,
+            player,
+            hash: zobrist::hash(&state, player),
+        }
+    }
+
+    /// 手番側の指し手を適用する
+    pub fn do_move(&mut self, mv: Move) -> Undo {
+        let player = self.player;
+        let prev_hash = self.hash;
+        let mut captured = None;
+        let mut promoted = false;
+
+        match mv {
+            Move::To(from, to, promote) => {
+                let mut piece = self.state.board[from.y][from.x]
+                    .expect("move_from position should have a piece");
+                self.hash ^= zobrist::piece_key(from, piece);
+
+                if let Some(target) = self.state.board[to.y][to.x] {
+                    self.hash ^= zobrist::piece_key(to, target);
+                    let hand = self.state.get_hand_mut(player);
+                    let count = hand.get(target.piece_type);
+                    self.hash ^= zobrist::hand_key(player, target.piece_type, count);
+                    hand.add(target.piece_type);
+                    captured = Some(target);
+                }
+
+                if promote && !piece.promoted {
+                    piece.promoted = true;
+                    promoted = true;
+                }
+
+                self.hash ^= zobrist::piece_key(to, piece);
+                self.state.board[to.y][to.x] = Some(piece);
+                self.state.board[from.y][from.x] = None;
+            }
+            Move::Drop(to, piece_type) => {
+                let hand = self.state.get_hand_mut(player);
+                assert!(hand.remove(piece_type), "dropped piece should be in hand");
+                self.hash ^= zobrist::hand_key(player, piece_type, hand.get(piece_type));
+
+                let piece = Piece {
+                    piece_type,
+                    owner: player,
+                    promoted: false,
+                };
+                self.hash ^= zobrist::piece_key(to, piece);
+                self.state.board[to.y][to.x] = Some(piece);
+            }
+        }
+
+        self.hash ^= zobrist::side_key();
+        self.player = opponent_of(player);
+
+        Undo {
+            mv,
+            captured,
+            promoted,
+            prev_hash,
+        }
+    }
+
+    /// 直前の `do_move` を取り消す
+    pub fn undo_move(&mut self, undo: Undo) {
+        let player = opponent_of(self.player);
+
+        match undo.mv {
+            Move::To(from, to, _) => {
+                let mut piece =
+                    self.state.board[to.y][to.x].expect("undo target should have a piece");
+                if undo.promoted {
+                    piece.promoted = false;
+                }
+                self.state.board[from.y][from.x] = Some(piece);
+                self.state.board[to.y][to.x] = undo.captured;
+                if let Some(captured) = undo.captured {
+                    self.state.get_hand_mut(player).remove(captured.piece_type);
+                }
+            }
+            Move::Drop(to, piece_type) => {
+                self.state.board[to.y][to.x] = None;
+                self.state.get_hand_mut(player).add(piece_type);
+            }
+        }
+
+        self.player = player;
+        self.hash = undo.prev_hash;
+    }
+}
@@ -1,93 +1,286 @@
-use crate::board::{GameState, PieceType, Player};
-use crate::rules::{self, Move};
+use crate::board::{GameState, Player};
+use crate::book::{Book, BookSelection};
+use crate::eval::{Evaluator, Positional};
+use crate::rules::{self, Move, MoveList, SearchState, Undo};
+use crate::tablebase::{self, Outcome, Tablebase};
+use crate::zobrist;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 const SEARCH_DEPTH: u32 = 4;
+/// 思考時間だけを指定したときに反復深化で読む最大の深さ
+const MAX_SEARCH_DEPTH: u32 = 32;
 const INF: i32 = 100_000;
+/// 終盤データベースで勝ちと判明した局面の評価値（手数分だけ差し引く）
+const TB_WIN: i32 = INF - 1_000;
+/// 思考時間を確かめる間隔（局面数）
+const TIME_CHECK_NODES: u32 = 1_024;
+
+/// 探索の設定
+#[derive(Clone, Copy, Default)]
+pub struct SearchOptions<'a> {
+    /// 参照する終盤データベース
+    pub tablebases: &'a [Tablebase],
+    /// 参照する定跡
+    pub book: Option<&'a Book>,
+    /// 定跡手の選び方
+    pub book_selection: BookSelection,
+    /// Alpha-Beta 探索の深さ（`None` なら既定の深さ）
+    pub depth: Option<u32>,
+    /// MCTS の反復回数（`None` なら既定の回数）
+    pub iterations: Option<u32>,
+    /// 思考時間の上限
+    ///
+    /// Alpha-Beta 探索は深さ 1 から反復深化し、時間内に読み終えた最も深い結果を返す。
+    /// MCTS は時間に達した時点で反復を打ち切る。深さ・反復回数を指定しなければ時間いっぱいまで読む。
+    pub time_limit: Option<Duration>,
+    /// 立てると探索を打ち切り、それまでに読んだ結果を返す（USI の `stop`）
+    ///
+    /// 指定すると、深さ・反復回数・思考時間を指定しなくても止められるまで読み続ける。
+    pub stop: Option<&'a AtomicBool>,
+    /// MCTS と定跡手の選択に使う乱数のシード（`None` なら時刻から決める）
+    pub seed: Option<u64>,
+}
 
-// ---- 評価関数 ----
+impl SearchOptions<'_> {
+    fn rng(&self) -> Rng {
+        self.seed.map_or_else(Rng::new, Rng::with_seed)
+    }
 
-fn piece_value(piece_type: PieceType, promoted: bool) -> i32 {
-    match (piece_type, promoted) {
-        (PieceType::Pawn, false) => 100,
-        (PieceType::Pawn, true) => 500,
-        (PieceType::Silver, false) => 400,
-        (PieceType::Silver, true) => 500,
-        (PieceType::Gold, _) => 500,
-        (PieceType::Bishop, false) => 600,
-        (PieceType::Bishop, true) => 800,
-        (PieceType::Rook, false) => 700,
-        (PieceType::Rook, true) => 900,
-        (PieceType::King, _) => 0,
+    /// 思考時間か `stop` で打ち切る探索か
+    fn is_open_ended(&self) -> bool {
+        self.time_limit.is_some() || self.stop.is_some()
     }
 }
 
-fn evaluate(state: &GameState) -> i32 {
-    // 終局判定: 王が取られていれば即座に極値を返す
-    let sente_king = has_king(state, Player::Sente);
-    let gote_king = has_king(state, Player::Gote);
-    if !sente_king {
-        return -INF;
-    }
-    if !gote_king {
-        return INF;
-    }
+/// `stop` の合図が立っているか
+fn is_stopped(stop: Option<&AtomicBool>) -> bool {
+    stop.is_some_and(|s| s.load(Ordering::Relaxed))
+}
 
-    let mut score = 0;
+/// 探索の結果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchResult {
+    /// 選んだ手（合法手がなければ `None`）
+    pub best_move: Option<Move>,
+    /// 先手から見た評価値（定跡の手なら `None`）
+    pub score: Option<i32>,
+    /// MCTS の根での各手の訪問回数（Alpha-Beta 探索や定跡・終盤データベースの手では空）
+    pub visits: Vec<(Move, u32)>,
+}
 
-    // 盤上の駒
-    for row in &state.board {
-        for cell in row.iter().flatten() {
-            let value = piece_value(cell.piece_type, cell.promoted);
-            match cell.owner {
-                Player::Sente => score += value,
-                Player::Gote => score -= value,
-            }
-        }
+/// 定跡・終盤データベースに該当する局面なら探索せずに指し手を返す
+fn probe_known_move(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+) -> Option<SearchResult> {
+    if let Some(book) = options.book
+        && let Some(mv) = book.select(state, player, options.book_selection, &mut options.rng())
+    {
+        return Some(SearchResult {
+            best_move: Some(mv),
+            ..SearchResult::default()
+        });
     }
+    tablebase::best_move(options.tablebases, state, player).map(|(mv, outcome)| SearchResult {
+        best_move: Some(mv),
+        score: Some(tablebase_score(outcome, player)),
+        visits: Vec::new(),
+    })
+}
 
-    // 持ち駒（未成り価値）
-    let hand_types = [
-        PieceType::Pawn,
-        PieceType::Silver,
-        PieceType::Gold,
-        PieceType::Bishop,
-        PieceType::Rook,
-    ];
+/// 終盤データベースの結果を先手から見た評価値に変換する
+fn tablebase_score(outcome: Outcome, player: Player) -> i32 {
+    let score = match outcome {
+        Outcome::Win(d) => TB_WIN - i32::from(d),
+        Outcome::Loss(d) => -TB_WIN + i32::from(d),
+        Outcome::Draw => 0,
+    };
+    match player {
+        Player::Sente => score,
+        Player::Gote => -score,
+    }
+}
+
+// ---- 評価関数 ----
 
-    for &pt in &hand_types {
-        let value = piece_value(pt, false);
-        score += value * state.sente_hand.get(pt) as i32;
-        score -= value * state.gote_hand.get(pt) as i32;
+/// 終局（玉が取られた局面）を判定したうえで評価関数を呼ぶ
+fn evaluate<E: Evaluator + ?Sized>(evaluator: &E, pos: &SearchState) -> i32 {
+    if !rules::has_king(&pos.state, Player::Sente) {
+        return -INF;
+    }
+    if !rules::has_king(&pos.state, Player::Gote) {
+        return INF;
     }
+    evaluator.evaluate_position(pos)
+}
 
-    score
+/// 局面を進め、評価関数の差分更新を呼ぶ
+fn play<E: Evaluator + ?Sized>(pos: &mut SearchState, mv: Move, evaluator: &mut E) -> Undo {
+    let undo = pos.do_move(mv);
+    evaluator.on_do_move(pos, &undo);
+    undo
 }
 
-fn has_king(state: &GameState, player: Player) -> bool {
-    state.board.iter().any(|row| {
-        row.iter()
-            .any(|cell| cell.is_some_and(|p| p.piece_type == PieceType::King && p.owner == player))
-    })
+/// 局面を戻し、評価関数の差分更新を呼ぶ
+fn unplay<E: Evaluator + ?Sized>(pos: &mut SearchState, undo: Undo, evaluator: &mut E) {
+    pos.undo_move(undo);
+    evaluator.on_undo_move(pos, &undo);
 }
 
 // ---- Alpha-Beta 探索 ----
 
 pub fn best_move_alpha_beta(state: &GameState, player: Player) -> Option<Move> {
+    best_move_alpha_beta_with(state, player, &SearchOptions::default())
+}
+
+pub fn best_move_alpha_beta_with(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+) -> Option<Move> {
+    best_move_alpha_beta_with_evaluator(state, player, options, &mut Positional::default())
+}
+
+/// 評価関数を指定した Alpha-Beta 探索
+pub fn best_move_alpha_beta_with_evaluator<E: Evaluator + ?Sized>(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+    evaluator: &mut E,
+) -> Option<Move> {
+    search_alpha_beta(state, player, options, evaluator).best_move
+}
+
+/// Alpha-Beta 探索を行い、選んだ手と評価値を返す
+pub fn search_alpha_beta<E: Evaluator + ?Sized>(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+    evaluator: &mut E,
+) -> SearchResult {
+    if let Some(result) = probe_known_move(state, player, options) {
+        return result;
+    }
+
     let legal_moves = rules::generate_legal_moves(state, player);
     if legal_moves.is_empty() {
-        return None;
+        return SearchResult::default();
     }
 
-    let maximizing = player == Player::Sente;
-    let mut best_mv = None;
+    // 1つの局面を do_move / undo_move で進め・戻しながら探索する
+    let mut pos = SearchState::new(*state, player);
+    evaluator.reset(&pos);
+
+    let (first_depth, max_depth) = match (options.depth, options.is_open_ended()) {
+        (Some(depth), false) => (depth.max(1), depth.max(1)),
+        (None, false) => (SEARCH_DEPTH, SEARCH_DEPTH),
+        (depth, true) => (1, depth.unwrap_or(MAX_SEARCH_DEPTH).max(1)),
+    };
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+
+    // 反復深化では前の深さの最善手から読む
+    let mut root_moves = legal_moves;
+    let mut best = None;
+    for depth in first_depth..=max_depth {
+        // 最初の深さは時間切れでも止められても最後まで読む
+        let mut timer = match best {
+            Some(_) => Timer::new(deadline, options.stop),
+            None => Timer::new(None, None),
+        };
+        let Some((mv, score)) =
+            search_root(&mut pos, &root_moves, depth, options, evaluator, &mut timer)
+        else {
+            break;
+        };
+        best = Some((mv, score));
+        if score.abs() >= INF
+            || deadline.is_some_and(|d| Instant::now() >= d)
+            || is_stopped(options.stop)
+        {
+            break;
+        }
+        let i = root_moves.iter().position(|&m| m == mv).unwrap_or(0);
+        root_moves[..=i].rotate_right(1);
+    }
+
+    let (best_mv, best_score) = best.expect("合法手があれば最初の深さは読み終える");
+    SearchResult {
+        best_move: Some(best_mv),
+        score: Some(best_score),
+        visits: Vec::new(),
+    }
+}
+
+/// Alpha-Beta 探索の時間切れ・`stop` の判定
+///
+/// 時計と合図を見るのは `TIME_CHECK_NODES` 局面ごとにし、一度打ち切ったらそのまま探索を終わらせる。
+struct Timer<'a> {
+    deadline: Option<Instant>,
+    stop: Option<&'a AtomicBool>,
+    nodes: u32,
+    expired: bool,
+}
+
+impl<'a> Timer<'a> {
+    fn new(deadline: Option<Instant>, stop: Option<&'a AtomicBool>) -> Self {
+        Self {
+            deadline,
+            stop,
+            nodes: 0,
+            expired: false,
+        }
+    }
+
+    /// すぐに時計と合図を見て、打ち切るなら `true` を返す
+    fn poll(&mut self) -> bool {
+        self.expired = self.expired
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+            || is_stopped(self.stop);
+        self.expired
+    }
+
+    /// 局面を1つ読むごとに呼び、打ち切るなら `true` を返す
+    fn tick(&mut self) -> bool {
+        if self.deadline.is_some() || self.stop.is_some() {
+            self.nodes += 1;
+            if self.nodes >= TIME_CHECK_NODES {
+                self.nodes = 0;
+                return self.poll();
+            }
+        }
+        self.expired
+    }
+}
+
+/// 根の全候補手を `depth` の深さで読み、最善手と評価値を返す（打ち切られたら `None`）
+fn search_root<E: Evaluator + ?Sized>(
+    pos: &mut SearchState,
+    root_moves: &[Move],
+    depth: u32,
+    options: &SearchOptions,
+    evaluator: &mut E,
+    timer: &mut Timer,
+) -> Option<(Move, i32)> {
+    let maximizing = pos.player == Player::Sente;
+    let mut best_mv = root_moves[0];
     // 先手なら、最初は「無限の負（最低点）」をセットし、それより高い点数を探す。
     // 後手なら、最初は「無限の正（最高点）」をセットし、それより低い点数を探す。
     let mut best_score = if maximizing { -INF - 1 } else { INF + 1 };
 
     // 全候補手の探索ループ
-    for mv in &legal_moves {
-        let new_state = rules::make_move(state, *mv, player);
-        let score = alpha_beta(&new_state, SEARCH_DEPTH - 1, -INF, INF, !maximizing);
+    for mv in root_moves {
+        if timer.poll() {
+            return None;
+        }
+        let undo = play(pos, *mv, evaluator);
+        let score = alpha_beta(pos, depth - 1, -INF, INF, options, evaluator, timer);
+        unplay(pos, undo, evaluator);
+        // 読み切れなかった深さの結果は使わない
+        if timer.expired {
+            return None;
+        }
 
         let is_better = if maximizing {
             score > best_score
@@ -97,39 +290,47 @@ pub fn best_move_alpha_beta(state: &GameState, player: Player) -> Option<Move> {
 
         if is_better {
             best_score = score;
-            best_mv = Some(*mv);
+            best_mv = *mv;
         }
     }
 
-    best_mv
+    Some((best_mv, best_score))
 }
 
-fn alpha_beta(
-    state: &GameState,
+fn alpha_beta<E: Evaluator + ?Sized>(
+    pos: &mut SearchState,
     depth: u32,
     mut alpha: i32,
     mut beta: i32,
-    maximizing: bool,
+    options: &SearchOptions,
+    evaluator: &mut E,
+    timer: &mut Timer,
 ) -> i32 {
+    // 時間切れなら評価値は使われないので、すぐに戻る
+    if timer.tick() {
+        return 0;
+    }
+
     // 終局判定
-    if !has_king(state, Player::Sente) {
+    if !rules::has_king(&pos.state, Player::Sente) {
         return -INF;
     }
-    if !has_king(state, Player::Gote) {
+    if !rules::has_king(&pos.state, Player::Gote) {
         return INF;
     }
 
+    let player = pos.player;
+    let maximizing = player == Player::Sente;
+
+    if let Some(outcome) = tablebase::probe(options.tablebases, &pos.state, player) {
+        return tablebase_score(outcome, player);
+    }
+
     // 葉ノード
     if depth == 0 {
-        return evaluate(state);
+        return evaluate(evaluator, pos);
     }
-
-    let player = if maximizing {
-        Player::Sente
-    } else {
-        Player::Gote
-    };
-    let legal_moves = rules::generate_legal_moves(state, player);
+    let legal_moves = rules::generate_legal_moves(&pos.state, player);
 
     if legal_moves.is_empty() {
         return if maximizing { -INF } else { INF };
@@ -138,8 +339,12 @@ fn alpha_beta(
     if maximizing {
         let mut max_eval = -INF;
         for mv in &legal_moves {
-            let new_state = rules::make_move(state, *mv, player);
-            let eval = alpha_beta(&new_state, depth - 1, alpha, beta, false);
+            let undo = play(pos, *mv, evaluator);
+            let eval = alpha_beta(pos, depth - 1, alpha, beta, options, evaluator, timer);
+            unplay(pos, undo, evaluator);
+            if timer.expired {
+                return 0;
+            }
             max_eval = max_eval.max(eval);
             alpha = alpha.max(eval);
             if beta <= alpha {
@@ -150,8 +355,12 @@ fn alpha_beta(
     } else {
         let mut min_eval = INF;
         for mv in &legal_moves {
-            let new_state = rules::make_move(state, *mv, player);
-            let eval = alpha_beta(&new_state, depth - 1, alpha, beta, true);
+            let undo = play(pos, *mv, evaluator);
+            let eval = alpha_beta(pos, depth - 1, alpha, beta, options, evaluator, timer);
+            unplay(pos, undo, evaluator);
+            if timer.expired {
+                return 0;
+            }
             min_eval = min_eval.min(eval);
             beta = beta.min(eval);
             if beta <= alpha {
@@ -170,37 +379,52 @@ const MCTS_UCB1_C: f64 = 1.41;
 const MCTS_ROLLOUT_DEPTH: u32 = 10;
 
 /// xorshift64 による高速な擬似乱数生成器
-struct Rng {
+pub struct Rng {
     state: u64,
 }
 
 impl Rng {
-    fn new() -> Self {
+    pub fn new() -> Self {
         use std::time::SystemTime;
         let seed = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_default()
-            .as_nanos() as u64
-            | 1; // 0 を避ける
-        Self { state: seed }
+            .as_nanos() as u64;
+        Self::with_seed(seed)
+    }
+
+    /// 再現性のためにシードを指定して生成する
+    ///
+    /// 隣り合うシードからも無関係な系列になるよう splitmix64 で混ぜる。xorshift は 0 から抜け出せないので避ける
+    pub fn with_seed(seed: u64) -> Self {
+        let state = zobrist::splitmix(seed).1;
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
     }
 
-    fn next(&mut self) -> u64 {
+    pub fn next_u64(&mut self) -> u64 {
         self.state ^= self.state << 13;
         self.state ^= self.state >> 7;
         self.state ^= self.state << 17;
         self.state
     }
 
-    fn range(&mut self, max: usize) -> usize {
-        (self.next() as usize) % max
+    pub fn range(&mut self, max: usize) -> usize {
+        (self.next_u64() as usize) % max
     }
 }
 
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 局面は保持せず、探索中は根から手順を do_move でたどって復元する
 struct MctsNode {
     mv: Option<Move>,
     player: Player, // この局面での手番
-    state: GameState,
     visits: u32,
     wins: f64,
     children: Vec<MctsNode>,
@@ -208,12 +432,11 @@ struct MctsNode {
 }
 
 impl MctsNode {
-    fn new(state: GameState, player: Player, mv: Option<Move>) -> Self {
-        let untried_moves = rules::generate_legal_moves(&state, player);
+    fn new(pos: &SearchState, mv: Option<Move>) -> Self {
+        let untried_moves = rules::generate_legal_moves(&pos.state, pos.player);
         Self {
             mv,
-            player,
-            state,
+            player: pos.player,
             visits: 0,
             wins: 0.0,
             children: Vec::new(),
@@ -225,9 +448,10 @@ impl MctsNode {
         self.untried_moves.is_empty()
     }
 
-    fn is_terminal(&self) -> bool {
-        !has_king(&self.state, Player::Sente)
-            || !has_king(&self.state, Player::Gote)
+    /// `state` はこのノードの局面
+    fn is_terminal(&self, state: &GameState) -> bool {
+        !rules::has_king(state, Player::Sente)
+            || !rules::has_king(state, Player::Gote)
             || (self.untried_moves.is_empty() && self.children.is_empty())
     }
 
@@ -256,33 +480,69 @@ impl MctsNode {
             .map(|(i, _)| i)
     }
 
-    fn expand(&mut self) -> usize {
+    /// 未展開の手を1つ展開し、`pos` をその子ノードの局面に進める
+    fn expand<E: Evaluator + ?Sized>(
+        &mut self,
+        pos: &mut SearchState,
+        evaluator: &mut E,
+    ) -> (usize, Undo) {
         let mv = self.untried_moves.pop().unwrap();
-        let new_state = rules::make_move(&self.state, mv, self.player);
-        let next_player = rules::opponent_of(self.player);
-        let child = MctsNode::new(new_state, next_player, Some(mv));
+        let undo = play(pos, mv, evaluator);
+        let child = MctsNode::new(pos, Some(mv));
         self.children.push(child);
-        self.children.len() - 1
+        (self.children.len() - 1, undo)
     }
 }
 
 /// 短いランダムプレイアウト + 評価関数で勝率を推定
 /// 評価値を sigmoid で [0, 1] の勝率に変換して返す
 /// 返り値は「先手(Sente)から見た勝率」
-fn simulate(state: &GameState, starting_player: Player, rng: &mut Rng) -> f64 {
-    let mut current_state = *state;
-    let mut current_player = starting_player;
+fn simulate<E: Evaluator + ?Sized>(
+    pos: &mut SearchState,
+    rng: &mut Rng,
+    options: &SearchOptions,
+    evaluator: &mut E,
+) -> f64 {
+    let mut undos = Vec::with_capacity(MCTS_ROLLOUT_DEPTH as usize);
+    let result = rollout(pos, rng, options, evaluator, &mut undos);
+
+    // プレイアウト前の局面に戻す
+    while let Some(undo) = undos.pop() {
+        unplay(pos, undo, evaluator);
+    }
+    result
+}
+
+fn rollout<E: Evaluator + ?Sized>(
+    pos: &mut SearchState,
+    rng: &mut Rng,
+    options: &SearchOptions,
+    evaluator: &mut E,
+    undos: &mut Vec<Undo>,
+) -> f64 {
+    let mut moves = MoveList::new();
 
     for _ in 0..MCTS_ROLLOUT_DEPTH {
-        if !has_king(&current_state, Player::Sente) {
+        let current_player = pos.player;
+        if !rules::has_king(&pos.state, Player::Sente) {
             return 0.0; // 先手の王なし → 先手負け
         }
-        if !has_king(&current_state, Player::Gote) {
+        if !rules::has_king(&pos.state, Player::Gote) {
             return 1.0; // 後手の王なし → 先手勝ち
         }
 
+        // 終盤データベースに収録された局面なら結果が確定する
+        if let Some(outcome) = tablebase::probe(options.tablebases, &pos.state, current_player) {
+            let sente_wins = match outcome {
+                Outcome::Win(_) => current_player == Player::Sente,
+                Outcome::Loss(_) => current_player == Player::Gote,
+                Outcome::Draw => return 0.5,
+            };
+            return if sente_wins { 1.0 } else { 0.0 };
+        }
+
         // 高速版（打ち歩詰めチェック省略）でプレイアウト
-        let moves = rules::generate_moves_fast(&current_state, current_player);
+        rules::generate_moves_fast_into(&pos.state, current_player, &mut moves);
         if moves.is_empty() {
             return if current_player == Player::Sente {
                 0.0
@@ -292,68 +552,115 @@ fn simulate(state: &GameState, starting_player: Player, rng: &mut Rng) -> f64 {
         }
 
         let idx = rng.range(moves.len());
-        current_state = rules::make_move(&current_state, moves[idx], current_player);
-        current_player = rules::opponent_of(current_player);
+        undos.push(play(pos, moves[idx], evaluator));
     }
 
     // プレイアウト終了後、評価関数でスコアリング
-    let score = evaluate(&current_state) as f64;
+    let score = evaluate(evaluator, pos) as f64;
     // sigmoid: score を勝率 [0, 1] に変換（400 はスケーリング定数）
     1.0 / (1.0 + (-score / 400.0).exp())
 }
 
 pub fn best_move_mcts(state: &GameState, player: Player) -> Option<Move> {
-    let mut root = MctsNode::new(*state, player, None);
+    best_move_mcts_with(state, player, &SearchOptions::default())
+}
+
+pub fn best_move_mcts_with(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+) -> Option<Move> {
+    best_move_mcts_with_evaluator(state, player, options, &mut Positional::default())
+}
+
+/// 評価関数を指定した MCTS
+pub fn best_move_mcts_with_evaluator<E: Evaluator + ?Sized>(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+    evaluator: &mut E,
+) -> Option<Move> {
+    search_mcts(state, player, options, evaluator).best_move
+}
+
+/// MCTS を行い、選んだ手と根の勝率から換算した評価値、根での各手の訪問回数を返す
+pub fn search_mcts<E: Evaluator + ?Sized>(
+    state: &GameState,
+    player: Player,
+    options: &SearchOptions,
+    evaluator: &mut E,
+) -> SearchResult {
+    if let Some(result) = probe_known_move(state, player, options) {
+        return result;
+    }
+
+    let mut pos = SearchState::new(*state, player);
+    let mut root = MctsNode::new(&pos, None);
 
     if root.untried_moves.is_empty() {
-        return None;
+        return SearchResult::default();
     }
 
-    let mut rng = Rng::new();
+    let mut rng = options.rng();
+    evaluator.reset(&pos);
+    let mut undos = Vec::new();
 
-    for _ in 0..MCTS_ITERATIONS {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let iterations = match (options.iterations, options.is_open_ended()) {
+        (Some(n), _) => n.max(1),
+        (None, true) => u32::MAX,
+        (None, false) => MCTS_ITERATIONS,
+    };
+    for i in 0..iterations {
+        if i > 0 && (deadline.is_some_and(|d| Instant::now() >= d) || is_stopped(options.stop)) {
+            break;
+        }
         // 1. 選択 (Selection)
         let mut path: Vec<usize> = Vec::new();
         let mut node = &root;
 
         while node.is_fully_expanded() && !node.children.is_empty() {
-            if node.is_terminal() {
+            if node.is_terminal(&pos.state) {
                 break;
             }
             if let Some(idx) = node.best_child_index() {
                 path.push(idx);
                 node = &node.children[idx];
+                undos.push(play(
+                    &mut pos,
+                    node.mv.expect("子ノードは手を持つ"),
+                    evaluator,
+                ));
             } else {
                 break;
             }
         }
 
         // 2. 展開 (Expansion) + シミュレーション位置の決定
-        let sim_state;
-        let sim_player;
-
         {
             let mut current = &mut root;
             for &idx in &path {
                 current = &mut current.children[idx];
             }
 
-            if !current.is_fully_expanded() && !current.is_terminal() {
-                let child_idx = current.expand();
+            if !current.is_fully_expanded() && !current.is_terminal(&pos.state) {
+                let (child_idx, undo) = current.expand(&mut pos, evaluator);
                 path.push(child_idx);
-                sim_state = current.children[child_idx].state;
-                sim_player = current.children[child_idx].player;
-            } else {
-                sim_state = current.state;
-                sim_player = current.player;
+                undos.push(undo);
             }
         }
 
         // 3. シミュレーション (Simulation)
-        let sente_win_rate = simulate(&sim_state, sim_player, &mut rng);
+        let sente_win_rate = simulate(&mut pos, &mut rng, options, evaluator);
+
+        // 根の局面に戻す
+        while let Some(undo) = undos.pop() {
+            unplay(&mut pos, undo, evaluator);
+        }
 
         // 4. 逆伝播 (Backpropagation)
-        // 各ノードに「そのノードの手番にとっての勝率」を加算
+        // 根には「根の手番にとっての勝率」、子孫には「そのノードへ指した側にとっての勝率」を加算する
+        // （親は子の勝率が高い手を選ぶので、子の勝率は親の手番から見た値でなければならない）
         let mut current = &mut root;
         current.visits += 1;
         current.wins += if current.player == Player::Sente {
@@ -366,16 +673,34 @@ pub fn best_move_mcts(state: &GameState, player: Player) -> Option<Move> {
             current = &mut current.children[idx];
             current.visits += 1;
             current.wins += if current.player == Player::Sente {
-                sente_win_rate
-            } else {
                 1.0 - sente_win_rate
+            } else {
+                sente_win_rate
             };
         }
     }
 
     // 最も訪問回数の多い子を選択
-    root.children
-        .iter()
-        .max_by_key(|c| c.visits)
-        .and_then(|c| c.mv)
+    let best = root.children.iter().max_by_key(|c| c.visits);
+    let best_move = best.and_then(|c| c.mv);
+
+    // 選んだ手の勝率を評価値に戻す（sigmoid の逆関数）
+    let win_rate = best
+        .map_or(0.5, |c| c.wins / f64::from(c.visits.max(1)))
+        .clamp(1e-6, 1.0 - 1e-6);
+    let score = (400.0 * (win_rate / (1.0 - win_rate)).ln()).round() as i32;
+    let score = match player {
+        Player::Sente => score,
+        Player::Gote => -score,
+    };
+
+    SearchResult {
+        best_move,
+        score: Some(score),
+        visits: root
+            .children
+            .iter()
+            .filter_map(|c| Some((c.mv?, c.visits)))
+            .collect(),
+    }
 }
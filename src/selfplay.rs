@@ -0,0 +1,302 @@
+use crate::board::{self, GameState, Player, player_index};
+use crate::engine::{Builtin, Engine};
+use crate::eval::Positional;
+use crate::rules::{self, Move};
+use crate::search::{Rng, SearchOptions};
+use crate::sfen;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+// 学習用の自己対局データ
+//
+// ファイルはテキスト形式で、'#' で始まる行は注釈として読み飛ばす。1局は次の行からなる。
+//
+//   game <先手のエンジン> <後手のエンジン>
+//   <SFEN>\t<指し手>\t<評価値>\t<訪問回数>      （1手ごとに1行）
+//   result <結果>
+//
+// - SFEN は指す前の局面、指し手は USI 形式。
+// - 評価値は先手から見た値で、序盤のランダムな手や定跡の手では "-"。
+// - 訪問回数は MCTS の根での各手の訪問回数を "<指し手>:<回数>" で空白区切りに並べたもので、MCTS 以外では "-"。
+// - 結果は先手から見て "1-0"（先手勝ち）、"0-1"（後手勝ち）、"1/2-1/2"（引き分け）。
+
+/// 1手分の記録
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlyRecord {
+    /// 指す前の局面
+    pub state: GameState,
+    pub player: Player,
+    pub mv: Move,
+    /// 先手から見た評価値
+    pub score: Option<i32>,
+    /// MCTS の根での各手の訪問回数
+    pub visits: Vec<(Move, u32)>,
+}
+
+/// 1局分の記録
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// [先手, 後手] のエンジン名
+    pub engines: [String; 2],
+    pub plies: Vec<PlyRecord>,
+    /// 先手から見た結果（勝ち 1、引き分け 0.5、負け 0）
+    pub result: f64,
+}
+
+impl GameRecord {
+    pub fn moves(&self) -> Vec<Move> {
+        self.plies.iter().map(|p| p.mv).collect()
+    }
+
+    /// 結果の表記（"1-0"、"0-1"、"1/2-1/2"）
+    pub fn result_text(&self) -> &'static str {
+        format_result(self.result)
+    }
+
+    /// 定跡・評価関数の調整で使う棋譜形式の1行（指し手と結果）
+    pub fn to_record_line(&self) -> String {
+        let mut tokens: Vec<String> = self.plies.iter().map(|p| sfen::format_move(p.mv)).collect();
+        tokens.push(self.result_text().to_string());
+        tokens.join(" ")
+    }
+}
+
+/// 自己対局の設定
+#[derive(Debug, Clone, Copy)]
+pub struct SelfplayConfig {
+    /// [先手, 後手] のエンジン
+    pub engines: [Builtin; 2],
+    /// [先手, 後手] の1手あたりの思考時間（`None` なら探索の設定に従う）
+    pub time_limits: [Option<Duration>; 2],
+    /// 序盤にランダムに指す手数
+    pub random_plies: usize,
+    /// この手数に達したら引き分けとする
+    pub max_plies: usize,
+}
+
+impl Default for SelfplayConfig {
+    fn default() -> Self {
+        Self {
+            engines: [Builtin {
+                engine: Engine::AlphaBeta,
+                strength: None,
+            }; 2],
+            time_limits: [None; 2],
+            random_plies: 4,
+            max_plies: 200,
+        }
+    }
+}
+
+/// 1局自己対局する
+///
+/// 玉を取った側の勝ち、指し手がなくなった側の負けとする。
+pub fn play_game(config: &SelfplayConfig, options: &SearchOptions, rng: &mut Rng) -> GameRecord {
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    let mut plies = Vec::new();
+    let mut result = 0.5;
+
+    while plies.len() < config.max_plies {
+        let record = if plies.len() < config.random_plies {
+            // 自玉を取られる手は選ばない
+            let mut legal = rules::generate_legal_moves(&state, player);
+            legal.retain(|&mv| !rules::is_in_check(&rules::make_move(&state, mv, player), player));
+            legal
+                .get(rng.range(legal.len().max(1)))
+                .map(|&mv| PlyRecord {
+                    state,
+                    player,
+                    mv,
+                    score: None,
+                    visits: Vec::new(),
+                })
+        } else {
+            let side = player_index(player);
+            let options = SearchOptions {
+                time_limit: config.time_limits[side].or(options.time_limit),
+                ..*options
+            };
+            let found =
+                config.engines[side].search(&state, player, &options, &mut Positional::default());
+            found.best_move.map(|mv| PlyRecord {
+                state,
+                player,
+                mv,
+                score: found.score,
+                visits: found.visits,
+            })
+        };
+        let Some(record) = record else {
+            // 指し手がなければ手番側の負け
+            result = if player == Player::Sente { 0.0 } else { 1.0 };
+            break;
+        };
+
+        let captured_king = rules::captures_king(&state, record.mv);
+        state = rules::make_move(&state, record.mv, player);
+        plies.push(record);
+        if captured_king {
+            result = if player == Player::Sente { 1.0 } else { 0.0 };
+            break;
+        }
+        player = rules::opponent_of(player);
+    }
+
+    GameRecord {
+        engines: config.engines.map(Builtin::name),
+        plies,
+        result,
+    }
+}
+
+fn format_result(result: f64) -> &'static str {
+    if result > 0.5 {
+        "1-0"
+    } else if result < 0.5 {
+        "0-1"
+    } else {
+        "1/2-1/2"
+    }
+}
+
+fn parse_result(s: &str) -> Result<f64, String> {
+    match s {
+        "1-0" => Ok(1.0),
+        "0-1" => Ok(0.0),
+        "1/2-1/2" => Ok(0.5),
+        _ => Err(format!("結果が不正です: {}", s)),
+    }
+}
+
+/// 記録をテキスト形式に変換する
+pub fn to_text(games: &[GameRecord]) -> String {
+    let mut text = String::from("# minishogi-rs selfplay\n");
+    for game in games {
+        writeln!(text, "game {} {}", game.engines[0], game.engines[1]).unwrap();
+        for ply in &game.plies {
+            let score = ply.score.map_or("-".to_string(), |s| s.to_string());
+            let visits = if ply.visits.is_empty() {
+                "-".to_string()
+            } else {
+                let entries: Vec<String> = ply
+                    .visits
+                    .iter()
+                    .map(|&(mv, n)| format!("{}:{}", sfen::format_move(mv), n))
+                    .collect();
+                entries.join(" ")
+            };
+            writeln!(
+                text,
+                "{}\t{}\t{}\t{}",
+                sfen::format(&ply.state, ply.player),
+                sfen::format_move(ply.mv),
+                score,
+                visits
+            )
+            .unwrap();
+        }
+        writeln!(text, "result {}", game.result_text()).unwrap();
+    }
+    text
+}
+
+/// テキスト形式の記録を読み込む
+pub fn parse(text: &str) -> Result<Vec<GameRecord>, String> {
+    let mut games = Vec::new();
+    let mut current: Option<GameRecord> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let err = |e: String| format!("{}行目: {}", i + 1, e);
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("game ") {
+            if current.is_some() {
+                return Err(err("前の対局に結果がありません".to_string()));
+            }
+            let names: Vec<&str> = rest.split_whitespace().collect();
+            let [sente, gote] = names[..] else {
+                return Err(err("エンジン名が2つ必要です".to_string()));
+            };
+            current = Some(GameRecord {
+                engines: [sente.to_string(), gote.to_string()],
+                plies: Vec::new(),
+                result: 0.5,
+            });
+        } else if let Some(rest) = line.strip_prefix("result ") {
+            let mut game = current
+                .take()
+                .ok_or_else(|| err("game 行がありません".to_string()))?;
+            game.result = parse_result(rest.trim()).map_err(err)?;
+            games.push(game);
+        } else {
+            let game = current
+                .as_mut()
+                .ok_or_else(|| err("game 行がありません".to_string()))?;
+            game.plies.push(parse_ply(line).map_err(err)?);
+        }
+    }
+
+    if current.is_some() {
+        return Err("最後の対局に結果がありません".to_string());
+    }
+    Ok(games)
+}
+
+fn parse_ply(line: &str) -> Result<PlyRecord, String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [position, mv, score, visits] = fields[..] else {
+        return Err("タブ区切りの4項目が必要です".to_string());
+    };
+
+    let (state, player) = sfen::parse(position)?;
+    let mv = sfen::parse_move(mv)?;
+    if !rules::generate_legal_moves(&state, player).contains(&mv) {
+        return Err(format!("非合法な手です: {}", sfen::format_move(mv)));
+    }
+    let score = match score {
+        "-" => None,
+        s => Some(
+            s.parse::<i32>()
+                .map_err(|_| format!("評価値が不正です: {}", s))?,
+        ),
+    };
+    let visits = match visits {
+        "-" => Vec::new(),
+        s => s
+            .split_whitespace()
+            .map(|entry| {
+                let (mv, n) = entry
+                    .split_once(':')
+                    .ok_or_else(|| format!("訪問回数が不正です: {}", entry))?;
+                let n = n
+                    .parse::<u32>()
+                    .map_err(|_| format!("訪問回数が不正です: {}", entry))?;
+                Ok((sfen::parse_move(mv)?, n))
+            })
+            .collect::<Result<_, String>>()?,
+    };
+
+    Ok(PlyRecord {
+        state,
+        player,
+        mv,
+        score,
+        visits,
+    })
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<GameRecord>> {
+    let text = fs::read_to_string(path)?;
+    parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save<P: AsRef<Path>>(path: P, games: &[GameRecord]) -> io::Result<()> {
+    fs::write(path, to_text(games))
+}
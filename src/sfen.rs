@@ -0,0 +1,282 @@
+use crate::board::{Board, GameState, Hand, MAX_HAND, Piece, PieceType, Player};
+use crate::rules::{Move, Position};
+
+/// 初期局面の SFEN
+pub const STARTPOS: &str = "kgsbr/p4/5/4P/RBSGK b - 1";
+
+/// 持ち駒の SFEN 表記順
+const HAND_ORDER: [PieceType; 5] = [
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::Pawn,
+];
+
+pub(crate) fn piece_char(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::King => 'K',
+        PieceType::Gold => 'G',
+        PieceType::Silver => 'S',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Pawn => 'P',
+    }
+}
+
+pub(crate) fn parse_piece_char(c: char) -> Option<(PieceType, Player)> {
+    let owner = if c.is_ascii_uppercase() {
+        Player::Sente
+    } else {
+        Player::Gote
+    };
+    let piece_type = match c.to_ascii_uppercase() {
+        'K' => PieceType::King,
+        'G' => PieceType::Gold,
+        'S' => PieceType::Silver,
+        'B' => PieceType::Bishop,
+        'R' => PieceType::Rook,
+        'P' => PieceType::Pawn,
+        _ => return None,
+    };
+    Some((piece_type, owner))
+}
+
+/// 局面と手番を SFEN 文字列に変換する（手数は 1 固定）
+pub fn format(state: &GameState, player: Player) -> String {
+    let mut rows = Vec::new();
+    for row in &state.board {
+        let mut s = String::new();
+        let mut empty = 0;
+        for cell in row {
+            match cell {
+                Some(piece) => {
+                    if empty > 0 {
+                        s.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    if piece.promoted {
+                        s.push('+');
+                    }
+                    let c = piece_char(piece.piece_type);
+                    s.push(match piece.owner {
+                        Player::Sente => c,
+                        Player::Gote => c.to_ascii_lowercase(),
+                    });
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            s.push_str(&empty.to_string());
+        }
+        rows.push(s);
+    }
+
+    let side = match player {
+        Player::Sente => "b",
+        Player::Gote => "w",
+    };
+
+    let mut hand = String::new();
+    for (owner, h) in [
+        (Player::Sente, &state.sente_hand),
+        (Player::Gote, &state.gote_hand),
+    ] {
+        for &pt in &HAND_ORDER {
+            let count = h.get(pt);
+            if count == 0 {
+                continue;
+            }
+            if count > 1 {
+                hand.push_str(&count.to_string());
+            }
+            let c = piece_char(pt);
+            hand.push(match owner {
+                Player::Sente => c,
+                Player::Gote => c.to_ascii_lowercase(),
+            });
+        }
+    }
+    if hand.is_empty() {
+        hand.push('-');
+    }
+
+    format!("{} {} {} 1", rows.join("/"), side, hand)
+}
+
+/// SFEN 文字列から局面と手番を読み込む
+/// 手数フィールドは省略可能で、読み飛ばす
+pub fn parse(sfen: &str) -> Result<(GameState, Player), String> {
+    let fields: Vec<&str> = sfen.split_whitespace().collect();
+    if fields.len() < 3 {
+        return Err(format!("SFEN のフィールドが不足しています: {}", sfen));
+    }
+
+    let board = parse_board(fields[0])?;
+
+    let player = match fields[1] {
+        "b" => Player::Sente,
+        "w" => Player::Gote,
+        s => return Err(format!("手番が不正です: {}", s)),
+    };
+
+    let (sente_hand, gote_hand) = parse_hand(fields[2])?;
+
+    let state = GameState {
+        board,
+        sente_hand,
+        gote_hand,
+    };
+    match state.excess_piece() {
+        Some(PieceType::King) => Err("玉は先手・後手とも1枚までです".to_string()),
+        Some(piece_type) => Err(format!(
+            "{}が{}枚を超えています",
+            piece_char(piece_type),
+            MAX_HAND
+        )),
+        None => Ok((state, player)),
+    }
+}
+
+fn parse_board(s: &str) -> Result<Board, String> {
+    let rows: Vec<&str> = s.split('/').collect();
+    if rows.len() != 5 {
+        return Err(format!("盤面の段数が 5 ではありません: {}", s));
+    }
+
+    let mut board: Board = [[None; 5]; 5];
+    for (y, row) in rows.iter().enumerate() {
+        let mut x = 0;
+        let mut promoted = false;
+        for c in row.chars() {
+            if c == '+' {
+                promoted = true;
+                continue;
+            }
+            if let Some(n) = c.to_digit(10) {
+                if promoted {
+                    return Err(format!("'+' の後に駒がありません: {}", row));
+                }
+                x += n as usize;
+                continue;
+            }
+            let (piece_type, owner) =
+                parse_piece_char(c).ok_or_else(|| format!("不明な駒: {}", c))?;
+            if promoted && matches!(piece_type, PieceType::King | PieceType::Gold) {
+                return Err(format!("成れない駒です: +{}", c));
+            }
+            if x >= 5 {
+                return Err(format!("段の長さが 5 を超えています: {}", row));
+            }
+            board[y][x] = Some(Piece {
+                piece_type,
+                owner,
+                promoted,
+            });
+            promoted = false;
+            x += 1;
+        }
+        if x != 5 || promoted {
+            return Err(format!("段の長さが 5 ではありません: {}", row));
+        }
+    }
+
+    Ok(board)
+}
+
+fn parse_hand(s: &str) -> Result<(Hand, Hand), String> {
+    let mut sente_hand = Hand::new();
+    let mut gote_hand = Hand::new();
+    if s == "-" {
+        return Ok((sente_hand, gote_hand));
+    }
+
+    let mut count = 0u32;
+    for c in s.chars() {
+        if let Some(n) = c.to_digit(10) {
+            count = count
+                .checked_mul(10)
+                .and_then(|c| c.checked_add(n))
+                .ok_or_else(|| format!("持ち駒の枚数が大きすぎます: {}", s))?;
+            continue;
+        }
+        let (piece_type, owner) = parse_piece_char(c).ok_or_else(|| format!("不明な駒: {}", c))?;
+        if piece_type == PieceType::King {
+            return Err("王は持ち駒にできません".to_string());
+        }
+        let hand = match owner {
+            Player::Sente => &mut sente_hand,
+            Player::Gote => &mut gote_hand,
+        };
+        let n = count.max(1);
+        if n as usize > MAX_HAND - hand.get(piece_type) as usize {
+            return Err(format!(
+                "{}が{}枚を超えています",
+                piece_char(piece_type),
+                MAX_HAND
+            ));
+        }
+        for _ in 0..n {
+            hand.add(piece_type);
+        }
+        count = 0;
+    }
+    if count != 0 {
+        return Err(format!("持ち駒の末尾に数字があります: {}", s));
+    }
+
+    Ok((sente_hand, gote_hand))
+}
+
+/// 指し手を USI 形式（例: `1e1d`, `1b1a+`, `G*3c`）に変換する
+pub fn format_move(mv: Move) -> String {
+    match mv {
+        Move::To(from, to, promote) => format!(
+            "{}{}{}",
+            format_square(from),
+            format_square(to),
+            if promote { "+" } else { "" }
+        ),
+        Move::Drop(to, piece_type) => format!("{}*{}", piece_char(piece_type), format_square(to)),
+    }
+}
+
+/// USI 形式の指し手を読み込む（合法性は判定しない）
+pub fn parse_move(s: &str) -> Result<Move, String> {
+    let chars: Vec<char> = s.chars().collect();
+    match chars.as_slice() {
+        [p, '*', f, r] => {
+            let (piece_type, _) = parse_piece_char(p.to_ascii_uppercase())
+                .ok_or_else(|| format!("不明な駒: {}", p))?;
+            if piece_type == PieceType::King {
+                return Err("王は打てません".to_string());
+            }
+            Ok(Move::Drop(parse_square(*f, *r)?, piece_type))
+        }
+        [f1, r1, f2, r2, rest @ ..] if rest.is_empty() || rest == ['+'] => Ok(Move::To(
+            parse_square(*f1, *r1)?,
+            parse_square(*f2, *r2)?,
+            !rest.is_empty(),
+        )),
+        _ => Err(format!("指し手の形式が不正です: {}", s)),
+    }
+}
+
+pub(crate) fn format_square(pos: Position) -> String {
+    let file = char::from(b'5' - pos.x as u8);
+    let rank = char::from(b'a' + pos.y as u8);
+    format!("{}{}", file, rank)
+}
+
+pub(crate) fn parse_square(file: char, rank: char) -> Result<Position, String> {
+    let x = match file {
+        '1'..='5' => 5 - file.to_digit(10).unwrap() as usize,
+        _ => return Err(format!("筋が不正です: {}", file)),
+    };
+    let y = match rank {
+        'a'..='e' => rank as usize - 'a' as usize,
+        _ => return Err(format!("段が不正です: {}", rank)),
+    };
+    Ok(Position::new(x, y))
+}
@@ -0,0 +1,783 @@
+use crate::board::{GameState, Hand, MAX_HAND, Piece, PieceType, Player, player_index};
+use crate::rules::{self, Move, Position};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::thread;
+
+// 終盤データベース（後退解析）
+//
+// 両玉 + 指定した駒の組み合わせについて、手番側から見た勝ち・負け・引き分けと
+// 玉を取られるまでの手数（plies）を全局面について求める。
+// 持ち駒は駒を取っても相手の持ち駒になるだけなので、駒の組み合わせは指し手で変化しない。
+//
+// 索引は対称性で詰める。後手番の局面は盤を180度回して先後を入れ替えた先手番の局面として、
+// 先手玉が右側（2筋・1筋）にある局面は左右を反転した局面として引く。
+// 玉以外の駒は玉のいないマスだけを数え、同じ駒種の駒は並べ替えて重複組み合わせとして数える。
+
+const MAGIC: &[u8; 4] = b"MSTB";
+const VERSION: u8 = 2;
+
+/// 未解決（＝引き分け）
+const UNKNOWN: u16 = 0;
+/// 局面として成立しない、または同一局面の別表現
+const INVALID: u16 = u16::MAX;
+
+/// 玉以外に収録できる駒の枚数
+const MAX_PIECES: usize = 3;
+
+/// 玉の配置の数: 先手玉は左の3筋（15マス）、後手玉はそれ以外の24マス
+const KING_PLACEMENTS: usize = 15 * 24;
+
+/// データベースに収録できる駒
+const TABLE_PIECES: [PieceType; 5] = [
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Pawn,
+];
+
+/// 手番側から見た局面の結果（数値は王を取るまでの手数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(u16),
+    Loss(u16),
+    Draw,
+}
+
+impl Outcome {
+    /// 1手進めた局面の結果から、指す前の局面の結果に変換する
+    fn from_successor(outcome: Outcome) -> Outcome {
+        match outcome {
+            Outcome::Win(d) => Outcome::Loss(d + 1),
+            Outcome::Loss(d) => Outcome::Win(d + 1),
+            Outcome::Draw => Outcome::Draw,
+        }
+    }
+
+    /// 大きいほど手番側にとって良い順序値
+    fn rank(self) -> i32 {
+        match self {
+            Outcome::Win(d) => i32::from(u16::MAX) - i32::from(d),
+            Outcome::Draw => 0,
+            Outcome::Loss(d) => -i32::from(u16::MAX) + i32::from(d),
+        }
+    }
+}
+
+fn encode_outcome(outcome: Outcome) -> u16 {
+    match outcome {
+        Outcome::Win(d) => ((d + 1) << 1) | 1,
+        Outcome::Loss(d) => (d + 1) << 1,
+        Outcome::Draw => UNKNOWN,
+    }
+}
+
+fn decode_outcome(code: u16) -> Option<Outcome> {
+    match code {
+        INVALID => None,
+        UNKNOWN => Some(Outcome::Draw),
+        _ if code & 1 == 1 => Some(Outcome::Win((code >> 1) - 1)),
+        _ => Some(Outcome::Loss((code >> 1) - 1)),
+    }
+}
+
+fn piece_code(piece_type: PieceType) -> u8 {
+    match piece_type {
+        PieceType::King => 0,
+        PieceType::Gold => 1,
+        PieceType::Silver => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Pawn => 5,
+    }
+}
+
+fn piece_from_code(code: u8) -> Option<PieceType> {
+    TABLE_PIECES.into_iter().find(|&pt| piece_code(pt) == code)
+}
+
+fn is_promotable(piece_type: PieceType) -> bool {
+    !matches!(piece_type, PieceType::King | PieceType::Gold)
+}
+
+fn player_from_index(i: usize) -> Player {
+    if i == 0 { Player::Sente } else { Player::Gote }
+}
+
+/// 駒1枚の所在の数: 盤上（手番2 × 成/不成 × 玉のいない23マス）+ 持ち駒（手番2）
+fn slot_size(piece_type: PieceType) -> usize {
+    let per_owner = if is_promotable(piece_type) { 46 } else { 23 };
+    2 * per_owner + 2
+}
+
+/// 玉のいないマスだけを数えたマスの番号
+fn square_code(sq: usize, kings: [usize; 2]) -> usize {
+    sq - kings.iter().filter(|&&k| k < sq).count()
+}
+
+fn square_from_code(code: usize, kings: [usize; 2]) -> usize {
+    (0..25)
+        .filter(|sq| !kings.contains(sq))
+        .nth(code)
+        .expect("玉のいないマスは23マス")
+}
+
+/// 駒1枚の所在をコード化する
+fn location_code(piece_type: PieceType, location: Location, kings: [usize; 2]) -> usize {
+    let per_owner = slot_size(piece_type) / 2 - 1;
+    match location {
+        Location::Board(pos, owner, promoted) => {
+            player_index(owner) * per_owner
+                + usize::from(promoted) * 23
+                + square_code(pos.y * 5 + pos.x, kings)
+        }
+        Location::Hand(owner) => 2 * per_owner + player_index(owner),
+    }
+}
+
+fn location_from_code(piece_type: PieceType, code: usize, kings: [usize; 2]) -> Location {
+    let per_owner = slot_size(piece_type) / 2 - 1;
+    if code >= 2 * per_owner {
+        return Location::Hand(player_from_index(code - 2 * per_owner));
+    }
+    let owner = player_from_index(code / per_owner);
+    let rest = code % per_owner;
+    let sq = square_from_code(rest % 23, kings);
+    Location::Board(Position::new(sq % 5, sq / 5), owner, rest >= 23)
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// 同じ駒種 `count` 枚の所在の組み合わせの数（重複組み合わせ）
+fn group_size(piece_type: PieceType, count: usize) -> usize {
+    binomial(slot_size(piece_type) + count - 1, count)
+}
+
+/// 昇順に並べた所在の組を組み合わせ数的記数法で番号にする
+fn group_rank(codes: &[usize]) -> usize {
+    codes
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| binomial(c + i, i + 1))
+        .sum()
+}
+
+fn group_from_rank(mut rank: usize, count: usize) -> Vec<usize> {
+    let mut codes = vec![0; count];
+    for i in (0..count).rev() {
+        let mut c = 0;
+        while binomial(c + 1 + i, i + 1) <= rank {
+            c += 1;
+        }
+        rank -= binomial(c + i, i + 1);
+        codes[i] = c;
+    }
+    codes
+}
+
+/// 駒の組み合わせを同じ駒種ごとにまとめる（piece_code 順）
+fn groups(material: &[PieceType]) -> Vec<(PieceType, usize)> {
+    let mut groups: Vec<(PieceType, usize)> = Vec::new();
+    for &pt in material {
+        match groups.last_mut() {
+            Some((last, count)) if *last == pt => *count += 1,
+            _ => groups.push((pt, 1)),
+        }
+    }
+    groups
+}
+
+/// 盤を180度回して先後を入れ替える
+fn flip(state: &GameState) -> GameState {
+    let mut flipped = GameState {
+        board: [[None; 5]; 5],
+        sente_hand: state.gote_hand,
+        gote_hand: state.sente_hand,
+    };
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            flipped.board[4 - y][4 - x] = cell.map(|piece| Piece {
+                owner: rules::opponent_of(piece.owner),
+                ..piece
+            });
+        }
+    }
+    flipped
+}
+
+/// 左右を反転する
+fn mirror(state: &GameState) -> GameState {
+    let mut mirrored = *state;
+    for row in mirrored.board.iter_mut() {
+        row.reverse();
+    }
+    mirrored
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Location {
+    Board(Position, Player, bool),
+    Hand(Player),
+}
+
+/// 駒の組み合わせを表す文字列（例: "GP"）を読み込む
+pub fn parse_material(s: &str) -> Result<Vec<PieceType>, String> {
+    s.chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'G' => Ok(PieceType::Gold),
+            'S' => Ok(PieceType::Silver),
+            'B' => Ok(PieceType::Bishop),
+            'R' => Ok(PieceType::Rook),
+            'P' => Ok(PieceType::Pawn),
+            _ => Err(format!("データベースに使えない駒です: {}", c)),
+        })
+        .collect()
+}
+
+/// 両玉と指定駒の終盤データベース
+pub struct Tablebase {
+    /// 玉以外の駒（piece_code 順）
+    material: Vec<PieceType>,
+    entries: Vec<u16>,
+}
+
+impl Tablebase {
+    pub fn material(&self) -> &[PieceType] {
+        &self.material
+    }
+
+    /// 収録局面数（成立しない局面も含む索引の大きさ）
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn table_size(material: &[PieceType]) -> usize {
+        groups(material)
+            .into_iter()
+            .map(|(pt, count)| group_size(pt, count))
+            .product::<usize>()
+            * KING_PLACEMENTS
+    }
+
+    /// 局面を索引に変換する。駒の組み合わせが一致しなければ None
+    ///
+    /// 対称な局面は同じ索引になる。先手玉が中央の筋にあれば、左右を反転した局面と小さいほうの索引を使う。
+    fn index(&self, state: &GameState, player: Player) -> Option<usize> {
+        let state = match player {
+            Player::Sente => *state,
+            Player::Gote => flip(state),
+        };
+        let king = state.board.iter().flatten().position(|cell| {
+            matches!(cell, Some(piece) if piece.piece_type == PieceType::King && piece.owner == Player::Sente)
+        })?;
+        match (king % 5).cmp(&2) {
+            Ordering::Less => self.canonical_index(&state),
+            Ordering::Greater => self.canonical_index(&mirror(&state)),
+            Ordering::Equal => [state, mirror(&state)]
+                .iter()
+                .filter_map(|state| self.canonical_index(state))
+                .min(),
+        }
+    }
+
+    /// 先手玉が左の3筋にある先手番の局面の索引
+    fn canonical_index(&self, state: &GameState) -> Option<usize> {
+        let mut kings = [None; 2];
+        for (y, row) in state.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let Some(piece) = cell else { continue };
+                if piece.piece_type == PieceType::King
+                    && kings[player_index(piece.owner)]
+                        .replace(y * 5 + x)
+                        .is_some()
+                {
+                    return None;
+                }
+            }
+        }
+        let [Some(sente_king), Some(gote_king)] = kings else {
+            return None;
+        };
+        if sente_king % 5 > 2 {
+            return None;
+        }
+        let kings = [sente_king, gote_king];
+
+        let mut pieces: Vec<(u8, usize)> = Vec::with_capacity(self.material.len());
+        for (y, row) in state.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let Some(piece) = cell else { continue };
+                if piece.piece_type == PieceType::King {
+                    continue;
+                }
+                let loc = Location::Board(Position::new(x, y), piece.owner, piece.promoted);
+                pieces.push((
+                    piece_code(piece.piece_type),
+                    location_code(piece.piece_type, loc, kings),
+                ));
+                if pieces.len() > self.material.len() {
+                    return None;
+                }
+            }
+        }
+
+        for owner in [Player::Sente, Player::Gote] {
+            let hand = state.get_hand(owner);
+            for pt in TABLE_PIECES {
+                for _ in 0..hand.get(pt) {
+                    pieces.push((
+                        piece_code(pt),
+                        location_code(pt, Location::Hand(owner), kings),
+                    ));
+                }
+            }
+        }
+
+        if pieces.len() != self.material.len() {
+            return None;
+        }
+        pieces.sort_unstable();
+        if self
+            .material
+            .iter()
+            .zip(&pieces)
+            .any(|(&pt, &(code, _))| piece_code(pt) != code)
+        {
+            return None;
+        }
+
+        let gote_king = gote_king - usize::from(gote_king > sente_king);
+        let mut index = (sente_king / 5 * 3 + sente_king % 5) * 24 + gote_king;
+        let mut rest = &pieces[..];
+        for (pt, count) in groups(&self.material) {
+            let (group, tail) = rest.split_at(count);
+            let codes: Vec<usize> = group.iter().map(|&(_, loc)| loc).collect();
+            index = index * group_size(pt, count) + group_rank(&codes);
+            rest = tail;
+        }
+        Some(index)
+    }
+
+    /// 索引から先手番の局面を復元する。駒が重なる索引は None
+    fn position(&self, mut index: usize) -> Option<(GameState, Player)> {
+        let mut ranks = Vec::new();
+        for (pt, count) in groups(&self.material).into_iter().rev() {
+            let size = group_size(pt, count);
+            ranks.push((pt, count, index % size));
+            index /= size;
+        }
+        let sente_king = index / 24 / 3 * 5 + index / 24 % 3;
+        let gote_king = index % 24 + usize::from(index % 24 >= sente_king);
+        let kings = [sente_king, gote_king];
+
+        let mut locations = Vec::with_capacity(self.material.len());
+        for (pt, count, rank) in ranks {
+            for code in group_from_rank(rank, count) {
+                locations.push((pt, location_from_code(pt, code, kings)));
+            }
+        }
+
+        let mut state = GameState {
+            board: [[None; 5]; 5],
+            sente_hand: Hand::new(),
+            gote_hand: Hand::new(),
+        };
+        for (sq, owner) in [(sente_king, Player::Sente), (gote_king, Player::Gote)] {
+            state.board[sq / 5][sq % 5] = Some(Piece {
+                piece_type: PieceType::King,
+                owner,
+                promoted: false,
+            });
+        }
+
+        for (piece_type, location) in locations {
+            match location {
+                Location::Board(pos, owner, promoted) => {
+                    if state.board[pos.y][pos.x].is_some() {
+                        return None;
+                    }
+                    state.board[pos.y][pos.x] = Some(Piece {
+                        piece_type,
+                        owner,
+                        promoted,
+                    });
+                }
+                Location::Hand(owner) => state.get_hand_mut(owner).add(piece_type),
+            }
+        }
+
+        Some((state, Player::Sente))
+    }
+
+    /// 後退解析で指定した駒の組み合わせのデータベースを生成する
+    ///
+    /// 局面数は3枚（角・飛・銀など成れる駒ばかり）で約3億、2枚で約300万になる。
+    pub fn generate(material: &[PieceType]) -> Result<Self, String> {
+        if material.contains(&PieceType::King) {
+            return Err("玉は駒の組み合わせに含められません".to_string());
+        }
+        if material.len() > MAX_PIECES {
+            return Err(format!(
+                "玉以外の駒は{}枚までです（{}枚では局面数が多すぎて生成できません）",
+                MAX_PIECES,
+                material.len()
+            ));
+        }
+
+        let mut material = material.to_vec();
+        material.sort_by_key(|&pt| piece_code(pt));
+        if groups(&material)
+            .into_iter()
+            .any(|(_, count)| count > MAX_HAND)
+        {
+            return Err(format!("同じ駒は{}枚までです", MAX_HAND));
+        }
+        let size = Self::table_size(&material);
+        let mut tb = Self {
+            material,
+            entries: Vec::new(),
+        };
+
+        // 初期化: 王を取れる局面は1手勝ち、合法手のない局面は0手負け。
+        // 局面ごとに独立なので、索引を区切ってスレッドに分ける
+        let mut entries = vec![UNKNOWN; size];
+        // 未解決の後続局面の数（負け判定用）
+        let mut counts = vec![0u8; size];
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = size.div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            let handles: Vec<_> = entries
+                .chunks_mut(chunk)
+                .zip(counts.chunks_mut(chunk))
+                .enumerate()
+                .map(|(i, (entries, counts))| {
+                    let tb = &tb;
+                    scope.spawn(move || tb.initialize(i * chunk, entries, counts))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("初期化のスレッドが異常終了しました"))
+        })?;
+        tb.entries = entries;
+
+        // 手数の短い順に処理する
+        let mut queue: VecDeque<u32> = [Outcome::Loss(0), Outcome::Win(1)]
+            .into_iter()
+            .map(encode_outcome)
+            .flat_map(|code| {
+                tb.entries
+                    .iter()
+                    .enumerate()
+                    .filter(move |&(_, &e)| e == code)
+                    .map(|(index, _)| index as u32)
+            })
+            .collect();
+
+        // 後退解析: 負け局面の直前は勝ち、全後続が勝ち局面なら負け
+        while let Some(index) = queue.pop_front() {
+            let index = index as usize;
+            let outcome = decode_outcome(tb.entries[index]).expect("解決済みの局面");
+            let (state, player) = tb.position(index).expect("有効な索引");
+
+            for prev in tb.predecessors(&state, player) {
+                if tb.entries[prev] != UNKNOWN {
+                    continue;
+                }
+                match outcome {
+                    Outcome::Loss(d) => {
+                        tb.entries[prev] = encode_outcome(Outcome::Win(d + 1));
+                        queue.push_back(prev as u32);
+                    }
+                    Outcome::Win(d) => {
+                        counts[prev] -= 1;
+                        if counts[prev] == 0 {
+                            tb.entries[prev] = encode_outcome(Outcome::Loss(d + 1));
+                            queue.push_back(prev as u32);
+                        }
+                    }
+                    Outcome::Draw => unreachable!(),
+                }
+            }
+        }
+
+        Ok(tb)
+    }
+
+    /// `first` から始まる索引の局面を分類し、未解決の局面には後続局面の数を数える
+    fn initialize(
+        &self,
+        first: usize,
+        entries: &mut [u16],
+        counts: &mut [u8],
+    ) -> Result<(), String> {
+        for (offset, (entry, count)) in entries.iter_mut().zip(counts.iter_mut()).enumerate() {
+            let index = first + offset;
+            let Some((state, player)) = self.position(index) else {
+                *entry = INVALID;
+                continue;
+            };
+            if self.index(&state, player) != Some(index) {
+                *entry = INVALID;
+                continue;
+            }
+
+            let moves = rules::generate_legal_moves(&state, player);
+            if moves.iter().any(|&mv| rules::captures_king(&state, mv)) {
+                *entry = encode_outcome(Outcome::Win(1));
+                continue;
+            }
+
+            let mut successors: Vec<usize> = moves
+                .iter()
+                .filter_map(|&mv| {
+                    let next = rules::make_move(&state, mv, player);
+                    self.index(&next, rules::opponent_of(player))
+                })
+                .collect();
+            successors.sort_unstable();
+            successors.dedup();
+
+            if successors.is_empty() {
+                *entry = encode_outcome(Outcome::Loss(0));
+            } else {
+                *count = u8::try_from(successors.len())
+                    .map_err(|_| format!("後続局面が多すぎます: {}", successors.len()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 1手前の局面（相手が指して `state` になった局面）の索引を列挙する
+    fn predecessors(&self, state: &GameState, player: Player) -> Vec<usize> {
+        let mover = rules::opponent_of(player);
+        let mut result = Vec::new();
+
+        let mut push = |prev: GameState, mv: Move| {
+            let Some(index) = self.index(&prev, mover) else {
+                return;
+            };
+            if self.entries[index] != UNKNOWN {
+                return;
+            }
+            if !rules::generate_moves_fast(&prev, mover).contains(&mv) {
+                return;
+            }
+            // 打ち歩詰めになる歩打ちは指せない
+            if matches!(mv, Move::Drop(_, PieceType::Pawn)) && rules::is_checkmate(state, player) {
+                return;
+            }
+            debug_assert_eq!(rules::make_move(&prev, mv, mover), *state);
+            result.push(index);
+        };
+
+        for (y, row) in state.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let Some(piece) = *cell else { continue };
+                if piece.owner != mover {
+                    continue;
+                }
+                let to = Position::new(x, y);
+
+                // 駒打ちの逆
+                if piece.piece_type != PieceType::King && !piece.promoted {
+                    let mut prev = *state;
+                    prev.board[y][x] = None;
+                    prev.get_hand_mut(mover).add(piece.piece_type);
+                    push(prev, Move::Drop(to, piece.piece_type));
+                }
+
+                // 駒の移動の逆（成った手なら成る前の駒から）
+                let promoted_before: &[bool] = if piece.promoted {
+                    &[true, false]
+                } else {
+                    &[false]
+                };
+                for &was_promoted in promoted_before {
+                    let before = Piece {
+                        promoted: was_promoted,
+                        ..piece
+                    };
+                    let promote = piece.promoted && !was_promoted;
+                    // 逆向きの利きは相手側の駒として見た利きと一致する
+                    let froms = rules::collect_piece_destinations(
+                        &state.board,
+                        player,
+                        piece.piece_type,
+                        was_promoted,
+                        to,
+                    );
+
+                    for from in froms {
+                        if state.board[from.y][from.x].is_some() {
+                            continue;
+                        }
+                        let mv = Move::To(from, to, promote);
+
+                        let mut prev = *state;
+                        prev.board[from.y][from.x] = Some(before);
+                        prev.board[y][x] = None;
+                        push(prev, mv);
+
+                        // 駒を取った手なら、取った駒を盤に戻す
+                        for pt in TABLE_PIECES {
+                            if state.get_hand(mover).get(pt) == 0 {
+                                continue;
+                            }
+                            let promotions: &[bool] = if is_promotable(pt) {
+                                &[false, true]
+                            } else {
+                                &[false]
+                            };
+                            for &captured_promoted in promotions {
+                                let mut prev = prev;
+                                prev.get_hand_mut(mover).remove(pt);
+                                prev.board[y][x] = Some(Piece {
+                                    piece_type: pt,
+                                    owner: player,
+                                    promoted: captured_promoted,
+                                });
+                                push(prev, mv);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// 局面を引く。収録外の局面なら None
+    pub fn probe(&self, state: &GameState, player: Player) -> Option<Outcome> {
+        let index = self.index(state, player)?;
+        decode_outcome(self.entries[index])
+    }
+
+    /// ファイルに書き出す。最大値が収まれば1局面1バイトで保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        let wide = self
+            .entries
+            .iter()
+            .any(|&e| e != INVALID && e >= u16::from(u8::MAX));
+
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION, self.material.len() as u8])?;
+        for &pt in &self.material {
+            w.write_all(&[piece_code(pt)])?;
+        }
+        w.write_all(&[if wide { 2 } else { 1 }])?;
+        w.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+
+        for &e in &self.entries {
+            if wide {
+                w.write_all(&e.to_le_bytes())?;
+            } else {
+                let b = if e == INVALID { u8::MAX } else { e as u8 };
+                w.write_all(&[b])?;
+            }
+        }
+        w.flush()
+    }
+
+    /// `save` で書き出したファイルを読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut header = [0u8; 6];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("終盤データベースのファイルではありません"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("未対応のバージョンです"));
+        }
+
+        if usize::from(header[5]) > MAX_PIECES {
+            return Err(invalid("玉以外の駒が多すぎます"));
+        }
+        let mut codes = vec![0u8; header[5] as usize];
+        r.read_exact(&mut codes)?;
+        let material = codes
+            .iter()
+            .map(|&c| piece_from_code(c).ok_or_else(|| invalid("不明な駒コードです")))
+            .collect::<io::Result<Vec<_>>>()?;
+        if !codes.is_sorted() || groups(&material).iter().any(|&(_, n)| n > MAX_HAND) {
+            return Err(invalid("駒の組み合わせが不正です"));
+        }
+
+        let mut width = [0u8; 1];
+        r.read_exact(&mut width)?;
+        let mut len = [0u8; 8];
+        r.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len) as usize;
+        if len != Self::table_size(&material) {
+            return Err(invalid("局面数が駒の組み合わせと一致しません"));
+        }
+
+        let mut entries = Vec::with_capacity(len);
+        match width[0] {
+            1 => {
+                let mut buf = vec![0u8; len];
+                r.read_exact(&mut buf)?;
+                entries.extend(
+                    buf.iter()
+                        .map(|&b| if b == u8::MAX { INVALID } else { u16::from(b) }),
+                );
+            }
+            2 => {
+                let mut buf = vec![0u8; len * 2];
+                r.read_exact(&mut buf)?;
+                entries.extend(
+                    buf.chunks_exact(2)
+                        .map(|c| u16::from_le_bytes([c[0], c[1]])),
+                );
+            }
+            _ => return Err(invalid("不明な値の幅です")),
+        }
+
+        Ok(Self { material, entries })
+    }
+}
+
+/// 複数のデータベースから局面を引く
+pub fn probe(tablebases: &[Tablebase], state: &GameState, player: Player) -> Option<Outcome> {
+    tablebases.iter().find_map(|tb| tb.probe(state, player))
+}
+
+/// データベースに基づく最善手と、その局面の結果を返す
+pub fn best_move(
+    tablebases: &[Tablebase],
+    state: &GameState,
+    player: Player,
+) -> Option<(Move, Outcome)> {
+    probe(tablebases, state, player)?;
+
+    let mut best: Option<(Move, Outcome)> = None;
+    for mv in rules::generate_legal_moves(state, player) {
+        let outcome = if rules::captures_king(state, mv) {
+            Outcome::Win(1)
+        } else {
+            let next = rules::make_move(state, mv, player);
+            Outcome::from_successor(probe(tablebases, &next, rules::opponent_of(player))?)
+        };
+        if best.is_none_or(|(_, b)| outcome.rank() > b.rank()) {
+            best = Some((mv, outcome));
+        }
+    }
+    best
+}
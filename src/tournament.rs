@@ -0,0 +1,482 @@
+use crate::board::{self, GameState, Player};
+use crate::book;
+use crate::engine::EngineSpec;
+use crate::rules::{self, Move};
+use crate::search::SearchOptions;
+use crate::usi::UsiEngine;
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// エンジン同士の対局
+//
+// 2つのエンジン（A・B）を開局集の各局面から先後を入れ替えて対局させ、A から見た勝ち・引き分け・負けと
+// Elo レーティング差を求める。対局は複数のスレッドで並行して進め、SPRT を指定すれば結論が出た時点で打ち切る。
+
+/// 対局中のエンジン
+enum Contestant<'a> {
+    Builtin(&'a EngineSpec),
+    Usi(UsiEngine),
+}
+
+impl<'a> Contestant<'a> {
+    fn start(spec: &'a EngineSpec) -> io::Result<Self> {
+        match spec {
+            EngineSpec::Usi { program, args } => {
+                Ok(Contestant::Usi(UsiEngine::spawn(program, args)?))
+            }
+            builtin => Ok(Contestant::Builtin(builtin)),
+        }
+    }
+
+    fn new_game(&mut self) -> io::Result<()> {
+        match self {
+            Contestant::Builtin(_) => Ok(()),
+            Contestant::Usi(engine) => engine.new_game(),
+        }
+    }
+
+    /// 1手の思考時間 `movetime_ms` ミリ秒で指し手を選ぶ（投了なら `None`）
+    fn choose(
+        &mut self,
+        state: &GameState,
+        player: Player,
+        movetime_ms: u64,
+    ) -> io::Result<Option<Move>> {
+        match self {
+            Contestant::Builtin(spec) => {
+                let options = SearchOptions {
+                    time_limit: Some(Duration::from_millis(movetime_ms)),
+                    ..SearchOptions::default()
+                };
+                Ok(spec
+                    .search(state, player, &options)
+                    .and_then(|r| r.best_move))
+            }
+            Contestant::Usi(engine) => engine.go(state, player, movetime_ms),
+        }
+    }
+}
+
+// ---- 成績 ----
+
+/// A から見た勝ち・引き分け・負けの数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// 勝率から Elo レーティング差を求める
+fn elo_from_score(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// Elo レーティング差から期待勝率を求める
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 勝率（引き分けは 0.5 勝）
+    pub fn ratio(&self) -> f64 {
+        (f64::from(self.wins) + 0.5 * f64::from(self.draws)) / f64::from(self.games().max(1))
+    }
+
+    /// A の B に対する Elo レーティング差
+    pub fn elo(&self) -> f64 {
+        elo_from_score(self.ratio())
+    }
+
+    /// Elo レーティング差の 95% 信頼区間の幅の半分
+    pub fn elo_error(&self) -> f64 {
+        let n = f64::from(self.games().max(1));
+        let s = self.ratio();
+        if s <= 0.0 || s >= 1.0 {
+            // 全勝・全敗では Elo 差そのものが無限大になる
+            return f64::INFINITY;
+        }
+        let variance = (f64::from(self.wins) * (1.0 - s).powi(2)
+            + f64::from(self.draws) * (0.5 - s).powi(2)
+            + f64::from(self.losses) * s.powi(2))
+            / n;
+        let margin = 1.96 * (variance / n).sqrt();
+        let high = elo_from_score((s + margin).min(1.0));
+        let low = elo_from_score((s - margin).max(0.0));
+        (high - low) / 2.0
+    }
+
+    fn add(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::Loss => self.losses += 1,
+        }
+    }
+}
+
+/// 逐次確率比検定（SPRT）の設定
+///
+/// 帰無仮説「Elo 差 = `elo0`」と対立仮説「Elo 差 = `elo1`」を、第1種の誤り `alpha`、第2種の誤り `beta` で検定する。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/// SPRT の結論
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// 帰無仮説を採択（`elo0` 程度）
+    H0,
+    /// 対立仮説を採択（`elo1` 程度）
+    H1,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// 対数尤度比の下限と上限
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// 対数尤度比（勝ち・引き分け・負けの3値を正規分布で近似する）
+    pub fn llr(&self, score: &Score) -> f64 {
+        if score.wins == 0 || score.losses == 0 {
+            // 片方の結果しかないうちは分散を見積もれない
+            return 0.0;
+        }
+        let n = f64::from(score.games());
+        let w = f64::from(score.wins) / n;
+        let d = f64::from(score.draws) / n;
+        let s = w + d / 2.0;
+        let variance = (w + d / 4.0 - s * s) / n;
+        let s0 = score_from_elo(self.elo0);
+        let s1 = score_from_elo(self.elo1);
+        (s1 - s0) * (2.0 * s - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn decision(&self, score: &Score) -> Option<SprtDecision> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Some(SprtDecision::H1)
+        } else if llr <= lower {
+            Some(SprtDecision::H0)
+        } else {
+            None
+        }
+    }
+}
+
+// ---- 対局 ----
+
+/// A から見た1局の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// 1局分の記録
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameReport {
+    /// 対局の番号（0 始まり）
+    pub index: usize,
+    /// A が先手か
+    pub a_is_sente: bool,
+    pub result: GameResult,
+    /// 開局の手を含む指し手
+    pub moves: Vec<Move>,
+}
+
+/// 対局の設定
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    /// 対局数（開局ごとに先後を入れ替えた2局ずつ指す）
+    pub games: usize,
+    /// 並行して指す対局の数
+    pub concurrency: usize,
+    /// この手数に達したら引き分け
+    pub max_plies: usize,
+    /// 1手の思考時間（ミリ秒）
+    ///
+    /// 外部エンジンには秒読みとして渡し、内蔵エンジンはこの時間で探索を打ち切る。
+    pub movetime_ms: u64,
+    /// 開局（初期局面からの指し手）。空なら初期局面から指す
+    pub openings: Vec<Vec<Move>>,
+    pub sprt: Option<Sprt>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            concurrency: 1,
+            max_plies: 200,
+            movetime_ms: 1000,
+            openings: Vec::new(),
+            sprt: None,
+        }
+    }
+}
+
+/// 対局の結果
+#[derive(Debug, Clone)]
+pub struct MatchReport {
+    pub score: Score,
+    /// 終わった順の各局の記録
+    pub games: Vec<GameReport>,
+    /// SPRT で打ち切ったときの結論
+    pub sprt: Option<SprtDecision>,
+}
+
+/// 開局集を読み込む
+///
+/// 1行に1つ、初期局面からの指し手を USI 形式で空白区切りに書く。空行と '#' で始まる行は読み飛ばす。
+pub fn parse_openings(text: &str) -> Result<Vec<Vec<Move>>, String> {
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        openings.push(book::replay(&tokens).map_err(|e| format!("{}行目: {}", i + 1, e))?);
+    }
+    Ok(openings)
+}
+
+/// 1局指す
+fn play_game(
+    engines: [&mut Contestant; 2],
+    opening: &[Move],
+    config: &MatchConfig,
+) -> io::Result<(Vec<Move>, Option<Player>)> {
+    let [sente, gote] = engines;
+    sente.new_game()?;
+    gote.new_game()?;
+
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    let mut moves = Vec::new();
+    for &mv in opening {
+        state = rules::make_move(&state, mv, player);
+        player = rules::opponent_of(player);
+        moves.push(mv);
+    }
+
+    while moves.len() < config.max_plies {
+        let engine = match player {
+            Player::Sente => &mut *sente,
+            Player::Gote => &mut *gote,
+        };
+        let mv = match engine.choose(&state, player, config.movetime_ms) {
+            // 時間内に指さなければ手番側の負け
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => None,
+            mv => mv?,
+        };
+        // 投了・合法手なし・非合法手は手番側の負け
+        let Some(mv) = mv.filter(|mv| rules::generate_legal_moves(&state, player).contains(mv))
+        else {
+            return Ok((moves, Some(rules::opponent_of(player))));
+        };
+
+        let captures_king = rules::captures_king(&state, mv);
+        state = rules::make_move(&state, mv, player);
+        moves.push(mv);
+        if captures_king {
+            return Ok((moves, Some(player)));
+        }
+        player = rules::opponent_of(player);
+    }
+
+    Ok((moves, None))
+}
+
+/// 対局の進み具合（スレッド間で共有する）
+struct Progress {
+    next: usize,
+    stopped: bool,
+    score: Score,
+    games: Vec<GameReport>,
+    sprt: Option<SprtDecision>,
+    error: Option<io::Error>,
+}
+
+/// A と B を対局させる
+///
+/// 1局終わるごとに、その記録とそこまでの成績を `on_game` に渡す。
+pub fn run_match<F>(
+    a: &EngineSpec,
+    b: &EngineSpec,
+    config: &MatchConfig,
+    on_game: F,
+) -> io::Result<MatchReport>
+where
+    F: Fn(&GameReport, &Score) + Sync,
+{
+    let start_position = [Vec::new()];
+    let openings: &[Vec<Move>] = if config.openings.is_empty() {
+        &start_position
+    } else {
+        &config.openings
+    };
+    let progress = Mutex::new(Progress {
+        next: 0,
+        stopped: false,
+        score: Score::default(),
+        games: Vec::new(),
+        sprt: None,
+        error: None,
+    });
+
+    let worker = || {
+        let started = Contestant::start(a).and_then(|ea| Ok((ea, Contestant::start(b)?)));
+        let (mut engine_a, mut engine_b) = match started {
+            Ok(engines) => engines,
+            Err(e) => {
+                let mut p = progress.lock().unwrap();
+                p.stopped = true;
+                p.error.get_or_insert(e);
+                return;
+            }
+        };
+
+        loop {
+            let index = {
+                let mut p = progress.lock().unwrap();
+                if p.stopped || p.next >= config.games {
+                    return;
+                }
+                p.next += 1;
+                p.next - 1
+            };
+
+            // 同じ開局を先後入れ替えて2局ずつ指す
+            let opening = &openings[index / 2 % openings.len()];
+            let a_is_sente = index % 2 == 0;
+            let engines = if a_is_sente {
+                [&mut engine_a, &mut engine_b]
+            } else {
+                [&mut engine_b, &mut engine_a]
+            };
+            let played = play_game(engines, opening, config);
+
+            let mut p = progress.lock().unwrap();
+            let (moves, winner) = match played {
+                Ok(r) => r,
+                Err(e) => {
+                    p.stopped = true;
+                    p.error.get_or_insert(e);
+                    return;
+                }
+            };
+            let result = match winner {
+                None => GameResult::Draw,
+                Some(w) if (w == Player::Sente) == a_is_sente => GameResult::Win,
+                Some(_) => GameResult::Loss,
+            };
+            let report = GameReport {
+                index,
+                a_is_sente,
+                result,
+                moves,
+            };
+            p.score.add(result);
+            on_game(&report, &p.score);
+            p.games.push(report);
+
+            if let Some(decision) = config.sprt.and_then(|sprt| sprt.decision(&p.score)) {
+                p.sprt = Some(decision);
+                p.stopped = true;
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..config.concurrency.clamp(1, config.games.max(1)) {
+            scope.spawn(worker);
+        }
+    });
+
+    let progress = progress.into_inner().unwrap();
+    if let Some(e) = progress.error {
+        return Err(e);
+    }
+    Ok(MatchReport {
+        score: progress.score,
+        games: progress.games,
+        sprt: progress.sprt,
+    })
+}
+
+/// 総当たり戦の1エンジン分の成績
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub score: Score,
+}
+
+/// すべての組み合わせで `run_match` を行い、勝ち点の多い順に並べる（SPRT は使わない）
+///
+/// 組み合わせが1つ終わるごとに、その2エンジンと結果を `on_pair` に渡す。
+pub fn run_tournament<F>(
+    engines: &[EngineSpec],
+    config: &MatchConfig,
+    mut on_pair: F,
+) -> io::Result<Vec<Standing>>
+where
+    F: FnMut(&EngineSpec, &EngineSpec, &MatchReport),
+{
+    let config = MatchConfig {
+        sprt: None,
+        ..config.clone()
+    };
+    let mut standings: Vec<Standing> = engines
+        .iter()
+        .map(|e| Standing {
+            name: e.name(),
+            score: Score::default(),
+        })
+        .collect();
+
+    for i in 0..engines.len() {
+        for j in i + 1..engines.len() {
+            let report = run_match(&engines[i], &engines[j], &config, |_, _| {})?;
+            let s = report.score;
+            let a = &mut standings[i].score;
+            a.wins += s.wins;
+            a.draws += s.draws;
+            a.losses += s.losses;
+            let b = &mut standings[j].score;
+            b.wins += s.losses;
+            b.draws += s.draws;
+            b.losses += s.wins;
+            on_pair(&engines[i], &engines[j], &report);
+        }
+    }
+
+    standings.sort_by(|x, y| y.score.ratio().total_cmp(&x.score.ratio()));
+    Ok(standings)
+}
@@ -0,0 +1,147 @@
+use crate::board::{GameState, Player};
+use crate::mate;
+use crate::rules::{self, Move};
+use crate::sfen;
+
+/// 詰将棋の問題
+#[derive(Debug, Clone, Copy)]
+pub struct Problem {
+    pub name: &'static str,
+    pub sfen: &'static str,
+    pub plies: u32,
+}
+
+/// 組み込みの問題集（いずれも作意が唯一解になることをソルバーで確認済み）
+pub const PROBLEMS: [Problem; 5] = [
+    Problem {
+        name: "1手詰 その1",
+        sfen: "4k/5/4P/5/K4 b G 1",
+        plies: 1,
+    },
+    Problem {
+        name: "3手詰 その1",
+        sfen: "5/k4/5/1P3/K4 b RBS 1",
+        plies: 3,
+    },
+    Problem {
+        name: "3手詰 その2",
+        sfen: "R4/1k3/5/3G1/K4 b GP 1",
+        plies: 3,
+    },
+    Problem {
+        name: "5手詰 その1",
+        sfen: "1k3/3B1/3G1/5/K4 b R 1",
+        plies: 5,
+    },
+    Problem {
+        name: "5手詰 その2",
+        sfen: "5/1k3/5/3R1/K4 b BG 1",
+        plies: 5,
+    },
+];
+
+/// 攻め方の手に対する判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// 詰み上がり。`alternative` は作意と異なる手順だったか
+    Solved { alternative: bool },
+    /// 正解。玉方が `reply` で応じた
+    Continue { reply: Move, alternative: bool },
+    /// 王手になっていない
+    NotCheck,
+    /// 残り手数内に詰まない
+    NoMate,
+}
+
+/// 詰将棋の対局状態
+pub struct Session {
+    pub state: GameState,
+    pub attacker: Player,
+    /// 残り手数（攻め方の手番時点）
+    pub remaining: u32,
+    /// 作意手順（玉方は最長抵抗）
+    pub solution: Vec<Move>,
+    played: Vec<Move>,
+}
+
+impl Session {
+    /// 局面と手数制限から問題を開始する。詰みがなければエラー
+    pub fn new(state: GameState, attacker: Player, plies: u32) -> Result<Self, String> {
+        let defender = rules::opponent_of(attacker);
+        if rules::is_in_check(&state, attacker) {
+            return Err("攻め方の玉に王手がかかっています".to_string());
+        }
+        if rules::is_in_check(&state, defender) {
+            return Err("玉方に既に王手がかかっています".to_string());
+        }
+        let solution = mate::solve(&state, attacker, plies)
+            .ok_or_else(|| format!("{}手以内の詰みがありません", plies))?;
+
+        Ok(Self {
+            state,
+            attacker,
+            remaining: solution.len() as u32,
+            solution,
+            played: Vec::new(),
+        })
+    }
+
+    /// SFEN から問題を開始する。手番側が攻め方
+    pub fn from_sfen(s: &str, plies: u32) -> Result<Self, String> {
+        let (state, attacker) = sfen::parse(s)?;
+        Self::new(state, attacker, plies)
+    }
+
+    /// 作意手順に沿って進んでいるか
+    fn on_main_line(&self) -> bool {
+        self.solution.starts_with(&self.played)
+    }
+
+    /// 攻め方の手を指し、玉方の最長抵抗で応じる
+    pub fn play(&mut self, mv: Move) -> Verdict {
+        let defender = rules::opponent_of(self.attacker);
+        let next = rules::make_move(&self.state, mv, self.attacker);
+
+        if !rules::is_in_check(&next, defender) || rules::is_in_check(&next, self.attacker) {
+            return Verdict::NotCheck;
+        }
+
+        let mating = mate::mating_moves(&self.state, self.attacker, self.remaining);
+        if !mating.iter().any(|&(m, _)| m == mv) {
+            return Verdict::NoMate;
+        }
+
+        let expected = self.solution.get(self.played.len()).copied();
+        let alternative = !self.on_main_line() || expected != Some(mv);
+
+        self.state = next;
+        self.played.push(mv);
+        self.remaining -= 1;
+
+        if rules::is_checkmate(&self.state, defender) {
+            return Verdict::Solved { alternative };
+        }
+
+        // 作意手順上なら作意の応手、外れていれば最長抵抗を探す
+        let reply = if self.on_main_line() {
+            self.solution[self.played.len()]
+        } else {
+            mate::best_defence(&self.state, self.attacker, self.remaining)
+                .expect("王手を回避する手が存在する")
+        };
+
+        self.state = rules::make_move(&self.state, reply, defender);
+        self.played.push(reply);
+        self.remaining -= 1;
+
+        Verdict::Continue { reply, alternative }
+    }
+
+    /// 現局面で残り手数内に詰む攻め方の手（別解の確認用）
+    pub fn mating_moves(&self) -> Vec<Move> {
+        mate::mating_moves(&self.state, self.attacker, self.remaining)
+            .into_iter()
+            .map(|(mv, _)| mv)
+            .collect()
+    }
+}
@@ -0,0 +1,266 @@
+use crate::board::{self, GameState, PieceType, Player};
+use crate::book;
+use crate::eval::{PARAM_COUNT, Positional, Weights};
+use crate::rules;
+use crate::search::Rng;
+
+// Texel 法による評価関数の調整
+// 対局結果の分かっている局面を集め、評価値を sigmoid で勝率に変換した値と結果との
+// ロジスティック損失（交差エントロピー）が小さくなるように重みを勾配法（Adam）で調整する。
+// 評価値は重みについて線形なので、局面ごとの係数（`Positional::features`）を最初に1度だけ求めておく。
+
+/// 評価値を勝率に変換する sigmoid のスケール（MCTS のプレイアウトと同じ）
+pub const SCALE: f64 = 400.0;
+
+/// 結果付きの局面
+#[derive(Debug, Clone)]
+pub struct Sample {
+    features: Vec<(usize, i32)>,
+    /// 先手から見た結果（勝ち 1、引き分け 0.5、負け 0）
+    result: f64,
+}
+
+impl Sample {
+    pub fn new(state: &GameState, player: Player, result: f64) -> Self {
+        Self {
+            features: Positional::features(state, player),
+            result,
+        }
+    }
+
+    pub fn result(&self) -> f64 {
+        self.result
+    }
+
+    /// 先手から見た評価値
+    fn score(&self, params: &[f64]) -> f64 {
+        self.features
+            .iter()
+            .map(|&(i, c)| params[i] * f64::from(c))
+            .sum()
+    }
+}
+
+fn sigmoid(score: f64) -> f64 {
+    1.0 / (1.0 + (-score / SCALE).exp())
+}
+
+/// 棋譜集から結果付きの局面を集める
+///
+/// 棋譜の形式は定跡の `Book::from_records` と同じ（1行1局、USI 形式の指し手と末尾の結果）。
+/// 結果のない対局と、玉が取られた後の局面は使わない。
+pub fn samples_from_records(text: &str) -> Result<Vec<Sample>, String> {
+    Ok(positions_from_records(text)?
+        .iter()
+        .map(|(state, player, result)| Sample::new(state, *player, *result))
+        .collect())
+}
+
+/// 初期局面からの1局分の指し手と先手から見た結果から、各局面を集める
+pub fn samples_from_game(moves: &[rules::Move], result: f64) -> Vec<Sample> {
+    positions_from_game(moves, result)
+        .iter()
+        .map(|(state, player, result)| Sample::new(state, *player, *result))
+        .collect()
+}
+
+/// `samples_from_records` と同じ局面を（局面, 手番, 先手から見た結果）の組で返す
+pub fn positions_from_records(text: &str) -> Result<Vec<(GameState, Player, f64)>, String> {
+    let mut positions = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut tokens: Vec<&str> = line.split_whitespace().collect();
+        let result = match tokens.last().copied() {
+            Some("1-0") => 1.0,
+            Some("0-1") => 0.0,
+            Some("1/2-1/2") => 0.5,
+            _ => continue,
+        };
+        tokens.pop();
+
+        let moves = book::replay(&tokens).map_err(|e| format!("{}行目: {}", i + 1, e))?;
+        positions.extend(positions_from_game(&moves, result));
+    }
+
+    Ok(positions)
+}
+
+/// `samples_from_game` と同じ局面を（局面, 手番, 先手から見た結果）の組で返す
+pub fn positions_from_game(moves: &[rules::Move], result: f64) -> Vec<(GameState, Player, f64)> {
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    let mut positions = Vec::with_capacity(moves.len() + 1);
+
+    for &mv in moves {
+        if has_both_kings(&state) {
+            positions.push((state, player, result));
+        }
+        state = rules::make_move(&state, mv, player);
+        player = rules::opponent_of(player);
+    }
+    if has_both_kings(&state) {
+        positions.push((state, player, result));
+    }
+
+    positions
+}
+
+/// 序盤の `random_plies` 手をランダムに指し、以降は `engine` の手で自己対局する
+///
+/// 玉を取った側の勝ち、指し手がなくなった側の負けとし、`max_plies` 手に達すれば引き分け（0.5）とする。
+/// 指し手と先手から見た結果を返す。
+pub fn selfplay_game<F>(
+    rng: &mut Rng,
+    random_plies: usize,
+    max_plies: usize,
+    mut engine: F,
+) -> (Vec<rules::Move>, f64)
+where
+    F: FnMut(&GameState, Player) -> Option<rules::Move>,
+{
+    let mut state = board::init();
+    let mut player = Player::Sente;
+    let mut moves = Vec::new();
+
+    while moves.len() < max_plies {
+        let mv = if moves.len() < random_plies {
+            let legal = rules::generate_legal_moves(&state, player);
+            if legal.is_empty() {
+                None
+            } else {
+                Some(legal[rng.range(legal.len())])
+            }
+        } else {
+            engine(&state, player)
+        };
+        let Some(mv) = mv else {
+            // 指し手がなければ手番側の負け
+            let result = match player {
+                Player::Sente => 0.0,
+                Player::Gote => 1.0,
+            };
+            return (moves, result);
+        };
+
+        moves.push(mv);
+        state = rules::make_move(&state, mv, player);
+        if !has_both_kings(&state) {
+            let result = match player {
+                Player::Sente => 1.0,
+                Player::Gote => 0.0,
+            };
+            return (moves, result);
+        }
+        player = rules::opponent_of(player);
+    }
+
+    (moves, 0.5)
+}
+
+fn has_both_kings(state: &GameState) -> bool {
+    let kings = state
+        .board
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|p| p.piece_type == PieceType::King)
+        .count();
+    kings == 2
+}
+
+/// 重み `weights` での平均ロジスティック損失
+pub fn loss(samples: &[Sample], weights: &Weights) -> f64 {
+    let params: Vec<f64> = weights.to_params().into_iter().map(f64::from).collect();
+    mean_loss(samples, &params)
+}
+
+fn mean_loss(samples: &[Sample], params: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = samples
+        .iter()
+        .map(|s| {
+            let p = sigmoid(s.score(params)).clamp(1e-12, 1.0 - 1e-12);
+            -(s.result * p.ln() + (1.0 - s.result) * (1.0 - p).ln())
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// 重みの調整（Adam）
+pub struct Tuner {
+    params: Vec<f64>,
+    m: Vec<f64>,
+    v: Vec<f64>,
+    steps: i32,
+    /// 1回の更新で動かす大きさ（評価値の単位）
+    pub learning_rate: f64,
+}
+
+impl Tuner {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    pub fn new(initial: &Weights) -> Self {
+        Self {
+            params: initial.to_params().into_iter().map(f64::from).collect(),
+            m: vec![0.0; PARAM_COUNT],
+            v: vec![0.0; PARAM_COUNT],
+            steps: 0,
+            learning_rate: 1.0,
+        }
+    }
+
+    /// 全局面の勾配で1回更新し、更新前の損失を返す
+    pub fn step(&mut self, samples: &[Sample]) -> f64 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        // 交差エントロピーの勾配は (予測 - 結果) × 係数 / SCALE
+        let mut grad = vec![0.0; PARAM_COUNT];
+        let mut total = 0.0;
+        for s in samples {
+            let p = sigmoid(s.score(&self.params));
+            let pc = p.clamp(1e-12, 1.0 - 1e-12);
+            total -= s.result * pc.ln() + (1.0 - s.result) * (1.0 - pc).ln();
+            let d = (p - s.result) / SCALE;
+            for &(i, c) in &s.features {
+                grad[i] += d * f64::from(c);
+            }
+        }
+
+        let n = samples.len() as f64;
+        self.steps += 1;
+        let bias1 = 1.0 - Self::BETA1.powi(self.steps);
+        let bias2 = 1.0 - Self::BETA2.powi(self.steps);
+        for (i, g) in grad.into_iter().enumerate() {
+            let g = g / n;
+            self.m[i] = Self::BETA1 * self.m[i] + (1.0 - Self::BETA1) * g;
+            self.v[i] = Self::BETA2 * self.v[i] + (1.0 - Self::BETA2) * g * g;
+            let m = self.m[i] / bias1;
+            let v = self.v[i] / bias2;
+            self.params[i] -= self.learning_rate * m / (v.sqrt() + Self::EPSILON);
+        }
+
+        total / n
+    }
+
+    /// 現在の重み（整数に丸める）
+    pub fn weights(&self) -> Weights {
+        let params: Vec<i32> = self.params.iter().map(|p| p.round() as i32).collect();
+        Weights::from_params(&params)
+    }
+
+    /// 現在の（丸める前の）重みでの損失
+    pub fn loss(&self, samples: &[Sample]) -> f64 {
+        mean_loss(samples, &self.params)
+    }
+}
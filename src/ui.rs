@@ -1,28 +1,23 @@
 use crate::board::{GameState, Hand, PieceType, Player};
+use crate::locale::Locale;
 use crate::rules::Position;
+use crate::tr;
 
-fn piece_name(piece_type: PieceType, promoted: bool) -> &'static str {
-    if promoted {
-        match piece_type {
-            PieceType::Pawn => "と",
-            PieceType::Silver => "全",
-            PieceType::Bishop => "馬",
-            PieceType::Rook => "龍",
-            _ => unreachable!(),
-        }
+/// 盤面のマス目に収まるように駒の名前を揃える（全角の漢字は1文字、英字は2文字分の幅）
+fn cell_name(name: &str) -> String {
+    if name.is_ascii() {
+        format!("{:<2}", name)
     } else {
-        match piece_type {
-            PieceType::King => "王",
-            PieceType::Gold => "金",
-            PieceType::Silver => "銀",
-            PieceType::Bishop => "角",
-            PieceType::Rook => "飛",
-            PieceType::Pawn => "歩",
-        }
+        name.to_string()
     }
 }
 
-pub fn print_board(state: &GameState, perspective: Player, last_move_to: Option<Position>) {
+pub fn print_board(
+    state: &GameState,
+    perspective: Player,
+    last_move_to: Option<Position>,
+    locale: Locale,
+) {
     let row_labels = ['a', 'b', 'c', 'd', 'e'];
 
     // 視点に応じて列ヘッダと行の走査順を変える
@@ -61,7 +56,8 @@ pub fn print_board(state: &GameState, perspective: Player, last_move_to: Option<
                     } else {
                         "^"
                     };
-                    print!("{}{}|", mark, piece_name(p.piece_type, p.promoted));
+                    let name = locale.piece_name(p.piece_type, p.promoted);
+                    print!("{}{}|", mark, cell_name(name));
                 }
                 None => {
                     if is_last_move {
@@ -77,33 +73,32 @@ pub fn print_board(state: &GameState, perspective: Player, last_move_to: Option<
     }
 }
 
-pub fn print_hand(hand: &Hand, player: Player) {
-    let player_name = match player {
-        Player::Sente => "先手",
-        Player::Gote => "後手",
-    };
-
-    print!("{}の持ち駒: ", player_name);
+pub fn print_hand(hand: &Hand, player: Player, locale: Locale) {
+    print!(
+        "{}",
+        tr!(
+            locale,
+            "{}の持ち駒: ",
+            "{} in hand: ",
+            locale.player_name(player)
+        )
+    );
 
     let mut pieces = Vec::new();
-    if hand.gold > 0 {
-        pieces.push(format!("金{}", hand.gold));
-    }
-    if hand.silver > 0 {
-        pieces.push(format!("銀{}", hand.silver));
-    }
-    if hand.bishop > 0 {
-        pieces.push(format!("角{}", hand.bishop));
-    }
-    if hand.rook > 0 {
-        pieces.push(format!("飛{}", hand.rook));
-    }
-    if hand.pawn > 0 {
-        pieces.push(format!("歩{}", hand.pawn));
+    for (piece_type, count) in [
+        (PieceType::Gold, hand.gold),
+        (PieceType::Silver, hand.silver),
+        (PieceType::Bishop, hand.bishop),
+        (PieceType::Rook, hand.rook),
+        (PieceType::Pawn, hand.pawn),
+    ] {
+        if count > 0 {
+            pieces.push(format!("{}{}", locale.piece_name(piece_type, false), count));
+        }
     }
 
     if pieces.is_empty() {
-        println!("なし");
+        println!("{}", locale.text("なし", "none"));
     } else {
         println!("{}", pieces.join(" "));
     }
@@ -113,6 +108,7 @@ pub fn print_game_state(
     state: &GameState,
     perspective: Player,
     last_move_to: Option<Position>,
+    locale: Locale,
 ) {
     // 相手の持ち駒を上、自分の持ち駒を下に表示
     let (top_player, bottom_player) = match perspective {
@@ -121,10 +117,10 @@ pub fn print_game_state(
     };
 
     println!();
-    print_hand(state.get_hand(top_player), top_player);
+    print_hand(state.get_hand(top_player), top_player, locale);
     println!();
-    print_board(state, perspective, last_move_to);
+    print_board(state, perspective, last_move_to, locale);
     println!();
-    print_hand(state.get_hand(bottom_player), bottom_player);
+    print_hand(state.get_hand(bottom_player), bottom_player, locale);
     println!();
 }
@@ -0,0 +1,322 @@
+use crate::board::{self, GameState, Player};
+use crate::rules::{self, Move};
+use crate::search::{SearchOptions, SearchResult};
+use crate::sfen;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// USI プロトコル
+//
+// `run_engine` はこのプログラムを USI エンジンとして標準入出力で動かし、
+// `UsiEngine` は外部の USI エンジンを子プロセスとして起動して指し手を求める。
+// 局面は 5×5 の SFEN（`sfen` モジュール）、指し手は USI 形式でやりとりする。
+
+/// エンジンとして受け付ける局面（`position` コマンド）を読み込む
+///
+/// `startpos [moves ...]` か `sfen <SFEN> [moves ...]` の形式で、指し手は合法手かどうかを確かめながら進める。
+pub fn parse_position(args: &str) -> Result<(GameState, Player), String> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let moves_at = tokens.iter().position(|&t| t == "moves");
+    let (setup, moves) = match moves_at {
+        Some(i) => (&tokens[..i], &tokens[i + 1..]),
+        None => (&tokens[..], &[][..]),
+    };
+
+    let (mut state, mut player) = match setup {
+        ["startpos"] => (board::init(), Player::Sente),
+        ["sfen", fields @ ..] => sfen::parse(&fields.join(" "))?,
+        _ => return Err(format!("局面の指定が不正です: {}", args)),
+    };
+    for token in moves {
+        let mv = sfen::parse_move(token)?;
+        if !rules::generate_legal_moves(&state, player).contains(&mv) {
+            return Err(format!("{} は合法手ではありません", token));
+        }
+        state = rules::make_move(&state, mv, player);
+        player = rules::opponent_of(player);
+    }
+
+    Ok((state, player))
+}
+
+/// `go` の持ち時間から決めた思考時間から差し引く余裕（指し手を送る間に秒読みが切れないように）
+const GO_MARGIN: Duration = Duration::from_millis(50);
+
+/// `go` の引数から1手の思考時間を決める（持ち時間の指定がなければ `None`）
+///
+/// `movetime` があればその時間、なければ残り時間の 1/20 に秒読みと加算時間を足した時間とする。
+fn think_time(args: &str, player: Player) -> Option<Duration> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let value = |key: &str| {
+        let i = tokens.iter().position(|&t| t == key)?;
+        tokens.get(i + 1)?.parse::<u64>().ok()
+    };
+    let (time, inc) = match player {
+        Player::Sente => ("btime", "binc"),
+        Player::Gote => ("wtime", "winc"),
+    };
+    let ms = match value("movetime") {
+        Some(ms) => ms,
+        None => {
+            let clock = [value(time), value("byoyomi"), value(inc)];
+            if clock.iter().all(Option::is_none) {
+                return None;
+            }
+            let [time, byoyomi, inc] = clock.map(Option::unwrap_or_default);
+            time / 20 + byoyomi + inc
+        }
+    };
+    Some(Duration::from_millis(ms).saturating_sub(GO_MARGIN))
+}
+
+/// USI エンジンとして `input` のコマンドに応答する（`quit` か入力の終わりで戻る）
+///
+/// `think` は局面と手番、`go` の指定を入れた探索の設定から指し手を探す。設定には、持ち時間
+/// （`movetime`・`byoyomi`・`btime`/`wtime` と加算）から決めた思考時間と、`stop` を受けると立つ合図が入る。
+/// `go infinite` は `stop` まで考え、持ち時間の指定がない `go` は探索の既定の深さ・反復回数で考える。
+/// 考えている間も `stop` を受け取れるように、入力は別のスレッドで読む。
+pub fn run_engine<R, W, F>(input: R, output: &mut W, mut think: F) -> io::Result<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+    F: FnMut(&GameState, Player, &SearchOptions) -> SearchResult,
+{
+    // `go` ごとに合図を作って一緒に渡し、その後に読んだ `stop`・`quit` で立てる
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        let mut current: Option<Arc<AtomicBool>> = None;
+        for line in input.lines() {
+            let command = line.as_deref().map(str::trim).unwrap_or_default();
+            let mut stop = None;
+            match command.split_whitespace().next() {
+                Some("go") => {
+                    let flag = Arc::new(AtomicBool::new(false));
+                    current = Some(Arc::clone(&flag));
+                    stop = Some(flag);
+                }
+                Some("stop" | "quit") => {
+                    if let Some(flag) = current.take() {
+                        flag.store(true, Ordering::Relaxed);
+                    }
+                }
+                _ => {}
+            }
+            let quit = command == "quit";
+            if sender.send((line, stop)).is_err() || quit {
+                break;
+            }
+        }
+    });
+    let mut position = (board::init(), Player::Sente);
+
+    for (line, stop) in lines {
+        let line = line?;
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "usi" => {
+                writeln!(
+                    output,
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                )?;
+                writeln!(output, "usiok")?;
+            }
+            "isready" => writeln!(output, "readyok")?,
+            "usinewgame" => position = (board::init(), Player::Sente),
+            "position" => match parse_position(args) {
+                Ok(p) => position = p,
+                Err(e) => writeln!(output, "info string {}", e)?,
+            },
+            "go" => {
+                let (state, player) = &position;
+                let time_limit = think_time(args, *player);
+                let infinite = args.split_whitespace().any(|t| t == "infinite");
+                let options = SearchOptions {
+                    time_limit,
+                    stop: stop.as_deref().filter(|_| infinite || time_limit.is_some()),
+                    ..SearchOptions::default()
+                };
+                let result = think(state, *player, &options);
+                if let Some(score) = result.score {
+                    // USI の評価値は手番側から見た値
+                    let score = match player {
+                        Player::Sente => score,
+                        Player::Gote => -score,
+                    };
+                    writeln!(output, "info score cp {}", score)?;
+                }
+                match result.best_move {
+                    Some(mv) => writeln!(output, "bestmove {}", sfen::format_move(mv))?,
+                    None => writeln!(output, "bestmove resign")?,
+                }
+            }
+            "quit" => return Ok(()),
+            // setoption、gameover などは何もしない。考え終えた後の stop も読み飛ばす
+            _ => {}
+        }
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// `quit` を送ってからエンジンの終了を待つ時間（過ぎたら強制終了する）
+const QUIT_GRACE: Duration = Duration::from_secs(1);
+/// `usiok`・`readyok` を待つ時間
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+/// `go` の秒読みを過ぎてから `bestmove` を待つ時間（過ぎたら時間切れ）
+const BESTMOVE_MARGIN: Duration = Duration::from_secs(1);
+
+/// 子プロセスとして動かす外部の USI エンジン
+///
+/// エンジンの出力は別のスレッドで1行ずつ読み、応答を待つときは期限を決めて受け取る。
+pub struct UsiEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<io::Result<String>>,
+    name: String,
+}
+
+fn protocol_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl UsiEngine {
+    /// エンジンを起動し、`usiok` と `readyok` を待つ
+    pub fn spawn(program: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("標準入力をパイプにした");
+        let stdout = BufReader::new(child.stdout.take().expect("標準出力をパイプにした"));
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            name: program.to_string(),
+        };
+
+        engine.send("usi")?;
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            let line = engine.read_line(deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.to_string();
+            } else if line == "usiok" {
+                break;
+            }
+        }
+        engine.wait_ready()?;
+        Ok(engine)
+    }
+
+    /// `id name` で名乗った名前（名乗らなければ起動したコマンド）
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 新しい対局を始める
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send("usinewgame")?;
+        self.wait_ready()
+    }
+
+    /// 局面を送って `byoyomi_ms` ミリ秒の秒読みで考えさせ、指し手を返す（投了なら `None`）
+    ///
+    /// 5五将棋には入玉宣言がないので、`bestmove win` は投了とは区別してエラーにする。
+    /// 秒読みを `BESTMOVE_MARGIN` 過ぎても指さなければ `stop` を送り、`ErrorKind::TimedOut` のエラーを返す。
+    pub fn go(
+        &mut self,
+        state: &GameState,
+        player: Player,
+        byoyomi_ms: u64,
+    ) -> io::Result<Option<Move>> {
+        self.send(&format!("position sfen {}", sfen::format(state, player)))?;
+        self.send(&format!("go btime 0 wtime 0 byoyomi {}", byoyomi_ms))?;
+        let deadline = Instant::now() + Duration::from_millis(byoyomi_ms) + BESTMOVE_MARGIN;
+        loop {
+            let line = match self.read_line(deadline) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    let _ = self.send("stop");
+                    return Err(e);
+                }
+                line => line?,
+            };
+            let Some(rest) = line.strip_prefix("bestmove ") else {
+                continue;
+            };
+            let token = rest.split_whitespace().next().unwrap_or_default();
+            return match token {
+                "resign" => Ok(None),
+                "win" => Err(protocol_error(format!(
+                    "{} が入玉宣言（bestmove win）をしましたが、5五将棋では宣言できません",
+                    self.name
+                ))),
+                _ => sfen::parse_move(token).map(Some).map_err(protocol_error),
+            };
+        }
+    }
+
+    fn wait_ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while self.read_line(deadline)? != "readyok" {}
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    /// 1行読む（`deadline` までに出力がなければ `ErrorKind::TimedOut` のエラー）
+    fn read_line(&mut self, deadline: Instant) -> io::Result<String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(line?.trim().to_string()),
+            Err(RecvTimeoutError::Timeout) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("{} が時間内に応答しませんでした", self.name),
+            )),
+            Err(RecvTimeoutError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} が終了しました", self.name),
+            )),
+        }
+    }
+}
+
+impl Drop for UsiEngine {
+    fn drop(&mut self) {
+        // quit を送って少し待ち、終わらなければ止める
+        if self.send("quit").is_ok() {
+            let deadline = Instant::now() + QUIT_GRACE;
+            while Instant::now() < deadline {
+                match self.child.try_wait() {
+                    Ok(None) => thread::sleep(Duration::from_millis(10)),
+                    _ => return,
+                }
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
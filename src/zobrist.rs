@@ -0,0 +1,130 @@
+use crate::board::{GameState, HAND_TYPES, MAX_HAND, Piece, PieceType, Player, player_index};
+use crate::rules::Position;
+
+// Zobrist ハッシュ
+// 盤上の駒（マス × 手番 × 駒種 × 成）、持ち駒（手番 × 駒種 × 枚数）、手番ごとに乱数を割り当て XOR する。
+
+struct Keys {
+    board: [[[[u64; 2]; 6]; 2]; 25],
+    hand: [[[u64; MAX_HAND]; 5]; 2],
+    side: u64,
+}
+
+/// splitmix64 による決定的な乱数列
+pub(crate) const fn splitmix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn make_keys() -> Keys {
+    let mut keys = Keys {
+        board: [[[[0; 2]; 6]; 2]; 25],
+        hand: [[[0; MAX_HAND]; 5]; 2],
+        side: 0,
+    };
+    let mut seed = 0x5EED_0F5A_0E00_u64;
+
+    let mut sq = 0;
+    while sq < 25 {
+        let mut owner = 0;
+        while owner < 2 {
+            let mut pt = 0;
+            while pt < 6 {
+                let mut promoted = 0;
+                while promoted < 2 {
+                    let (s, k) = splitmix(seed);
+                    seed = s;
+                    keys.board[sq][owner][pt][promoted] = k;
+                    promoted += 1;
+                }
+                pt += 1;
+            }
+            owner += 1;
+        }
+        sq += 1;
+    }
+
+    let mut owner = 0;
+    while owner < 2 {
+        let mut pt = 0;
+        while pt < 5 {
+            let mut n = 0;
+            while n < MAX_HAND {
+                let (s, k) = splitmix(seed);
+                seed = s;
+                keys.hand[owner][pt][n] = k;
+                n += 1;
+            }
+            pt += 1;
+        }
+        owner += 1;
+    }
+
+    keys.side = splitmix(seed).1;
+    keys
+}
+
+static KEYS: Keys = make_keys();
+
+fn type_index(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::Gold => 0,
+        PieceType::Silver => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Pawn => 4,
+        PieceType::King => 5,
+    }
+}
+
+/// 盤上の駒1枚の乱数（差分更新用）
+pub fn piece_key(pos: Position, piece: Piece) -> u64 {
+    KEYS.board[pos.y * 5 + pos.x][player_index(piece.owner)][type_index(piece.piece_type)]
+        [usize::from(piece.promoted)]
+}
+
+/// 持ち駒の `n` 枚目（0 始まり）の乱数（差分更新用）
+pub fn hand_key(owner: Player, piece_type: PieceType, n: u8) -> u64 {
+    let n = n as usize;
+    if piece_type != PieceType::King && n < MAX_HAND {
+        KEYS.hand[player_index(owner)][type_index(piece_type)][n]
+    } else {
+        0
+    }
+}
+
+/// 手番が後手であることを表す乱数（差分更新用）
+pub fn side_key() -> u64 {
+    KEYS.side
+}
+
+/// 局面と手番のハッシュ値
+pub fn hash(state: &GameState, player: Player) -> u64 {
+    let mut h = 0;
+
+    for (y, row) in state.board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Some(piece) = cell {
+                h ^= piece_key(Position::new(x, y), *piece);
+            }
+        }
+    }
+
+    for owner in [Player::Sente, Player::Gote] {
+        let hand = state.get_hand(owner);
+        for pt in HAND_TYPES {
+            for n in 0..hand.get(pt) {
+                h ^= hand_key(owner, pt, n);
+            }
+        }
+    }
+
+    if player == Player::Gote {
+        h ^= side_key();
+    }
+
+    h
+}
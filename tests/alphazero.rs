@@ -0,0 +1,141 @@
+use minishogi_rs::alphazero::{
+    self, Mlp, PolicyValue, Prediction, PuctOptions, TrainingExample, Uniform,
+};
+use minishogi_rs::board::{self, GameState, Player};
+use minishogi_rs::rl::{self, ACTION_COUNT};
+use minishogi_rs::rules;
+use minishogi_rs::search::Rng;
+use minishogi_rs::sfen;
+use std::cell::RefCell;
+
+/// 受け取ったバッチの大きさを記録するモデル
+struct Recording {
+    batches: RefCell<Vec<usize>>,
+}
+
+impl PolicyValue for Recording {
+    fn predict(&self, state: &GameState, player: Player) -> Prediction {
+        Uniform.predict(state, player)
+    }
+
+    fn predict_batch(&self, positions: &[(GameState, Player)]) -> Vec<Prediction> {
+        self.batches.borrow_mut().push(positions.len());
+        positions
+            .iter()
+            .map(|(state, player)| self.predict(state, *player))
+            .collect()
+    }
+}
+
+#[test]
+fn search_finds_king_captures_with_a_uniform_model() {
+    for (position, expected) in [
+        ("k4/1G3/5/5/4K b - 1", "4b5a"),
+        ("4k/5/5/5/K3r w - 1", "1e5e"),
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        let result = alphazero::search(&state, player, &PuctOptions::default(), &Uniform);
+        let best = sfen::format_move(result.best_move.unwrap());
+        assert!(best.starts_with(expected), "{}: {}", position, best);
+
+        let score = result.score.unwrap();
+        let score = if player == Player::Sente {
+            score
+        } else {
+            -score
+        };
+        assert!(score > 1000, "{}: {}", position, score);
+    }
+}
+
+#[test]
+fn visits_cover_legal_moves_and_add_up() {
+    let state = board::init();
+    let options = PuctOptions {
+        simulations: 200,
+        ..PuctOptions::default()
+    };
+    let result = alphazero::search(&state, Player::Sente, &options, &Uniform);
+
+    let legal = rules::generate_legal_moves(&state, Player::Sente);
+    assert_eq!(result.visits.len(), legal.len());
+    assert!(result.visits.iter().all(|(mv, _)| legal.contains(mv)));
+    // 根の展開の1回を除いた数だけ子を訪問する
+    let total: u32 = result.visits.iter().map(|&(_, n)| n).sum();
+    assert_eq!(total, options.simulations - 1);
+
+    let best = result.visits.iter().max_by_key(|&&(_, n)| n).unwrap();
+    assert_eq!(result.best_move, Some(best.0));
+}
+
+#[test]
+fn leaves_are_evaluated_in_batches() {
+    let model = Recording {
+        batches: RefCell::new(Vec::new()),
+    };
+    let options = PuctOptions {
+        simulations: 100,
+        batch_size: 8,
+        ..PuctOptions::default()
+    };
+    let result = alphazero::search(&board::init(), Player::Sente, &options, &model);
+    assert!(result.best_move.is_some());
+
+    let batches = model.batches.borrow();
+    assert!(batches.iter().all(|&n| (1..=8).contains(&n)));
+    assert!(batches.iter().any(|&n| n > 1), "{:?}", batches);
+    assert!(batches.iter().sum::<usize>() <= 100);
+}
+
+#[test]
+fn mlp_batch_matches_single_predictions() {
+    let model = Mlp::new(16, 1);
+    let positions: Vec<(GameState, Player)> =
+        ["kgsbr/p4/5/4P/RBSGK b - 1", "k1sb1/p1g2/4r/5/RBS1K w Pg 1"]
+            .iter()
+            .map(|s| sfen::parse(s).unwrap())
+            .collect();
+
+    let batch = model.predict_batch(&positions);
+    for ((state, player), prediction) in positions.iter().zip(&batch) {
+        assert_eq!(*prediction, model.predict(state, *player));
+        assert_eq!(prediction.policy.len(), ACTION_COUNT);
+        assert!((-1.0..=1.0).contains(&prediction.value));
+    }
+}
+
+#[test]
+fn selfplay_examples_train_the_model() {
+    let mut model = Mlp::new(16, 2);
+    let options = PuctOptions {
+        simulations: 16,
+        ..PuctOptions::default()
+    };
+    let mut rng = Rng::with_seed(3);
+
+    let mut examples: Vec<TrainingExample> = Vec::new();
+    for _ in 0..2 {
+        let game = alphazero::selfplay_game(&model, &options, &mut rng, 4, 30);
+        assert!(!game.is_empty());
+        for example in &game {
+            assert_eq!(example.observation.len(), rl::OBSERVATION_SIZE);
+            let total: f32 = example.policy.iter().map(|&(_, p)| p).sum();
+            assert!((total - 1.0).abs() < 1e-4);
+            assert!([-1.0, 0.0, 1.0].contains(&example.value));
+        }
+        // 手番が交互なので、決着した対局では結果の符号も交互になる
+        for pair in game.windows(2) {
+            assert_eq!(pair[0].value, -pair[1].value);
+        }
+        examples.extend(game);
+    }
+
+    let before = model.loss(&examples);
+    for _ in 0..60 {
+        for batch in examples.chunks(16) {
+            model.train_batch(batch, 0.05);
+        }
+    }
+    let after = model.loss(&examples);
+    assert!(after < before * 0.8, "{} -> {}", before, after);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{Follow, Step};
+
+use minishogi_rs::bitboard::BitPosition;
+use minishogi_rs::board::{self, GameState, Player};
+use minishogi_rs::rules::{self, Move, SearchState};
+use minishogi_rs::sfen;
+
+fn sorted(moves: Vec<Move>) -> Vec<String> {
+    let mut s: Vec<String> = moves.into_iter().map(sfen::format_move).collect();
+    s.sort();
+    s
+}
+
+/// 局面木の各局面でビットボード版と配列版の結果が一致することを確かめる
+fn compare(state: &GameState, player: Player, depth: u32) {
+    let mut pos = SearchState::new(*state, player);
+    common::walk(&mut pos, depth, Follow::Safe, &mut |pos, step| match step {
+        Step::Visit => {
+            let (state, player) = (&pos.state, pos.player);
+            let bits = BitPosition::from_state(state);
+            assert_eq!(bits.to_state(), *state);
+            assert_eq!(bits.is_in_check(player), rules::is_in_check(state, player));
+            assert_eq!(
+                sorted(bits.generate_legal_moves(player)),
+                sorted(rules::generate_legal_moves(state, player)),
+                "{}",
+                sfen::format(state, player)
+            );
+            assert_eq!(
+                sorted(bits.generate_moves_fast(player)),
+                sorted(rules::generate_moves_fast(state, player))
+            );
+        }
+        Step::Do { before, undo } => {
+            let bits = BitPosition::from_state(&before.state);
+            assert_eq!(bits.make_move(undo.mv, before.player).to_state(), pos.state);
+        }
+        Step::Undo { .. } => {}
+    });
+}
+
+#[test]
+fn matches_array_generator_from_initial_position() {
+    compare(&board::init(), Player::Sente, 3);
+}
+
+#[test]
+fn matches_array_generator_with_hands_and_promoted_pieces() {
+    for position in [
+        "k1sb1/p1g2/4r/5/RBS1K w Pg 1",
+        "1k3/3B1/3G1/5/K4 b R 1",
+        "+R4/4k/3b1/5/K4 b R 1",
+        "k4/2S2/1G3/5/4K b P 1",
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        compare(&state, player, 2);
+    }
+}
+
+#[test]
+fn perft_matches() {
+    let state = board::init();
+    assert_eq!(
+        BitPosition::from_state(&state).perft(Player::Sente, 4),
+        35_401
+    );
+}
@@ -0,0 +1,173 @@
+use minishogi_rs::board::{self, Player};
+use minishogi_rs::book::{Book, BookMove, BookSelection};
+use minishogi_rs::rules::{self, SearchState};
+use minishogi_rs::search::Rng;
+use minishogi_rs::{sfen, zobrist};
+use std::collections::HashMap;
+
+const RECORDS: &str = "\
+# 勝った側の手は重み2、引き分けは重み1、負けた側の手は登録しない
+1d1c 5b5c 1-0
+1d1c 5b5c 0-1
+1d1c 1-0
+2e2d 5b5c 1/2-1/2
+2e2d
+";
+
+fn usi(s: &str) -> rules::Move {
+    sfen::parse_move(s).unwrap()
+}
+
+#[test]
+fn records_are_weighted_by_result() {
+    let book = Book::from_records(RECORDS, 10).unwrap();
+    let start = board::init();
+    let mut moves = book.probe(&start, Player::Sente).unwrap().to_vec();
+    moves.sort_by_key(|m| m.weight);
+    assert_eq!(
+        moves,
+        [
+            BookMove {
+                mv: usi("2e2d"),
+                weight: 2
+            },
+            BookMove {
+                mv: usi("1d1c"),
+                weight: 4
+            },
+        ]
+    );
+
+    let after = rules::make_move(&start, usi("1d1c"), Player::Sente);
+    assert_eq!(
+        book.probe(&after, Player::Gote).unwrap(),
+        [BookMove {
+            mv: usi("5b5c"),
+            weight: 2
+        }]
+    );
+    let after = rules::make_move(&start, usi("2e2d"), Player::Sente);
+    assert_eq!(
+        book.probe(&after, Player::Gote).unwrap(),
+        [BookMove {
+            mv: usi("5b5c"),
+            weight: 1
+        }]
+    );
+
+    // 手数の上限より先は登録しない
+    assert_eq!(Book::from_records(RECORDS, 1).unwrap().len(), 1);
+
+    let err = Book::from_records("1d1c\n1d1c 1d1b\n", 10).unwrap_err();
+    assert!(err.starts_with("2行目"), "{}", err);
+}
+
+#[test]
+fn text_format_round_trips() {
+    let book = Book::from_records(RECORDS, 10).unwrap();
+    let text = book.to_text();
+    let parsed = Book::parse(&text).unwrap();
+    assert_eq!(parsed.len(), book.len());
+    assert_eq!(parsed.to_text(), text);
+
+    for bad in ["0 1d1c", "xyz 1d1c 1", "0 1d1c heavy", "0 9z9z 1"] {
+        assert!(Book::parse(bad).is_err(), "{}", bad);
+    }
+
+    // 同じ手の重みは足し合わせ、u32 の上限で止まる
+    let book = Book::parse("0 1d1c 4294967295\n0 1d1c 4294967295\n").unwrap();
+    assert!(
+        book.to_text()
+            .ends_with("\n0000000000000000 1d1c 4294967295\n")
+    );
+}
+
+#[test]
+fn selfplay_stops_after_a_king_is_captured() {
+    let mut rng = Rng::with_seed(7);
+    let mut captures = 0;
+    let book = Book::from_selfplay(20, 200, |state, player| {
+        // 玉が取られた局面では指さない
+        assert!(rules::has_king(state, Player::Sente) && rules::has_king(state, Player::Gote));
+        let moves = rules::generate_legal_moves(state, player);
+        let capture = moves.iter().find(|&&mv| rules::captures_king(state, mv));
+        if capture.is_some() {
+            captures += 1;
+        }
+        capture
+            .or(moves.get(rng.range(moves.len().max(1))))
+            .copied()
+    });
+    assert!(captures > 0);
+    assert!(!book.is_empty());
+}
+
+#[test]
+fn selection_is_best_or_proportional_to_weight() {
+    let book = Book::from_records(RECORDS, 10).unwrap();
+    let start = board::init();
+    let mut rng = Rng::with_seed(1);
+    assert_eq!(
+        book.select(&start, Player::Sente, BookSelection::Best, &mut rng),
+        Some(usi("1d1c"))
+    );
+
+    // 重み 4:2 なので 1d1c が約 2/3 選ばれる
+    let draws = 600;
+    let chosen = (0..draws)
+        .filter(|_| {
+            book.select(
+                &start,
+                Player::Sente,
+                BookSelection::WeightedRandom,
+                &mut rng,
+            ) == Some(usi("1d1c"))
+        })
+        .count();
+    assert!((340..460).contains(&chosen), "{}", chosen);
+
+    // ハッシュが一致しても合法手でない手は選ばない
+    let key = zobrist::hash(&start, Player::Sente);
+    let book = Book::parse(&format!("{:016x} 5a5b 100\n", key)).unwrap();
+    for selection in [BookSelection::Best, BookSelection::WeightedRandom] {
+        assert_eq!(
+            book.select(&start, Player::Sente, selection, &mut rng),
+            None
+        );
+    }
+}
+
+#[test]
+fn zobrist_hash_identifies_positions() {
+    // 深さ 3 までの全局面で、同じ局面は同じハッシュ、違う局面は違うハッシュになる
+    let mut seen: HashMap<u64, String> = HashMap::new();
+    let mut frontier = vec![SearchState::new(board::init(), Player::Sente)];
+    for _ in 0..3 {
+        let mut next = Vec::new();
+        for pos in frontier {
+            let key = sfen::format(&pos.state, pos.player);
+            assert_eq!(pos.hash, zobrist::hash(&pos.state, pos.player));
+            assert_eq!(seen.entry(pos.hash).or_insert_with(|| key.clone()), &key);
+            for mv in rules::generate_legal_moves(&pos.state, pos.player) {
+                let mut child = pos;
+                child.do_move(mv);
+                next.push(child);
+            }
+        }
+        frontier = next;
+    }
+
+    // 手番と持ち駒の枚数もハッシュに含まれる
+    let (state, _) = sfen::parse("k4/5/5/5/4K b P 1").unwrap();
+    let (two, _) = sfen::parse("k4/5/5/5/4K b 2P 1").unwrap();
+    let (gote, _) = sfen::parse("k4/5/5/5/4K b p 1").unwrap();
+    let hashes = [
+        zobrist::hash(&state, Player::Sente),
+        zobrist::hash(&state, Player::Gote),
+        zobrist::hash(&two, Player::Sente),
+        zobrist::hash(&gote, Player::Sente),
+    ];
+    for (i, a) in hashes.iter().enumerate() {
+        assert!(!hashes[i + 1..].contains(a));
+    }
+}
@@ -0,0 +1,365 @@
+use minishogi_rs::board::PieceType;
+use minishogi_rs::cli::{self, Command, Options, Side};
+use minishogi_rs::engine::Engine;
+use minishogi_rs::locale::{self, Locale};
+use minishogi_rs::notation::Notation;
+use minishogi_rs::selfplay;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+fn play_options(line: &str) -> Options {
+    match cli::parse(&args(line), Locale::Japanese) {
+        Ok(Command::Play(options)) => options,
+        other => panic!("{}: {:?}", line, other),
+    }
+}
+
+#[test]
+fn play_options_are_parsed() {
+    let options = play_options(
+        "play --sente ab:3 --gote mcts --time 500 --seed 42 --record game.txt --delay 0 --tb a.tb --tb b.tb --book-best",
+    );
+    assert_eq!(
+        options.fixed_sides(),
+        Some([
+            Side::Ai {
+                engine: Engine::AlphaBeta,
+                strength: Some(3)
+            },
+            Side::Ai {
+                engine: Engine::Mcts,
+                strength: None
+            },
+        ])
+    );
+    assert_eq!(options.time_ms, Some(500));
+    assert_eq!(options.seed, Some(42));
+    assert_eq!(options.record.as_deref(), Some("game.txt"));
+    assert_eq!(options.delay_ms, Some(0));
+    assert_eq!(options.tablebases, ["a.tb", "b.tb"]);
+    assert!(options.book_best);
+
+    // サブコマンドは省略できる
+    assert_eq!(play_options("--seed 42").seed, Some(42));
+    assert_eq!(play_options("").notation, None);
+    assert_eq!(
+        play_options("--notation western").notation,
+        Some(Notation::Western)
+    );
+    // 何も指定しなければ対局者は対話的に選ぶ
+    assert_eq!(play_options("").fixed_sides(), None);
+}
+
+#[test]
+fn side_pairs_the_human_with_the_engine() {
+    let options = play_options("--side g --engine mcts --iterations 300");
+    assert_eq!(
+        options.fixed_sides(),
+        Some([
+            Side::Ai {
+                engine: Engine::Mcts,
+                strength: None
+            },
+            Side::Human,
+        ])
+    );
+    assert_eq!(options.iterations, Some(300));
+
+    let options = play_options("--side s --gote human");
+    assert_eq!(options.fixed_sides(), Some([Side::Human, Side::Human]));
+}
+
+#[test]
+fn analyze_accepts_an_unquoted_sfen() {
+    let Ok(Command::Analyze(options)) = cli::parse(
+        &args("analyze --depth 3 k4/1G3/5/5/4K b - 1"),
+        Locale::Japanese,
+    ) else {
+        panic!();
+    };
+    assert_eq!(options.sfen.as_deref(), Some("k4/1G3/5/5/4K b - 1"));
+    assert_eq!(options.depth, Some(3));
+}
+
+#[test]
+fn help_and_errors() {
+    assert_eq!(
+        cli::parse(&args("--help"), Locale::Japanese),
+        Ok(Command::Help)
+    );
+    assert_eq!(
+        cli::parse(&args("play -h"), Locale::English),
+        Ok(Command::Help)
+    );
+    assert!(cli::USAGE.contains("analyze"));
+
+    for (line, expected) in [
+        ("--bogus", "不明な引数です"),
+        ("--depth", "値が必要です"),
+        ("--depth 0", "値が不正です"),
+        ("--seed x", "値が不正です"),
+        ("--sente random", "不明なエンジンです"),
+        ("--side x", "--side"),
+        ("--notation usi", "不明な表記です"),
+        ("play extra", "不明な引数です"),
+    ] {
+        let err = cli::parse(&args(line), Locale::Japanese).unwrap_err();
+        assert!(err.contains(expected), "{}: {}", line, err);
+    }
+}
+
+#[test]
+fn in_game_commands_take_back_and_list_moves() {
+    let path = env::temp_dir().join(format!("minishogi_cli_{}.txt", process::id()));
+    let mut child = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+        .args(["--side", "s", "--depth", "1", "--record"])
+        .arg(&path)
+        .env_remove(locale::LANG_ENV)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("undo\nmoves 1d\n1d 1c\nundo\nhint\neval\nflip\n1d 1c\nquit\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("戻せる手がありません"));
+    assert!(stdout.contains("合法手（1手）: １三歩"));
+    assert!(stdout.contains("2手戻しました"));
+    assert!(stdout.contains("ヒント: "));
+    assert!(stdout.contains("評価値: "));
+    // flip で後手から見た盤面（筋が 1 から並ぶ）になる
+    assert!(stdout.contains("     1   2   3   4   5"));
+
+    // 戻した手は棋譜に残らない
+    let records = selfplay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let [game] = &records[..] else {
+        panic!("{:?}", records);
+    };
+    assert_eq!(game.plies.len(), 2);
+    assert_eq!(game.engines, ["human", "alphabeta"]);
+    // quit は投了として記録する
+    assert_eq!(game.result, 0.0);
+}
+
+#[test]
+fn ai_vs_ai_game_plays_to_the_end() {
+    let path = env::temp_dir().join(format!("minishogi_spectate_{}.txt", process::id()));
+    let output = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+        .args([
+            "--sente", "ab:1", "--gote", "ab:1", "--delay", "0", "--record",
+        ])
+        .arg(&path)
+        .env_remove(locale::LANG_ENV)
+        .stdin(process::Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // 結果の後には棋譜の保存先だけが表示される
+    let mut lines = stdout.lines().rev().filter(|l| !l.trim().is_empty());
+    assert!(lines.next().unwrap().starts_with("棋譜を"));
+    let last = lines.next().unwrap();
+
+    let records = selfplay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let [game] = &records[..] else {
+        panic!("{:?}", records);
+    };
+    assert_eq!(game.engines, ["alphabeta", "alphabeta"]);
+    // 決着するか、300 手で引き分けになる
+    if game.result == 0.5 {
+        assert_eq!(game.plies.len(), 300);
+        assert_eq!(last, "300手に達したので引き分けです");
+    } else {
+        assert!(game.plies.len() < 300);
+        assert!(["先手の勝ち！", "後手の勝ち！"].contains(&last), "{}", last);
+    }
+}
+
+#[test]
+fn closed_stdin_ends_every_prompt() {
+    let path = env::temp_dir().join(format!("minishogi_eof_{}.txt", process::id()));
+    let run = |args: &[&str], input: &str| {
+        let mut child = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+            .args(args)
+            .env_remove(locale::LANG_ENV)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        // 書き終えたら stdin を閉じる
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{:?}", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // 対局者を選ぶ途中のどこで終わっても、対局を始めずに終了する
+    for input in ["", "1\n", "1\ns\n", "1\ns\n2\n", "3\n1\n\n2\n\n"] {
+        let stdout = run(&[], input);
+        assert!(
+            stdout.contains("入力が終わったので終了します"),
+            "{:?}",
+            input
+        );
+        assert!(!stdout.contains("の番です"), "{:?}", input);
+    }
+
+    // 対局中に終わったときは投了として記録する
+    let path_arg = path.to_str().unwrap();
+    run(&["--side", "s", "--depth", "1", "--record", path_arg], "");
+    let records = selfplay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(records[0].plies.len(), 0);
+    assert_eq!(records[0].result, 0.0);
+
+    // 詰将棋は問題を選ぶ前でも解答中でも終了する
+    assert!(run(&["tsume"], "").contains("入力が終わったので終了します"));
+    assert!(run(&["tsume"], "1\n").contains("詰将棋を終了します"));
+}
+
+#[test]
+fn english_locale_shows_latin_pieces_and_western_notation() {
+    assert_eq!(Locale::parse("en_US.UTF-8"), Some(Locale::English));
+    assert_eq!(Locale::parse("ja"), Some(Locale::Japanese));
+    assert_eq!(Locale::parse("fr"), None);
+    assert_eq!(Locale::English.piece_name(PieceType::Rook, true), "+R");
+    assert_eq!(Locale::Japanese.piece_name(PieceType::Rook, true), "龍");
+
+    // --lang はそれより前の引数のエラーにも効く
+    let err = cli::parse(&args("--depth 0 --lang en"), Locale::Japanese).unwrap_err();
+    assert_eq!(err, "Invalid value for --depth: 0");
+    let err = cli::parse(&args("--sente random"), Locale::English).unwrap_err();
+    assert_eq!(err, "Invalid value for --sente: random");
+    let err = cli::parse(&args("--lang fr"), Locale::English).unwrap_err();
+    assert_eq!(err, "Unknown language: fr");
+    let err = cli::parse(&args("--lang fr"), Locale::Japanese).unwrap_err();
+    assert_eq!(err, "不明な言語です: fr");
+    assert_eq!(play_options("--lang en").locale, Some(Locale::English));
+
+    let mut child = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+        .args(["--side", "s", "--depth", "1", "--lang", "en"])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("moves 1d\ndrop G 3c\nP-1b\nquit\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(" a|^K |^G |^S |^B |^R |"), "{}", stdout);
+    assert!(stdout.contains(" e| R | B | S | G | K |"));
+    assert!(stdout.contains("Sente in hand: none"));
+    assert!(stdout.contains("Sente (you) to move"));
+    assert!(stdout.contains("Legal moves (1): P-1c"));
+    assert!(stdout.contains("Error: No G in hand"));
+    assert!(stdout.contains("Error: Not a legal move: P-1b"));
+    // CJK の文字を表示しない
+    let cjk = |s: &str| s.chars().any(|c| ('\u{3000}'..='\u{9fff}').contains(&c));
+    assert!(!cjk(&stdout));
+
+    // SFEN のエラーも英語で表示する
+    let output = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+        .args(["analyze", "--lang", "en", "k4/5"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Invalid SFEN: k4/5"), "{}", stderr);
+}
+
+#[test]
+fn errors_go_to_stderr_with_a_failure_status() {
+    let path = env::temp_dir()
+        .join(format!("minishogi_missing_{}", process::id()))
+        .join("game.kif");
+    let path = path.to_str().unwrap();
+    for line in [
+        vec!["--bogus"],
+        vec!["analyze", "k4/5"],
+        vec!["perft", "x"],
+        vec!["tbgen"],
+        vec!["--sente", "ab:1", "--gote", "ab:1", "--record", path],
+    ] {
+        let output = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"))
+            .args(&line)
+            .env_remove(locale::LANG_ENV)
+            .stdin(process::Stdio::null())
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{:?}", line);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!stderr.trim().is_empty(), "{:?}", line);
+        // 保存できない棋譜の保存先は対局を始める前に分かる
+        assert!(output.stdout.is_empty(), "{:?}", line);
+    }
+}
+
+#[test]
+fn subcommands_follow_the_display_language() {
+    let run = |args: &[&str], lang: Option<&str>| {
+        let mut command = process::Command::new(env!("CARGO_BIN_EXE_minishogi-rs"));
+        command.args(args).env_remove(locale::LANG_ENV);
+        if let Some(lang) = lang {
+            command.env(locale::LANG_ENV, lang);
+        }
+        let output = command.stdin(process::Stdio::null()).output().unwrap();
+        assert!(!output.status.success(), "{:?}", args);
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let cjk = |s: &str| s.chars().any(|c| ('\u{3000}'..='\u{9fff}').contains(&c));
+
+    // --lang はサブコマンドの引数のどこに置いてもよく、環境変数より優先する
+    for (args, lang, expected) in [
+        (&["tbgen", "--lang", "en"][..], None, "Usage: tbgen"),
+        (
+            &["bookgen", "--lang", "en"],
+            Some("ja"),
+            "Usage:\n  bookgen",
+        ),
+        (&["nnue"], Some("en"), "Usage:\n  nnue"),
+        (&["tune", "--lang", "en"], None, "Usage: tune"),
+        (&["match", "--lang", "en"], None, "Usage: match"),
+        (&["usi", "usi:other", "--lang", "en"], None, "Usage: usi"),
+        (&["csaserver"], Some("en"), "Usage: csaserver"),
+        (
+            &["selfplay", "x", "out.txt"],
+            Some("en"),
+            "Invalid number of games: x",
+        ),
+        (&["bench", "x", "--lang", "en"], None, "Usage: bench"),
+    ] {
+        let stderr = run(args, lang);
+        assert!(stderr.starts_with(expected), "{:?}: {}", args, stderr);
+        assert!(!cjk(&stderr), "{:?}: {}", args, stderr);
+    }
+    assert!(run(&["tbgen"], None).starts_with("使い方: tbgen"));
+    assert!(run(&["tbgen", "--lang"], None).starts_with("--lang には値が必要です"));
+
+    // SFEN のエラーの詳細は日本語のまま括弧内に残す
+    let stderr = run(&["perft", "1", "k4/5", "--lang", "en"], None);
+    assert!(stderr.starts_with("Invalid SFEN: k4/5 ("), "{}", stderr);
+    assert!(cjk(&stderr));
+}
@@ -0,0 +1,117 @@
+// 使わない関数があるテストもある
+#![allow(dead_code)]
+
+use minishogi_rs::board::{self, PieceType, Player};
+use minishogi_rs::kifu::Kifu;
+use minishogi_rs::rules::{self, SearchState, Undo};
+use minishogi_rs::search::{Rng, SearchOptions};
+use minishogi_rs::selfplay::{self, SelfplayConfig};
+
+// 複数のテストで共有する対局と局面木の辿り方
+
+/// 最後まで合法手をランダムに選んだ、40 手までの対局の棋譜
+pub fn random_game(seed: u64) -> Kifu {
+    // すべての手をランダムに指すので、探索エンジンは動かない
+    let config = SelfplayConfig {
+        random_plies: 40,
+        max_plies: 40,
+        ..SelfplayConfig::default()
+    };
+    let record = selfplay::play_game(
+        &config,
+        &SearchOptions::default(),
+        &mut Rng::with_seed(seed),
+    );
+    let mut kifu = Kifu::new(board::init(), Player::Sente);
+    kifu.players = ["random".to_string(), "random".to_string()];
+    for mv in record.moves() {
+        kifu.push(mv).unwrap();
+    }
+    kifu
+}
+
+/// 局面木を辿る途中の出来事
+pub enum Step<'a> {
+    /// 局面に着いた
+    Visit,
+    /// 手を指した（`before` は指す前の局面）
+    Do {
+        before: &'a SearchState,
+        undo: &'a Undo,
+    },
+    /// 手を戻した（戻した局面は `before` と一致するはず）
+    Undo {
+        before: &'a SearchState,
+        undo: &'a Undo,
+    },
+}
+
+/// 局面木で辿る手
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Follow {
+    /// すべての合法手
+    All,
+    /// 自玉を取られる形になる手は辿らない
+    Safe,
+}
+
+/// 合法手を `do_move` / `undo_move` で深さ `depth` まで辿り、出来事ごとに `check` を呼ぶ
+///
+/// 玉を取った局面にも着くが、その先は辿らない。
+pub fn walk(
+    pos: &mut SearchState,
+    depth: u32,
+    follow: Follow,
+    check: &mut impl FnMut(&SearchState, Step),
+) {
+    check(pos, Step::Visit);
+    if depth == 0 {
+        return;
+    }
+    for mv in rules::generate_legal_moves(&pos.state, pos.player) {
+        let before = *pos;
+        let undo = pos.do_move(mv);
+        if follow == Follow::Safe && rules::is_in_check(&pos.state, before.player) {
+            pos.undo_move(undo);
+            continue;
+        }
+        check(
+            pos,
+            Step::Do {
+                before: &before,
+                undo: &undo,
+            },
+        );
+        let captured_king = undo
+            .captured
+            .is_some_and(|p| p.piece_type == PieceType::King);
+        walk(
+            pos,
+            if captured_king { 0 } else { depth - 1 },
+            follow,
+            check,
+        );
+        pos.undo_move(undo);
+        check(
+            pos,
+            Step::Undo {
+                before: &before,
+                undo: &undo,
+            },
+        );
+    }
+}
+
+/// 局面木の各局面で `check` を呼ぶ
+pub fn visit(
+    pos: &mut SearchState,
+    depth: u32,
+    follow: Follow,
+    check: &mut impl FnMut(&SearchState),
+) {
+    walk(pos, depth, follow, &mut |pos, step| {
+        if let Step::Visit = step {
+            check(pos);
+        }
+    });
+}
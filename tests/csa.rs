@@ -0,0 +1,130 @@
+mod common;
+
+use minishogi_rs::board::Player;
+use minishogi_rs::csa;
+use minishogi_rs::kif;
+use minishogi_rs::kifu::Ending;
+use minishogi_rs::sfen;
+
+#[test]
+fn games_round_trip_through_csa_and_kif() {
+    let mut text = String::new();
+    for seed in 1..=8 {
+        let mut kifu = common::random_game(seed);
+        if kifu.winner().is_none() {
+            kifu.ending = Some(Ending::Resign);
+        }
+        let exported = csa::to_text(&kifu);
+        assert_eq!(csa::parse(&exported).unwrap(), kifu, "{}", exported);
+        // KIF を経由しても同じ棋譜に戻る
+        let via_kif = kif::parse(&kif::to_text(&kifu)).unwrap();
+        assert_eq!(csa::to_text(&via_kif), exported);
+        text.push_str(&exported);
+    }
+    // 駒打ちと成りがいずれかの対局に現れる
+    assert!(text.contains("+00") || text.contains("-00"));
+    assert!(
+        ["TO", "NG", "UM", "RY"]
+            .iter()
+            .any(|code| text.contains(code))
+    );
+}
+
+#[test]
+fn explicit_positions_and_statements_are_read() {
+    let text = "\
+V2.2
+N+太郎
+N-花子
+$EVENT:テスト
+P1-OU *  *  *  *
+P2 *  * +GI *  *
+P3 *  *  *  *  *
+P4 *  *  *  *  *
+P5 *  *  *  * +OU
+P+00KI00FU00FU
+P-00AL
++
++3231GI,T1
+'コメント, 区切らない
+-5142OU
+T2
++0043KI,-4243OU
+%TORYO
+";
+    let kifu = csa::parse(text).unwrap();
+    assert_eq!(kifu.players, ["太郎", "花子"]);
+    // 後手の持駒は残りの金・銀1枚と角・飛2枚
+    assert_eq!(
+        sfen::format(&kifu.start, kifu.start_player),
+        "k4/2S2/5/5/4K b G2P2r2bgs 1"
+    );
+    let moves: Vec<String> = kifu.moves.iter().map(|&mv| sfen::format_move(mv)).collect();
+    assert_eq!(moves, ["3b3a", "5a4b", "G*4c", "4b4c"]);
+    assert_eq!(kifu.ending, Some(Ending::Resign));
+    assert_eq!(kifu.winner(), Some(Player::Gote));
+
+    let exported = csa::to_text(&kifu);
+    assert!(exported.contains("P2 *  * +GI *  * \n"));
+    assert!(exported.contains("P+00KI00FU00FU\n"));
+    assert!(exported.contains("P-00HI00HI00KA00KA00KI00GI\n"));
+    assert_eq!(csa::parse(&exported).unwrap(), kifu);
+
+    // PI に続く駒は初期局面から取り除く
+    let kifu = csa::parse("PI14FU\n-\n-4142KI\n+1514OU\n").unwrap();
+    assert_eq!(
+        sfen::format(&kifu.start, kifu.start_player),
+        "kgsbr/p4/5/5/RBSGK w - 1"
+    );
+    assert_eq!(kifu.moves.len(), 2);
+}
+
+#[test]
+fn promotions_are_read_from_the_piece_after_the_move() {
+    // 角が敵陣に入って馬になる手と、成らない手
+    let (state, player) = sfen::parse("k4/5/2B2/5/4K b - 1").unwrap();
+    assert_eq!(
+        sfen::format_move(csa::parse_move("+3311UM", &state, player).unwrap()),
+        "3c1a+"
+    );
+    assert_eq!(
+        sfen::format_move(csa::parse_move("+3311KA", &state, player).unwrap()),
+        "3c1a"
+    );
+    assert!(csa::parse_move("-3311KA", &state, player).is_err());
+    assert!(csa::parse_move("+3311HI", &state, player).is_err());
+    // 敵陣の外では成れない
+    let err = csa::parse("PI\n+\n+4523UM\n").unwrap_err();
+    assert!(err.contains("合法手ではありません"), "{}", err);
+}
+
+#[test]
+fn illegal_records_report_line_numbers() {
+    let header = "V2.2\nPI\n+\n";
+    for (body, line, message) in [
+        // 歩は2つ進めない
+        ("+1412FU\n", 4, "合法手ではありません"),
+        ("+1413KI\n", 4, "移動元の駒はFUです"),
+        ("-4142KI\n", 4, "手番ではありません"),
+        ("+1413FU\n-4142KI,+6564FU\n", 5, "指し手を読めません"),
+        ("%TORYO\n+1413FU\n", 5, "終局の後に指し手があります"),
+        ("%KACHI\n", 4, "対応していない終局です"),
+        ("P1 *  *  *  *  * \n", 4, "指し手の後に局面があります"),
+    ] {
+        let err = csa::parse(&format!("{}{}", header, body)).unwrap_err();
+        assert!(err.starts_with(&format!("{}行目: ", line)), "{}", err);
+        assert!(err.contains(message), "{}", err);
+    }
+
+    for (text, line, message) in [
+        ("+1413FU\n", 1, "開始局面と手番より前に指し手があります"),
+        ("P1-OU\nP+00AL\n+\n", 3, "P1～P5 の一部が欠けています"),
+        ("PI\nP+00FU\n+\n", 2, "FUが2枚を超えています"),
+        ("PI11KI\n", 1, "取り除く駒がありません"),
+    ] {
+        let err = csa::parse(text).unwrap_err();
+        assert!(err.starts_with(&format!("{}行目: ", line)), "{}", err);
+        assert!(err.contains(message), "{}", err);
+    }
+    assert!(csa::parse("PI\n").is_err());
+}
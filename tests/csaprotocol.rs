@@ -0,0 +1,174 @@
+use minishogi_rs::board::{GameState, Player};
+use minishogi_rs::csaprotocol::{
+    self, Client, GameReport, GameSummary, Outcome, ServerConfig, TimeControl,
+};
+use minishogi_rs::engine::Engine;
+use minishogi_rs::kifu::{Ending, Kifu};
+use minishogi_rs::search::{SearchOptions, SearchResult};
+use minishogi_rs::sfen;
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+type Think = fn(&GameState, Player, Duration) -> SearchResult;
+
+fn alpha_beta(state: &GameState, player: Player, _: Duration) -> SearchResult {
+    let options = SearchOptions {
+        depth: Some(1),
+        ..SearchOptions::default()
+    };
+    Engine::AlphaBeta.search(state, player, &options)
+}
+
+fn mcts(state: &GameState, player: Player, think_time: Duration) -> SearchResult {
+    assert!(think_time >= Duration::from_millis(10));
+    let options = SearchOptions {
+        iterations: Some(30),
+        seed: Some(1),
+        ..SearchOptions::default()
+    };
+    Engine::Mcts.search(state, player, &options)
+}
+
+/// ローカルのサーバーで先手 `sente`・後手 `gote` を1局対局させる
+fn play_on_localhost(config: &ServerConfig, sente: Think, gote: Think) -> (Kifu, [GameReport; 2]) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::scope(|s| {
+        let server = s.spawn(|| csaprotocol::serve_game(&listener, config).unwrap());
+        // 先にログインした方が先手
+        let clients = [
+            (Client::login(addr, "alice", "pass").unwrap(), sente),
+            (Client::login(addr, "bob", "pass").unwrap(), gote),
+        ];
+        let players = clients.map(|(mut client, think)| {
+            s.spawn(move || {
+                let report = client.play(think).unwrap();
+                client.logout().unwrap();
+                report
+            })
+        });
+        let reports = players.map(|p| p.join().unwrap());
+        (server.join().unwrap(), reports)
+    })
+}
+
+#[test]
+fn engines_play_a_game_over_tcp() {
+    let config = ServerConfig {
+        time: TimeControl {
+            unit: Duration::from_millis(1),
+            total: 10_000,
+            byoyomi: 1_000,
+            increment: 0,
+        },
+        max_moves: 40,
+        ..ServerConfig::default()
+    };
+    let (kifu, [sente, gote]) = play_on_localhost(&config, alpha_beta, mcts);
+
+    assert_eq!(kifu.players, ["alice", "bob"]);
+    assert!(!kifu.moves.is_empty());
+    for (report, turn) in [(&sente, Player::Sente), (&gote, Player::Gote)] {
+        assert_eq!(report.summary.my_turn, turn);
+        assert_eq!(report.summary.game_id, "minishogi-rs");
+        assert_eq!(report.summary.time, config.time);
+        assert_eq!(report.kifu, kifu);
+        assert!(report.remaining.iter().all(|&t| t <= 10_000));
+    }
+    // 両者とも同じ消費時間を受け取っている
+    assert_eq!(sente.remaining, gote.remaining);
+
+    match kifu.winner() {
+        Some(Player::Sente) => {
+            assert_eq!((sente.outcome, gote.outcome), (Outcome::Win, Outcome::Lose))
+        }
+        Some(Player::Gote) => {
+            assert_eq!((sente.outcome, gote.outcome), (Outcome::Lose, Outcome::Win))
+        }
+        None => {
+            assert_eq!(kifu.moves.len(), 40);
+            assert_eq!(kifu.ending, Some(Ending::Draw));
+            assert_eq!(sente.outcome, Outcome::Censored);
+            assert_eq!(sente.reason.as_deref(), Some("MAX_MOVES"));
+        }
+    }
+}
+
+#[test]
+fn resignation_and_time_up_end_the_game() {
+    fn resign(_: &GameState, _: Player, _: Duration) -> SearchResult {
+        SearchResult {
+            best_move: None,
+            score: None,
+            visits: Vec::new(),
+        }
+    }
+    let (kifu, [sente, gote]) = play_on_localhost(&ServerConfig::default(), resign, alpha_beta);
+    assert!(kifu.moves.is_empty());
+    assert_eq!(kifu.ending, Some(Ending::Resign));
+    assert_eq!(kifu.winner(), Some(Player::Gote));
+    assert_eq!((sente.outcome, gote.outcome), (Outcome::Lose, Outcome::Win));
+    assert_eq!(gote.reason.as_deref(), Some("RESIGN"));
+    assert_eq!(gote.kifu, kifu);
+
+    fn slow(state: &GameState, player: Player, think_time: Duration) -> SearchResult {
+        thread::sleep(Duration::from_millis(600));
+        alpha_beta(state, player, think_time)
+    }
+    let config = ServerConfig {
+        time: TimeControl {
+            unit: Duration::from_millis(1),
+            total: 200,
+            byoyomi: 0,
+            increment: 0,
+        },
+        ..ServerConfig::default()
+    };
+    let (kifu, [sente, gote]) = play_on_localhost(&config, alpha_beta, slow);
+    // 後手は1手目で時間切れになる
+    assert_eq!(kifu.moves.len(), 1);
+    assert_eq!(kifu.winner(), Some(Player::Sente));
+    assert_eq!((sente.outcome, gote.outcome), (Outcome::Win, Outcome::Lose));
+    assert_eq!(gote.reason.as_deref(), Some("TIME_UP"));
+}
+
+#[test]
+fn game_summary_round_trips_and_time_is_consumed() {
+    let (state, player) = sfen::parse("k4/2S2/5/5/4K w G2P 1").unwrap();
+    let mut kifu = Kifu::new(state, player);
+    kifu.players = ["先手".to_string(), "後手".to_string()];
+    kifu.push(sfen::parse_move("5a4b").unwrap()).unwrap();
+    let summary = GameSummary {
+        game_id: "test-1".to_string(),
+        kifu,
+        my_turn: Player::Sente,
+        time: TimeControl {
+            unit: Duration::from_secs(60),
+            total: 5,
+            byoyomi: 0,
+            increment: 1,
+        },
+        max_moves: 0,
+    };
+    let text = summary.to_text();
+    assert!(text.contains("Time_Unit:1min\n"));
+    assert!(text.contains("To_Move:+\n"));
+    assert!(text.contains("P+00KI00FU00FU\n-\n-5142OU\n"));
+    assert_eq!(GameSummary::parse(&text), Ok(summary));
+    assert!(GameSummary::parse(&text.replace("Your_Turn:+", "Your_Turn:x")).is_err());
+
+    let time = TimeControl {
+        unit: Duration::from_secs(1),
+        total: 60,
+        byoyomi: 10,
+        increment: 0,
+    };
+    assert_eq!(time.units(Duration::from_millis(2_999)), 2);
+    assert_eq!(time.consume(60, 3), Some(57));
+    // 持ち時間を使い切っても秒読みの内なら指せる
+    assert_eq!(time.consume(5, 15), Some(0));
+    assert_eq!(time.consume(5, 16), None);
+    assert!(time.think_time(60) > Duration::from_secs(10));
+    assert!(time.think_time(0) < Duration::from_secs(10));
+}
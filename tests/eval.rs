@@ -0,0 +1,157 @@
+mod common;
+
+use common::{Follow, Step};
+
+use minishogi_rs::board::{self, GameState, Player};
+use minishogi_rs::eval::{Evaluator, IncrementalMaterial, Material, Positional};
+use minishogi_rs::rules::{SearchState, Undo};
+use minishogi_rs::search::{self, SearchOptions};
+use minishogi_rs::{rules, sfen};
+
+/// 盤を180度回して先後を入れ替えた局面
+fn flip(state: &GameState) -> GameState {
+    let mut flipped = *state;
+    for y in 0..5 {
+        for x in 0..5 {
+            flipped.board[4 - y][4 - x] = state.board[y][x].map(|mut piece| {
+                piece.owner = rules::opponent_of(piece.owner);
+                piece
+            });
+        }
+    }
+    flipped.sente_hand = state.gote_hand;
+    flipped.gote_hand = state.sente_hand;
+    flipped
+}
+
+#[test]
+fn evaluation_is_symmetric() {
+    let evaluator = Positional::default();
+    let mut positions = vec![(board::init(), Player::Sente)];
+    for sfen in ["kgsb1/5/4r/4P/RBSGK b P 1", "k1sb1/p1g2/4r/5/RBS1K w Pg 1"] {
+        positions.push(sfen::parse(sfen).unwrap());
+    }
+
+    for (state, player) in positions {
+        for mv in rules::generate_legal_moves(&state, player) {
+            let next = rules::make_move(&state, mv, player);
+            let opponent = rules::opponent_of(player);
+            assert_eq!(
+                evaluator.evaluate(&next, opponent),
+                -evaluator.evaluate(&flip(&next), player),
+                "{}",
+                sfen::format(&next, opponent)
+            );
+        }
+    }
+}
+
+#[test]
+fn startpos_is_balanced_apart_from_tempo() {
+    let evaluator = Positional::default();
+    let state = board::init();
+    let sente = evaluator.evaluate(&state, Player::Sente);
+    let gote = evaluator.evaluate(&state, Player::Gote);
+    assert!(sente > 0);
+    assert_eq!(sente, -gote);
+    assert_eq!(Material.evaluate(&state, Player::Sente), 0);
+}
+
+#[test]
+fn hand_pieces_get_a_bonus() {
+    // 先手が金を持ち駒にしている
+    let (state, player) = sfen::parse("k4/5/5/5/4K b G 1").unwrap();
+    assert_eq!(Material.evaluate(&state, player), 500);
+
+    // 駒割りと持ち駒の加点だけを残す
+    let mut weights = Positional::default().weights;
+    weights.pst = [[0; 25]; 10];
+    weights.king_defender = 0;
+    weights.king_attacker = 0;
+    weights.king_attacked_square = 0;
+    weights.mobility = 0;
+    weights.tempo = 0;
+    let positional = Positional::new(weights.clone());
+    assert_eq!(positional.evaluate(&state, player), 500 + weights.hand[2]);
+}
+
+/// `do_move` / `undo_move` と評価関数の差分更新を交互に呼びながら局面木を辿る
+fn check_incremental(pos: &mut SearchState, evaluator: &mut IncrementalMaterial, depth: u32) {
+    common::walk(pos, depth, Follow::All, &mut |pos, step| match step {
+        Step::Visit => assert_eq!(
+            evaluator.evaluate(&pos.state, pos.player),
+            Material.evaluate(&pos.state, pos.player),
+            "{}",
+            sfen::format(&pos.state, pos.player)
+        ),
+        Step::Do { undo, .. } => evaluator.on_do_move(pos, undo),
+        Step::Undo { undo, .. } => evaluator.on_undo_move(pos, undo),
+    });
+}
+
+#[test]
+fn incremental_material_matches_full_evaluation() {
+    for position in [
+        sfen::STARTPOS,
+        "kgsb1/5/4r/4P/RBSGK b P 1",
+        "k1sb1/p1g2/4r/5/RBS1K w Pg 1",
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        let mut pos = SearchState::new(state, player);
+        let mut evaluator = IncrementalMaterial::default();
+        evaluator.reset(&pos);
+        check_incremental(&mut pos, &mut evaluator, 3);
+    }
+}
+
+/// 差分更新の呼び出しを数える評価関数
+#[derive(Default)]
+struct Counting {
+    depth: i32,
+    max_depth: i32,
+    resets: u32,
+}
+
+impl Evaluator for Counting {
+    fn evaluate(&self, state: &GameState, player: Player) -> i32 {
+        Material.evaluate(state, player)
+    }
+
+    fn reset(&mut self, _pos: &SearchState) {
+        self.resets += 1;
+        self.depth = 0;
+    }
+
+    fn on_do_move(&mut self, _pos: &SearchState, _undo: &Undo) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn on_undo_move(&mut self, _pos: &SearchState, _undo: &Undo) {
+        self.depth -= 1;
+    }
+}
+
+#[test]
+fn searches_accept_custom_evaluators() {
+    let state = board::init();
+    let legal = rules::generate_legal_moves(&state, Player::Sente);
+    let options = SearchOptions::default();
+
+    let mut counting = Counting::default();
+    let mv =
+        search::best_move_alpha_beta_with_evaluator(&state, Player::Sente, &options, &mut counting);
+    assert!(legal.contains(&mv.unwrap()));
+    assert_eq!((counting.resets, counting.depth), (1, 0));
+    assert!(counting.max_depth >= 4);
+
+    let mut counting = Counting::default();
+    let mv = search::best_move_mcts_with_evaluator(&state, Player::Sente, &options, &mut counting);
+    assert!(legal.contains(&mv.unwrap()));
+    assert_eq!((counting.resets, counting.depth), (1, 0));
+
+    let mut boxed: Box<dyn Evaluator> = Box::new(IncrementalMaterial::default());
+    let mv =
+        search::best_move_alpha_beta_with_evaluator(&state, Player::Sente, &options, &mut boxed);
+    assert!(legal.contains(&mv.unwrap()));
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use minishogi_rs::board::Player;
+use minishogi_rs::kif;
+use minishogi_rs::kifu::Ending;
+use minishogi_rs::sfen;
+
+#[test]
+fn games_round_trip_through_kif() {
+    let mut text = String::new();
+    for seed in 1..=8 {
+        let mut kifu = common::random_game(seed);
+        if kifu.winner().is_none() {
+            kifu.ending = Some(Ending::Interrupted);
+        }
+        let exported = kif::to_text(&kifu);
+        assert_eq!(kif::parse(&exported).unwrap(), kifu, "{}", exported);
+        text.push_str(&exported);
+    }
+    // 同・成・打の表記がいずれかの対局に現れる
+    for word in ["同　", "成(", "打\n"] {
+        assert!(text.contains(word), "{}", word);
+    }
+}
+
+#[test]
+fn custom_start_and_notation_are_read() {
+    let text = "\
+# コメント
+開始日時：2026/10/18
+手合割：五々将棋
+先手：太郎
+後手：花子
+後手の持駒：なし
+  ５ ４ ３ ２ １
++---------------+
+|v玉 ・ ・ ・ ・|一
+| ・ ・ 銀 ・ ・|二
+| ・ ・ ・ ・ ・|三
+| ・ ・ ・ ・ ・|四
+| ・ ・ ・ ・ 玉|五
++---------------+
+先手の持駒：金　歩二
+手数----指手---------消費時間--
+   1 ３一銀不成(32)   ( 0:01/00:00:01)
+*コメント
+   2 ４二玉(51)   ( 0:02/00:00:02)
+   3 ４三金打
+   4 同　玉(42)
+   5 投了
+まで4手で後手の勝ち
+";
+    let kifu = kif::parse(text).unwrap();
+    assert_eq!(kifu.players, ["太郎", "花子"]);
+    assert_eq!(
+        sfen::format(&kifu.start, kifu.start_player),
+        "k4/2S2/5/5/4K b G2P 1"
+    );
+    let moves: Vec<String> = kifu.moves.iter().map(|&mv| sfen::format_move(mv)).collect();
+    assert_eq!(moves, ["3b3a", "5a4b", "G*4c", "4b4c"]);
+    assert_eq!(kifu.ending, Some(Ending::Resign));
+    assert_eq!(kifu.winner(), Some(Player::Gote));
+
+    // 書き出すと盤面図と「不成」「同　」が出る
+    let exported = kif::to_text(&kifu);
+    assert!(exported.contains("|v玉 ・ ・ ・ ・|一"));
+    assert!(exported.contains("先手の持駒：金　歩二"));
+    assert!(exported.contains("   1 ３一銀不成(32)"));
+    assert!(exported.contains("   4 同　玉(42)"));
+    assert!(exported.contains("   5 投了\nまで4手で後手の勝ち"));
+    assert_eq!(kif::parse(&exported).unwrap(), kifu);
+}
+
+#[test]
+fn illegal_records_report_line_numbers() {
+    let header = "手合割：五々将棋\n手数----指手---------消費時間--\n";
+    for (body, line, message) in [
+        // 歩は2つ進めない
+        ("   1 １二歩(14)\n", 3, "合法手ではありません"),
+        // 五々将棋に香はない
+        ("   1 １三香(14)\n", 3, "指し手を読めません"),
+        ("   1 １三金(14)\n", 3, "移動元の駒は歩です"),
+        (
+            "   1 １三歩(14)\n   3 ２四金(25)\n",
+            4,
+            "手数が 2 ではありません",
+        ),
+        ("   1 同　歩(14)\n", 3, "「同」の前に指し手がありません"),
+        (
+            "   1 投了\n   2 １三歩(14)\n",
+            4,
+            "終局の後に指し手があります",
+        ),
+    ] {
+        let err = kif::parse(&format!("{}{}", header, body)).unwrap_err();
+        assert!(err.starts_with(&format!("{}行目: ", line)), "{}", err);
+        assert!(err.contains(message), "{}", err);
+    }
+
+    // 持駒は五々将棋の駒の数まで
+    let board = "\
+  ５ ４ ３ ２ １
++---------------+
+|v玉 ・ ・ ・ ・|一
+| ・ ・ ・ ・ ・|二
+| ・ ・ ・ ・ ・|三
+| ・ ・ ・ ・ 歩|四
+| ・ ・ ・ ・ 玉|五
++---------------+
+";
+    for (hands, message) in [
+        ("先手の持駒：歩三\n", "歩が2枚を超えています"),
+        ("先手の持駒：歩300\n", "歩が2枚を超えています"),
+        (
+            "先手の持駒：歩99999999999999999999\n",
+            "持駒の枚数を読めません",
+        ),
+        ("後手の持駒：歩二\n", "歩が2枚を超えています"),
+    ] {
+        let err = kif::parse(&format!("{}{}", hands, board)).unwrap_err();
+        assert!(err.contains(message), "{}: {}", hands, err);
+    }
+
+    let err = kif::parse("手合割：平手\n").unwrap_err();
+    assert!(
+        err.starts_with("1行目: ") && err.contains("平手"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{Follow, Step};
+
+use minishogi_rs::board::{self, Player};
+use minishogi_rs::rules::{self, SearchState};
+use minishogi_rs::{sfen, zobrist};
+
+/// do_move / undo_move が make_move と一致し、ハッシュ値も再計算と一致することを局面木全体で確かめる
+fn check_tree(pos: &mut SearchState, depth: u32) {
+    common::walk(pos, depth, Follow::All, &mut |pos, step| match step {
+        Step::Visit => assert_eq!(pos.hash, zobrist::hash(&pos.state, pos.player)),
+        Step::Do { before, undo } => {
+            let expected = rules::make_move(&before.state, undo.mv, before.player);
+            assert_eq!(pos.state, expected, "{}", sfen::format_move(undo.mv));
+            assert_eq!(pos.player, rules::opponent_of(before.player));
+        }
+        Step::Undo { before, undo } => assert_eq!(pos, before, "{}", sfen::format_move(undo.mv)),
+    });
+}
+
+#[test]
+fn do_undo_matches_make_move_from_startpos() {
+    check_tree(&mut SearchState::new(board::init(), Player::Sente), 3);
+}
+
+#[test]
+fn do_undo_matches_make_move_with_hands_and_promotions() {
+    for position in [
+        "kgsb1/5/4r/4P/RBSGK b P 1",
+        "k1sb1/p1g2/4r/5/RBS1K w Pg 1",
+        "k4/2S2/1G3/5/4K b P 1",
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        check_tree(&mut SearchState::new(state, player), 3);
+    }
+}
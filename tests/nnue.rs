@@ -0,0 +1,148 @@
+mod common;
+
+use common::{Follow, Step};
+
+use minishogi_rs::board::{self, GameState, PieceType, Player};
+use minishogi_rs::eval::Evaluator;
+use minishogi_rs::nnue::{Network, Nnue, Trainer, TrainingSample};
+use minishogi_rs::rules::{self, Move, SearchState};
+use minishogi_rs::search::{self, Rng};
+use minishogi_rs::{sfen, tune};
+
+/// 差分更新した評価値が全体の再計算と一致することを局面木全体で確かめる（玉を取った局面も評価できる）
+fn check_incremental(pos: &mut SearchState, evaluator: &mut Nnue, depth: u32) {
+    common::walk(pos, depth, Follow::All, &mut |pos, step| match step {
+        Step::Visit => assert_eq!(
+            evaluator.evaluate_position(pos),
+            evaluator.network().evaluate_full(&pos.state, pos.player),
+            "{}",
+            sfen::format(&pos.state, pos.player)
+        ),
+        Step::Do { undo, .. } => evaluator.on_do_move(pos, undo),
+        Step::Undo { undo, .. } => evaluator.on_undo_move(pos, undo),
+    });
+}
+
+/// ランダムな対局の局面を学習用に集める
+fn random_samples(games: usize, seed: u64) -> Vec<TrainingSample> {
+    let mut rng = Rng::with_seed(seed);
+    let mut engine_rng = Rng::with_seed(seed + 1);
+    let mut samples = Vec::new();
+    for _ in 0..games {
+        let (moves, result) = tune::selfplay_game(&mut rng, 0, 60, |state, player| {
+            let legal = rules::generate_legal_moves(state, player);
+            // 玉を取れるなら取る
+            let capture_king = legal.iter().copied().find(|&mv| match mv {
+                Move::To(_, to, _) => {
+                    state.board[to.y][to.x].is_some_and(|p| p.piece_type == PieceType::King)
+                }
+                Move::Drop(..) => false,
+            });
+            if legal.is_empty() {
+                None
+            } else {
+                capture_king.or(Some(legal[engine_rng.range(legal.len())]))
+            }
+        });
+        samples.extend(
+            tune::positions_from_game(&moves, result)
+                .iter()
+                .map(|(state, player, result)| TrainingSample::new(state, *player, *result)),
+        );
+    }
+    samples
+}
+
+fn positions() -> Vec<(GameState, Player)> {
+    [
+        "kgsbr/p4/5/4P/RBSGK b - 1",
+        "kgsb1/5/4r/4P/RBSGK b P 1",
+        "k1sb1/p1g2/4r/5/RBS1K w Pg 1",
+        "k4/2S2/1G3/5/4K b P 1",
+    ]
+    .iter()
+    .map(|s| sfen::parse(s).unwrap())
+    .collect()
+}
+
+#[test]
+fn incremental_evaluation_matches_full_evaluation() {
+    let mut evaluator = Nnue::new(Trainer::new(1).to_network());
+    for (state, player) in positions() {
+        let mut pos = SearchState::new(state, player);
+        evaluator.reset(&pos);
+        check_incremental(&mut pos, &mut evaluator, 3);
+    }
+}
+
+#[test]
+fn network_round_trips_through_bytes() {
+    let network = Trainer::new(2).to_network();
+    let bytes = network.to_bytes();
+    assert_eq!(&bytes[..4], b"MSNN");
+    assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+
+    assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(Network::from_bytes(&extra).is_err());
+    assert!(Network::from_bytes(b"MSTB").is_err());
+}
+
+#[test]
+fn training_reduces_loss() {
+    let samples = random_samples(30, 3);
+    let mut trainer = Trainer::new(4);
+    let before = trainer.loss(&samples);
+    for _ in 0..10 {
+        trainer.epoch(&samples, 32);
+    }
+    let after = trainer.loss(&samples);
+    assert!(after < before * 0.9, "{} -> {}", before, after);
+}
+
+#[test]
+fn quantized_network_follows_float_network() {
+    let samples = random_samples(10, 5);
+    let mut trainer = Trainer::new(6);
+    for _ in 0..3 {
+        trainer.epoch(&samples, 32);
+    }
+    let network = trainer.to_network();
+
+    for (state, player) in positions() {
+        let float = trainer.evaluate(&state, player);
+        let quantized = match player {
+            Player::Sente => network.evaluate_full(&state, player),
+            Player::Gote => -network.evaluate_full(&state, player),
+        };
+        assert!(
+            (float - quantized as f32).abs() < 40.0,
+            "{}: {} vs {}",
+            sfen::format(&state, player),
+            float,
+            quantized
+        );
+    }
+}
+
+#[test]
+fn searches_run_with_nnue() {
+    let network = Trainer::new(7).to_network();
+    let state = board::init();
+    let options = search::SearchOptions::default();
+    let mut evaluator = Nnue::new(network);
+    assert!(
+        search::best_move_alpha_beta_with_evaluator(
+            &state,
+            Player::Sente,
+            &options,
+            &mut evaluator
+        )
+        .is_some()
+    );
+    assert!(
+        search::best_move_mcts_with_evaluator(&state, Player::Sente, &options, &mut evaluator)
+            .is_some()
+    );
+}
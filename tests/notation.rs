@@ -0,0 +1,185 @@
+mod common;
+
+use minishogi_rs::board::Player;
+use minishogi_rs::notation::{self, Notation};
+use minishogi_rs::rules;
+use minishogi_rs::sfen;
+
+/// 局面 `position` で USI 形式の `usi` の手を日本式の表記にする
+fn japanese(position: &str, usi: &str) -> String {
+    let (state, player) = sfen::parse(position).unwrap();
+    let mv = sfen::parse_move(usi).unwrap();
+    assert!(rules::generate_legal_moves(&state, player).contains(&mv));
+    let text = notation::format_japanese(&state, player, mv, None);
+    assert_eq!(
+        notation::parse_japanese(&text, &state, player, None),
+        Ok(mv),
+        "{}",
+        text
+    );
+    text
+}
+
+#[test]
+fn identical_pieces_are_disambiguated() {
+    for (position, usi, expected) in [
+        // 動作で区別する
+        ("k4/2G2/4G/5/4K b - 1", "3b2b", "２二金寄"),
+        ("k4/2G2/4G/5/4K b - 1", "1c2b", "２二金上"),
+        ("k2G1/5/4G/5/4K b - 1", "2a2b", "２二金引"),
+        // どちらも上がるときは位置で区別する
+        ("k4/5/2G1G/5/4K b - 1", "3c2b", "２二金左"),
+        ("k4/5/2G1G/5/4K b - 1", "1c2b", "２二金右"),
+        // 真っ直ぐ上がる手は「直」
+        ("k4/5/3GG/5/4K b - 1", "2c2b", "２二金直"),
+        ("k4/5/3GG/5/4K b - 1", "1c2b", "２二金右"),
+        // 龍・馬には「直」を使わない
+        ("k4/5/4+R/3+R1/4K b - 1", "2d2b", "２二龍左"),
+        ("k4/5/4+R/3+R1/4K b - 1", "1c2b", "２二龍右"),
+        // 後手の左右は後手から見た向き
+        ("5/5/2g1g/5/K3k w - 1", "3c2d", "２四金右"),
+        ("5/5/2g1g/5/K3k w - 1", "1c2d", "２四金左"),
+        // 1枚しか動けなければ何も付けない
+        ("k4/5/2G2/5/4K b - 1", "3c2b", "２二金"),
+    ] {
+        assert_eq!(japanese(position, usi), expected, "{} {}", position, usi);
+    }
+}
+
+#[test]
+fn promotion_drop_and_recapture_are_written() {
+    // 成れる手は「成」か「不成」
+    assert_eq!(japanese("k4/4S/5/5/4K b - 1", "1b1a+"), "１一銀成");
+    assert_eq!(japanese("k4/4S/5/5/4K b - 1", "1b1a"), "１一銀不成");
+    // 「打」は盤上の同じ駒も動けるときだけ
+    assert_eq!(japanese("k4/5/5/5/4K b G 1", "G*2b"), "２二金");
+    assert_eq!(japanese("k4/5/4G/5/4K b G 1", "G*2b"), "２二金打");
+    assert_eq!(japanese("k4/5/4G/5/4K b G 1", "1c2b"), "２二金");
+
+    // 直前の手と同じマスは「同」
+    let (state, player) = sfen::parse("k4/5/4p/4G/4K w - 1").unwrap();
+    let previous = sfen::parse_move("1c1d").unwrap();
+    let state = rules::make_move(&state, previous, player);
+    let recapture = sfen::parse_move("1e1d").unwrap();
+    assert_eq!(
+        notation::format_japanese(&state, Player::Sente, recapture, Some(previous)),
+        "同玉"
+    );
+    for input in ["同玉", "同　玉", "▲同 玉", "１四玉", "14王"] {
+        assert_eq!(
+            notation::parse_japanese(input, &state, Player::Sente, Some(previous)),
+            Ok(recapture),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn lenient_and_invalid_input() {
+    let (state, player) = sfen::parse("k4/4S/5/5/4K b - 1").unwrap();
+    // 「不成」は省いてもよい
+    assert_eq!(
+        notation::parse_japanese("１一銀", &state, player, None),
+        Ok(sfen::parse_move("1b1a").unwrap())
+    );
+
+    let (state, player) = sfen::parse("k4/5/4G/5/4K b G 1").unwrap();
+    // 「打」は省かずに書いてもよい
+    assert_eq!(
+        notation::parse_japanese("３四金打", &state, player, None),
+        Ok(sfen::parse_move("G*3d").unwrap())
+    );
+
+    let (state, player) = sfen::parse("k4/5/2G1G/5/4K b - 1").unwrap();
+    for (input, message) in [
+        ("２二金", "決まりません"),
+        ("２二金直", "合法手ではありません"),
+        ("５五飛", "合法手ではありません"),
+        ("同金", "「同」の前に指し手がありません"),
+        ("２二香", "読めません"),
+        ("２二金引く", "読めません"),
+        ("六二金", "読めません"),
+    ] {
+        let err = notation::parse_japanese(input, &state, player, None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(message), "{}: {}", input, err);
+    }
+}
+
+#[test]
+fn every_legal_move_has_a_unique_notation() {
+    for seed in 1..=6 {
+        let kifu = common::random_game(seed);
+        let mut previous = None;
+        for (&played, (state, player)) in kifu.moves.iter().zip(kifu.positions()) {
+            let moves = rules::generate_legal_moves(&state, player);
+            for notation in [Notation::Japanese, Notation::Western] {
+                let texts: Vec<String> = moves
+                    .iter()
+                    .map(|&mv| notation.format_move(&state, player, mv, previous))
+                    .collect();
+                for (&mv, text) in moves.iter().zip(&texts) {
+                    assert_eq!(
+                        notation.parse_move(text, &state, player, previous),
+                        Ok(mv),
+                        "{} {}",
+                        sfen::format(&state, player),
+                        text
+                    );
+                    assert_eq!(texts.iter().filter(|t| *t == text).count(), 1, "{}", text);
+                }
+            }
+            previous = Some(played);
+        }
+    }
+}
+
+#[test]
+fn western_notation_marks_captures_drops_and_promotions() {
+    for (position, usi, expected) in [
+        ("k4/4S/5/5/4K b - 1", "1b1a+", "S-1a+"),
+        ("k4/4S/5/5/4K b - 1", "1b1a", "S-1a="),
+        ("k4/5/5/5/4K b P 1", "P*3c", "P*3c"),
+        ("k4/5/2p2/1B3/4K b - 1", "4d3c", "Bx3c"),
+        ("k4/5/2p2/1+B3/4K b - 1", "4d3c", "+Bx3c"),
+        ("k4/5/5/5/4K b - 1", "1e1d", "K-1d"),
+        // 同じ駒が他にも動けるときは移動元を書く
+        ("k4/5/2G1G/5/4K b - 1", "3c2b", "G3c-2b"),
+        ("k4/5/2G1G/5/4K b - 1", "1c2b", "G1c-2b"),
+        ("5/5/2g1g/5/K3k w - 1", "3c2d", "G3c-2d"),
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        let mv = sfen::parse_move(usi).unwrap();
+        assert!(rules::generate_legal_moves(&state, player).contains(&mv));
+        let text = notation::format_western(&state, player, mv);
+        assert_eq!(text, expected, "{} {}", position, usi);
+        assert_eq!(notation::parse_western(&text, &state, player), Ok(mv));
+    }
+
+    let (state, player) = sfen::parse("k4/4S/5/2G1G/4K b - 1").unwrap();
+    // 移動元は1手に決まっても書いてよく、「=」は省いてもよい
+    for (input, usi) in [("S1b-1a", "1b1a"), ("S-1a", "1b1a"), ("G3d-2c", "3d2c")] {
+        assert_eq!(
+            notation::parse_western(input, &state, player),
+            Ok(sfen::parse_move(usi).unwrap()),
+            "{}",
+            input
+        );
+    }
+    for (input, message) in [
+        ("G-2c", "決まりません"),
+        ("Gx3c", "合法手ではありません"),
+        ("S-1a+=", "読めません"),
+        ("+S-1a", "合法手ではありません"),
+        ("s-1a", "読めません"),
+        ("S-6a", "読めません"),
+        ("G*2c", "合法手ではありません"),
+    ] {
+        let err = notation::parse_western(input, &state, player)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(message), "{}: {}", input, err);
+    }
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::Follow;
+
+use minishogi_rs::board::{self, PieceType, Player};
+use minishogi_rs::perft::{divide, perft};
+use minishogi_rs::rules::{self, Move, MoveList, SearchState};
+use minishogi_rs::sfen;
+
+/// 5五将棋の初期局面の perft（公開されている値）
+const STARTPOS_NODES: [u64; 5] = [14, 181, 2_512, 35_401, 533_203];
+
+#[test]
+fn perft_initial_position() {
+    let state = board::init();
+    for (i, &expected) in STARTPOS_NODES.iter().enumerate() {
+        let depth = i as u32 + 1;
+        assert_eq!(
+            perft(&state, Player::Sente, depth),
+            expected,
+            "depth {}",
+            depth
+        );
+    }
+}
+
+#[test]
+#[ignore = "release ビルドで数秒かかる"]
+fn perft_initial_position_depth_6() {
+    assert_eq!(perft(&board::init(), Player::Sente, 6), 8_276_188);
+}
+
+#[test]
+fn startpos_sfen_matches_init() {
+    let (state, player) = sfen::parse(sfen::STARTPOS).unwrap();
+    assert_eq!(state, board::init());
+    assert_eq!(player, Player::Sente);
+}
+
+#[test]
+fn sfen_rejects_impossible_material() {
+    for (position, message) in [
+        ("k4/5/5/5/4K b 99999999999P 1", "大きすぎます"),
+        ("k4/5/5/5/4K b 300P 1", "Pが2枚を超えています"),
+        ("k4/5/5/5/4K b 3P 1", "Pが2枚を超えています"),
+        ("k4/5/5/5/4K b PpP 1", "Pが2枚を超えています"),
+        ("k4/5/5/4P/4K b Pp 1", "Pが2枚を超えています"),
+        ("k4/5/2G1G/5/4K b G 1", "Gが2枚を超えています"),
+        ("k4/5/5/5/K3K b - 1", "玉は先手・後手とも1枚までです"),
+    ] {
+        let err = sfen::parse(position).unwrap_err();
+        assert!(err.contains(message), "{}: {}", position, err);
+    }
+    // 玉を取られて終わった局面は読める
+    assert!(sfen::parse("5/5/5/5/4K w G 1").is_ok());
+}
+
+#[test]
+fn perft_positions_with_hands() {
+    let cases: [(&str, [u64; 3]); 2] = [
+        ("kgsb1/5/4r/4P/RBSGK b P 1", [28, 171, 3_560]),
+        ("k1sb1/p1g2/4r/5/RBS1K w Pg 1", [35, 226, 6_886]),
+    ];
+
+    for (position, nodes) in cases {
+        let (state, player) = sfen::parse(position).unwrap();
+        for (i, &expected) in nodes.iter().enumerate() {
+            let depth = i as u32 + 1;
+            assert_eq!(
+                perft(&state, player, depth),
+                expected,
+                "{} depth {}",
+                position,
+                depth
+            );
+        }
+    }
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let state = board::init();
+    let split = divide(&state, Player::Sente, 3);
+    assert_eq!(split.len() as u64, STARTPOS_NODES[0]);
+    assert_eq!(
+        split.iter().map(|&(_, n)| n).sum::<u64>(),
+        STARTPOS_NODES[2]
+    );
+}
+
+/// 初期局面から深さ 3 までの各局面で `check` を呼ぶ
+fn walk(check: &mut impl FnMut(&board::GameState, Player)) {
+    let mut pos = SearchState::new(board::init(), Player::Sente);
+    common::visit(&mut pos, 3, Follow::Safe, &mut |pos| {
+        check(&pos.state, pos.player)
+    });
+}
+
+#[test]
+fn fast_generator_differs_only_by_pawn_drop_mate() {
+    walk(&mut |state, player| {
+        let fast = rules::generate_moves_fast(state, player);
+        let legal = rules::generate_legal_moves(state, player);
+        assert!(legal.iter().all(|mv| fast.contains(mv)));
+
+        for mv in fast.iter().filter(|mv| !legal.contains(mv)) {
+            assert!(matches!(mv, Move::Drop(_, PieceType::Pawn)));
+            let next = rules::make_move(state, *mv, player);
+            assert!(rules::is_checkmate(&next, rules::opponent_of(player)));
+        }
+    });
+}
+
+#[test]
+fn no_illegal_pawn_drops() {
+    walk(&mut |state, player| {
+        for mv in rules::generate_moves_fast(state, player) {
+            let Move::Drop(to, PieceType::Pawn) = mv else {
+                continue;
+            };
+            let last_rank = match player {
+                Player::Sente => 0,
+                Player::Gote => 4,
+            };
+            assert_ne!(to.y, last_rank, "最奥段への歩打ち");
+            let nifu = (0..5).any(|y| {
+                state.board[y][to.x].is_some_and(|p| {
+                    p.piece_type == PieceType::Pawn && p.owner == player && !p.promoted
+                })
+            });
+            assert!(!nifu, "二歩");
+        }
+    });
+}
+
+#[test]
+fn pawn_drop_mate_is_excluded() {
+    // 5a の玉に対する 5b への歩打ちは、4c の金と 3b の銀で逃げ道がなく打ち歩詰め
+    let (state, player) = sfen::parse("k4/2S2/1G3/5/4K b P 1").unwrap();
+    let drop = Move::Drop(rules::Position::new(0, 1), PieceType::Pawn);
+    assert!(rules::generate_moves_fast(&state, player).contains(&drop));
+    assert!(!rules::generate_legal_moves(&state, player).contains(&drop));
+}
+
+#[test]
+fn generation_into_buffer_matches_vec() {
+    let mut buffer = MoveList::new();
+    walk(&mut |state, player| {
+        rules::generate_moves_fast_into(state, player, &mut buffer);
+        assert_eq!(&buffer[..], &rules::generate_moves_fast(state, player)[..]);
+        rules::generate_legal_moves_into(state, player, &mut buffer);
+        assert_eq!(&buffer[..], &rules::generate_legal_moves(state, player)[..]);
+    });
+}
+
+#[test]
+fn move_list_holds_all_drops() {
+    // 全ての駒を持ち駒にした局面で駒打ちが最も多くなる
+    let (state, player) = sfen::parse("4k/5/5/5/K4 b 2R2B2G2S2P 1").unwrap();
+    let mut buffer = MoveList::new();
+    rules::generate_moves_fast_into(&state, player, &mut buffer);
+    // 玉の3手 + 空き23マスへの4種 + 一段目を除く19マスへの歩
+    assert_eq!(buffer.len(), 3 + 23 * 4 + 19);
+    assert!(buffer.len() <= rules::MAX_MOVES);
+}
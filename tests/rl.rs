@@ -0,0 +1,127 @@
+mod common;
+
+use common::Follow;
+
+use minishogi_rs::board::{self, PieceType, Player};
+use minishogi_rs::rl::{self, ACTION_COUNT, Env, OBSERVATION_SIZE};
+use minishogi_rs::rules::{self, Move, SearchState};
+use minishogi_rs::search::Rng;
+use minishogi_rs::sfen;
+
+/// 局面木のすべての合法手が行動の番号と1対1に対応することを確かめる
+fn check_actions(pos: &mut SearchState, depth: u32) {
+    common::visit(pos, depth, Follow::All, &mut |pos| {
+        let moves = rules::generate_legal_moves(&pos.state, pos.player);
+        let mask = rl::legal_action_mask(&pos.state, pos.player);
+        assert_eq!(mask.iter().filter(|&&m| m).count(), moves.len());
+
+        for &mv in &moves {
+            let action = rl::encode_move(mv);
+            assert!(action < ACTION_COUNT);
+            assert!(mask[action]);
+            assert_eq!(
+                rl::decode_action(action),
+                Some(mv),
+                "{}",
+                sfen::format_move(mv)
+            );
+        }
+    });
+}
+
+#[test]
+fn legal_moves_map_to_distinct_actions() {
+    for position in [
+        "kgsbr/p4/5/4P/RBSGK b - 1",
+        "k1sb1/p1g2/4r/5/RBS1K w Pg 1",
+        "k4/2S2/1G3/5/4K b PSGBR 1",
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        check_actions(&mut SearchState::new(state, player), 2);
+    }
+
+    // すべての番号が指し手に戻り、番号に戻すと元の番号になる
+    for action in 0..ACTION_COUNT {
+        let mv = rl::decode_action(action).unwrap();
+        assert_eq!(rl::encode_move(mv), action);
+    }
+    assert_eq!(rl::decode_action(ACTION_COUNT), None);
+}
+
+#[test]
+fn observation_encodes_pieces_hands_and_side_to_move() {
+    let (state, _) = sfen::parse("k4/5/5/5/4K b Pgg 1").unwrap();
+    let sente = rl::observe(&state, Player::Sente);
+    let gote = rl::observe(&state, Player::Gote);
+    assert_eq!(sente.len(), OBSERVATION_SIZE);
+
+    let plane = |obs: &[f32], p: usize| obs[p * 25..(p + 1) * 25].to_vec();
+    // 先手玉（種類 5）は 1e = (x 4, y 4)、後手玉は 5a = (x 0, y 0)
+    assert_eq!(plane(&sente, 5)[24], 1.0);
+    assert_eq!(plane(&sente, 15)[0], 1.0);
+    assert_eq!(plane(&sente, 5).iter().sum::<f32>(), 1.0);
+    // 先手の歩1枚、後手の金2枚
+    assert!(plane(&sente, 20).iter().all(|&v| v == 0.5));
+    assert!(plane(&sente, 27).iter().all(|&v| v == 1.0));
+    assert!(plane(&sente, 21).iter().all(|&v| v == 0.0));
+    // 手番の面だけが違う
+    assert!(plane(&sente, 30).iter().all(|&v| v == 1.0));
+    assert!(plane(&gote, 30).iter().all(|&v| v == 0.0));
+    assert_eq!(sente[..30 * 25], gote[..30 * 25]);
+}
+
+#[test]
+fn random_episodes_end_with_a_winner_or_the_ply_limit() {
+    let mut rng = Rng::with_seed(7);
+    let mut env = Env::new(60);
+
+    for _ in 0..20 {
+        let observation = env.reset();
+        assert_eq!(observation, rl::observe(&board::init(), Player::Sente));
+
+        loop {
+            let actions = env.legal_actions();
+            assert!(!actions.is_empty());
+            let mover = env.player();
+            let before = *env.state();
+            let action = actions[rng.range(actions.len())];
+            let step = env.step(action).unwrap();
+
+            let mv = rl::decode_action(action).unwrap();
+            assert_eq!(*env.state(), rules::make_move(&before, mv, mover));
+            assert_eq!(step.observation, rl::observe(env.state(), env.player()));
+
+            if step.done {
+                let captured_king = matches!(mv, Move::To(_, to, _)
+                    if before.board[to.y][to.x].is_some_and(|p| p.piece_type == PieceType::King));
+                if step.reward == 1.0 {
+                    let opponent_has_moves =
+                        !rules::generate_legal_moves(env.state(), env.player()).is_empty();
+                    assert!(captured_king || !opponent_has_moves);
+                } else {
+                    assert_eq!(step.reward, 0.0);
+                    assert_eq!(env.plies(), 60);
+                }
+                break;
+            }
+            assert_eq!(step.reward, 0.0);
+        }
+
+        assert!(env.legal_actions().is_empty());
+        assert!(env.legal_action_mask().iter().all(|&m| !m));
+        assert!(env.step(0).is_err());
+    }
+}
+
+#[test]
+fn illegal_actions_are_rejected() {
+    let mut env = Env::default();
+    env.reset();
+    let mask = env.legal_action_mask();
+    let illegal = (0..ACTION_COUNT).find(|&a| !mask[a]).unwrap();
+    assert!(env.step(illegal).is_err());
+    assert!(env.step(ACTION_COUNT).is_err());
+    // 失敗した行動では局面は変わらない
+    assert_eq!(*env.state(), board::init());
+    assert_eq!(env.player(), Player::Sente);
+}
@@ -0,0 +1,118 @@
+use minishogi_rs::board::Player;
+use minishogi_rs::eval::Positional;
+use minishogi_rs::search::{self, Rng, SearchOptions};
+use minishogi_rs::{rules, sfen};
+use std::time::{Duration, Instant};
+
+#[test]
+fn mcts_takes_the_king_when_it_can() {
+    for (position, expected) in [
+        ("k4/1G3/5/5/4K b - 1", "4b5a"),
+        ("4k/5/5/5/K3r w - 1", "1e5e"),
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        let result = search::search_mcts(
+            &state,
+            player,
+            &SearchOptions::default(),
+            &mut Positional::default(),
+        );
+        let best = sfen::format_move(result.best_move.unwrap());
+        assert!(best.starts_with(expected), "{}: {}", position, best);
+        // 勝ちの局面なので、手番側から見て大きな評価値になる
+        let score = result.score.unwrap();
+        let score = if player == Player::Sente {
+            score
+        } else {
+            -score
+        };
+        assert!(score > 1000, "{}: {}", position, score);
+    }
+}
+
+#[test]
+fn seeded_mcts_is_reproducible() {
+    let (state, player) = sfen::parse("kgsbr/p4/5/4P/RBSGK b - 1").unwrap();
+    let options = SearchOptions {
+        iterations: Some(2000),
+        seed: Some(7),
+        ..SearchOptions::default()
+    };
+    let first = search::search_mcts(&state, player, &options, &mut Positional::default());
+    let second = search::search_mcts(&state, player, &options, &mut Positional::default());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn time_limited_search_deepens_within_the_limit() {
+    let (state, player) = sfen::parse("kgsbr/p4/5/4P/RBSGK b - 1").unwrap();
+    let limit = Duration::from_millis(100);
+    let options = SearchOptions {
+        time_limit: Some(limit),
+        ..SearchOptions::default()
+    };
+
+    // 時間切れでも深さ 1 は読み終えるので、手と評価値は必ず返る。深い反復は読んでいる途中でも打ち切る
+    let started = Instant::now();
+    let result = search::search_alpha_beta(&state, player, &options, &mut Positional::default());
+    assert!(result.best_move.is_some() && result.score.is_some());
+    assert!(started.elapsed() < limit * 2, "{:?}", started.elapsed());
+
+    let started = Instant::now();
+    let result = search::search_mcts(&state, player, &options, &mut Positional::default());
+    assert!(result.best_move.is_some());
+    assert!(started.elapsed() < limit * 5, "{:?}", started.elapsed());
+
+    // 深さを指定すればその深さで打ち切る
+    let fixed = SearchOptions {
+        depth: Some(2),
+        ..SearchOptions::default()
+    };
+    let timed = SearchOptions {
+        time_limit: Some(Duration::from_secs(60)),
+        ..fixed
+    };
+    let mut evaluator = Positional::default();
+    assert_eq!(
+        search::search_alpha_beta(&state, player, &timed, &mut evaluator).score,
+        search::search_alpha_beta(&state, player, &fixed, &mut evaluator).score
+    );
+}
+
+#[test]
+fn neighbouring_seeds_give_unrelated_sequences() {
+    // 下位ビットだけが違うシードも別の系列になる
+    let sequence = |seed| {
+        let mut rng = Rng::with_seed(seed);
+        [rng.next_u64(), rng.next_u64(), rng.next_u64()]
+    };
+    for seed in [0, 2, 1 << 40] {
+        assert_ne!(sequence(seed), sequence(seed + 1));
+    }
+    assert_eq!(sequence(5), sequence(5));
+}
+
+#[test]
+fn mcts_expects_the_king_capturing_reply() {
+    // 王手を放置すると 2 手目に王を取られる。子の勝率を指した側から見て積んでいれば、
+    // 王を取る応手を読んで、放置する手は最も訪問回数が少なくなる
+    for (position, blunder) in [
+        ("k4/5/5/5/K3r b - 1", "5e4e"),
+        ("R3k/5/5/5/4K w - 1", "1a2a"),
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        let blunder = sfen::parse_move(blunder).unwrap();
+        for seed in 1..=3 {
+            let options = SearchOptions {
+                iterations: Some(3000),
+                seed: Some(seed),
+                ..SearchOptions::default()
+            };
+            let result = search::search_mcts(&state, player, &options, &mut Positional::default());
+            let fewest = result.visits.iter().min_by_key(|&&(_, n)| n).unwrap().0;
+            assert_eq!(fewest, blunder, "{}: {:?}", position, result.visits);
+            let next = rules::make_move(&state, result.best_move.unwrap(), player);
+            assert!(!rules::is_in_check(&next, player), "{}", position);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use minishogi_rs::engine::Builtin;
+use minishogi_rs::rules;
+use minishogi_rs::search::{Rng, SearchOptions};
+use minishogi_rs::selfplay::{self, GameRecord, SelfplayConfig};
+
+fn play(engines: [&str; 2], random_plies: usize, max_plies: usize, seed: u64) -> GameRecord {
+    let config = SelfplayConfig {
+        engines: engines.map(|e| Builtin::parse(e).unwrap()),
+        random_plies,
+        max_plies,
+        ..SelfplayConfig::default()
+    };
+    selfplay::play_game(
+        &config,
+        &SearchOptions::default(),
+        &mut Rng::with_seed(seed),
+    )
+}
+
+/// 記録された局面が指し手どおりにつながっていることを確かめる
+fn check_consistent(game: &GameRecord) {
+    for pair in game.plies.windows(2) {
+        let next = rules::make_move(&pair[0].state, pair[0].mv, pair[0].player);
+        assert_eq!(pair[1].state, next);
+        assert_eq!(pair[1].player, rules::opponent_of(pair[0].player));
+    }
+}
+
+#[test]
+fn records_scores_and_visits() {
+    let game = play(["mcts:200", "ab"], 2, 4, 1);
+    assert_eq!(game.plies.len(), 4);
+    assert_eq!(game.engines, ["mcts:200", "alphabeta"]);
+    check_consistent(&game);
+
+    // ランダムな手には評価値も訪問回数もない
+    for ply in &game.plies[..2] {
+        assert_eq!(ply.score, None);
+        assert!(ply.visits.is_empty());
+    }
+    // MCTS（先手）は訪問回数を、Alpha-Beta（後手）は評価値だけを記録する
+    let mcts = &game.plies[2];
+    assert!(mcts.score.is_some());
+    assert!(!mcts.visits.is_empty());
+    let legal = rules::generate_legal_moves(&mcts.state, mcts.player);
+    assert!(mcts.visits.iter().all(|(mv, _)| legal.contains(mv)));
+    let best = mcts.visits.iter().max_by_key(|&&(_, n)| n).unwrap();
+    assert_eq!(best.0, mcts.mv);
+
+    let ab = &game.plies[3];
+    assert!(ab.score.is_some());
+    assert!(ab.visits.is_empty());
+
+    // 手数の上限に達すれば引き分け
+    assert_eq!(game.result_text(), "1/2-1/2");
+}
+
+#[test]
+fn records_round_trip_through_text() {
+    let games = vec![
+        play(["mcts", "ab:2"], 3, 5, 2),
+        play(["alphabeta", "ab"], 8, 12, 3),
+    ];
+    for game in &games {
+        check_consistent(game);
+        assert!(game.to_record_line().ends_with(game.result_text()));
+    }
+    let text = selfplay::to_text(&games);
+    assert_eq!(selfplay::parse(&text).unwrap(), games);
+}
+
+#[test]
+fn malformed_records_report_line_numbers() {
+    let err = selfplay::parse("game ab ab\nkgsbr/p4/5/4P/RBSGK b - 1\t1d1c\t-\t-\n").unwrap_err();
+    assert!(err.contains("結果がありません"), "{}", err);
+
+    let err = selfplay::parse("game ab ab\nkgsbr/p4/5/4P/RBSGK b - 1\t1a1b\t-\t-\nresult 1-0\n")
+        .unwrap_err();
+    assert!(err.starts_with("2行目"), "{}", err);
+
+    let err = selfplay::parse("game ab ab\nkgsbr/p4/5/4P/RBSGK b - 1\t1d1c\tx\t-\nresult 1-0\n")
+        .unwrap_err();
+    assert!(err.starts_with("2行目"), "{}", err);
+}
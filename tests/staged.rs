@@ -0,0 +1,93 @@
+mod common;
+
+use common::Follow;
+
+use minishogi_rs::board::{self, GameState, Player};
+use minishogi_rs::rules::{self, Move, SearchState};
+use minishogi_rs::{sfen, tsume};
+
+fn sorted(moves: Vec<Move>) -> Vec<String> {
+    let mut s: Vec<String> = moves.into_iter().map(sfen::format_move).collect();
+    s.sort();
+    s
+}
+
+/// 合法手を条件で絞り込んだ結果
+fn filtered(
+    state: &GameState,
+    player: Player,
+    keep: impl Fn(Move, &GameState) -> bool,
+) -> Vec<String> {
+    sorted(
+        rules::generate_legal_moves(state, player)
+            .into_iter()
+            .filter(|&mv| keep(mv, &rules::make_move(state, mv, player)))
+            .collect(),
+    )
+}
+
+/// 局面木の各局面で段階的な手生成が合法手の絞り込みと一致することを確かめる
+fn compare(state: &GameState, player: Player, depth: u32) {
+    let mut pos = SearchState::new(*state, player);
+    common::visit(&mut pos, depth, Follow::Safe, &mut |pos| {
+        check_staged(&pos.state, pos.player)
+    });
+}
+
+fn check_staged(state: &GameState, player: Player) {
+    let opponent = rules::opponent_of(player);
+
+    assert_eq!(
+        sorted(rules::generate_captures(state, player)),
+        filtered(state, player, |mv, _| match mv {
+            Move::To(_, to, promote) => promote || state.board[to.y][to.x].is_some(),
+            Move::Drop(..) => false,
+        }),
+        "captures: {}",
+        sfen::format(state, player)
+    );
+    assert_eq!(
+        sorted(rules::generate_checks(state, player)),
+        filtered(state, player, |_, next| rules::is_in_check(next, opponent)),
+        "checks: {}",
+        sfen::format(state, player)
+    );
+    let evasions = if rules::is_in_check(state, player) {
+        filtered(state, player, |_, next| !rules::is_in_check(next, player))
+    } else {
+        Vec::new()
+    };
+    assert_eq!(
+        sorted(rules::generate_evasions(state, player)),
+        evasions,
+        "evasions: {}",
+        sfen::format(state, player)
+    );
+}
+
+#[test]
+fn staged_generation_from_startpos() {
+    compare(&board::init(), Player::Sente, 3);
+}
+
+#[test]
+fn staged_generation_in_mating_positions() {
+    for problem in &tsume::PROBLEMS {
+        let (state, player) = sfen::parse(problem.sfen).unwrap();
+        compare(&state, player, 2);
+    }
+}
+
+#[test]
+fn double_check_allows_only_king_moves() {
+    // 5c の飛車と 4b の金による両王手
+    let (state, player) = sfen::parse("k4/1G3/R4/5/4K w - 1").unwrap();
+    assert!(rules::is_in_check(&state, player));
+    let evasions = rules::generate_evasions(&state, player);
+    assert!(!evasions.is_empty());
+    assert!(
+        evasions
+            .iter()
+            .all(|mv| matches!(mv, Move::To(from, _, _) if from.x == 0 && from.y == 0))
+    );
+}
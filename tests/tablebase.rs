@@ -0,0 +1,253 @@
+use minishogi_rs::board::{GameState, Hand, Piece, PieceType, Player};
+use minishogi_rs::mate;
+use minishogi_rs::rules;
+use minishogi_rs::tablebase::{Outcome, Tablebase};
+use std::{env, fs, process};
+
+/// 両玉と指定した駒のすべての配置（駒は盤上か持ち主の持ち駒）
+fn placements(pieces: &[(PieceType, Player)]) -> Vec<GameState> {
+    let mut states = Vec::new();
+    for sente_king in 0..25 {
+        for gote_king in 0..25 {
+            if sente_king == gote_king {
+                continue;
+            }
+            let mut state = GameState {
+                board: [[None; 5]; 5],
+                sente_hand: Hand::new(),
+                gote_hand: Hand::new(),
+            };
+            state.board[sente_king / 5][sente_king % 5] = piece(PieceType::King, Player::Sente);
+            state.board[gote_king / 5][gote_king % 5] = piece(PieceType::King, Player::Gote);
+            place(state, pieces, &mut states);
+        }
+    }
+    states
+}
+
+fn place(state: GameState, pieces: &[(PieceType, Player)], states: &mut Vec<GameState>) {
+    let Some((&(piece_type, owner), rest)) = pieces.split_first() else {
+        states.push(state);
+        return;
+    };
+    let mut in_hand = state;
+    in_hand.get_hand_mut(owner).add(piece_type);
+    place(in_hand, rest, states);
+    for sq in 0..25 {
+        if state.board[sq / 5][sq % 5].is_none() {
+            let mut on_board = state;
+            on_board.board[sq / 5][sq % 5] = piece(piece_type, owner);
+            place(on_board, rest, states);
+        }
+    }
+}
+
+fn piece(piece_type: PieceType, owner: Player) -> Option<Piece> {
+    Some(Piece {
+        piece_type,
+        owner,
+        promoted: false,
+    })
+}
+
+/// 両玉と先手の金1枚のすべての配置（相手の玉を取れる局面は手番側の 1 手勝ちなので除く）
+fn king_gold_king(player: Player) -> Vec<GameState> {
+    placements(&[(PieceType::Gold, Player::Sente)])
+        .into_iter()
+        .filter(|state| !rules::is_in_check(state, rules::opponent_of(player)))
+        .collect()
+}
+
+/// 大きいほど手番側にとって良い順序
+fn rank(outcome: Outcome) -> i32 {
+    match outcome {
+        Outcome::Win(d) => 1000 - i32::from(d),
+        Outcome::Draw => 0,
+        Outcome::Loss(d) => -1000 + i32::from(d),
+    }
+}
+
+/// 各局面の結果が、1手進めた局面の結果のうち手番側に最も良いものと一致する
+fn assert_consistent(tb: &Tablebase, states: &[GameState]) {
+    for state in states {
+        for player in [Player::Sente, Player::Gote] {
+            let outcome = tb.probe(state, player).unwrap();
+            let best = rules::generate_legal_moves(state, player)
+                .into_iter()
+                .map(|mv| {
+                    if rules::captures_king(state, mv) {
+                        return Outcome::Win(1);
+                    }
+                    let next = rules::make_move(state, mv, player);
+                    match tb.probe(&next, rules::opponent_of(player)).unwrap() {
+                        Outcome::Win(d) => Outcome::Loss(d + 1),
+                        Outcome::Loss(d) => Outcome::Win(d + 1),
+                        Outcome::Draw => Outcome::Draw,
+                    }
+                })
+                .max_by_key(|&outcome| rank(outcome))
+                .unwrap_or(Outcome::Loss(0));
+            assert_eq!(outcome, best, "{:?} {:?}", state, player);
+        }
+    }
+}
+
+/// 左右を反転する
+fn mirror(state: &GameState) -> GameState {
+    let mut mirrored = *state;
+    for row in mirrored.board.iter_mut() {
+        row.reverse();
+    }
+    mirrored
+}
+
+/// 盤を180度回して先後を入れ替える
+fn flip(state: &GameState) -> GameState {
+    let mut flipped = GameState {
+        board: [[None; 5]; 5],
+        sente_hand: state.gote_hand,
+        gote_hand: state.sente_hand,
+    };
+    for y in 0..5 {
+        for x in 0..5 {
+            flipped.board[4 - y][4 - x] = state.board[y][x].map(|p| Piece {
+                owner: rules::opponent_of(p.owner),
+                ..p
+            });
+        }
+    }
+    flipped
+}
+
+/// 玉方のどの手にも王を取り返される（詰みか、動ける所がすべて利きの中）
+fn every_reply_loses(state: &GameState, player: Player) -> bool {
+    rules::generate_legal_moves(state, player)
+        .into_iter()
+        .all(|mv| rules::is_in_check(&rules::make_move(state, mv, player), player))
+}
+
+#[test]
+fn one_piece_table_agrees_with_the_mate_solver() {
+    let tb = Tablebase::generate(&[PieceType::Gold]).unwrap();
+
+    // 玉方が指してから王を取られるので 2 手負け。詰みの局面は必ずそうなる
+    let mut mated = 0;
+    for state in king_gold_king(Player::Gote) {
+        let outcome = tb.probe(&state, Player::Gote).unwrap();
+        assert_eq!(
+            outcome == Outcome::Loss(2),
+            every_reply_loses(&state, Player::Gote)
+        );
+        if rules::is_checkmate(&state, Player::Gote) {
+            assert_eq!(outcome, Outcome::Loss(2));
+            mated += 1;
+        }
+    }
+    assert!(mated > 0);
+
+    // n 手詰は詰ませてから王を取るまでの 2 手を足して n + 2 手勝ち。
+    // 王手でなくても玉の逃げ場をなくせば 3 手で勝てるので、5 手勝ちより短くなることがある
+    for state in king_gold_king(Player::Sente) {
+        let outcome = tb.probe(&state, Player::Sente).unwrap();
+        let three = rules::generate_legal_moves(&state, Player::Sente)
+            .into_iter()
+            .any(|mv| {
+                let next = rules::make_move(&state, mv, Player::Sente);
+                !rules::is_in_check(&next, Player::Sente) && every_reply_loses(&next, Player::Gote)
+            });
+        assert_eq!(outcome == Outcome::Win(3), three);
+        match mate::mate_length(&state, Player::Sente, 3) {
+            Some(1) => assert_eq!(outcome, Outcome::Win(3)),
+            Some(3) => assert!(
+                matches!(outcome, Outcome::Win(3) | Outcome::Win(5)),
+                "{:?}",
+                outcome
+            ),
+            _ => assert!(!matches!(outcome, Outcome::Win(1)), "{:?}", outcome),
+        }
+    }
+
+    // 相手の玉を取れる局面は 1 手勝ち
+    let mut state = GameState {
+        board: [[None; 5]; 5],
+        sente_hand: Hand::new(),
+        gote_hand: Hand::new(),
+    };
+    state.sente_hand.add(PieceType::Gold);
+    state.board[0][0] = Some(Piece {
+        piece_type: PieceType::King,
+        owner: Player::Gote,
+        promoted: false,
+    });
+    state.board[1][1] = Some(Piece {
+        piece_type: PieceType::King,
+        owner: Player::Sente,
+        promoted: false,
+    });
+    assert_eq!(tb.probe(&state, Player::Sente), Some(Outcome::Win(1)));
+}
+
+#[test]
+fn saved_table_loads_back_identically() {
+    let tb = Tablebase::generate(&[PieceType::Gold]).unwrap();
+    let path = env::temp_dir().join(format!("minishogi_tb_{}.tb", process::id()));
+    tb.save(&path).unwrap();
+    let loaded = Tablebase::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.material(), tb.material());
+    assert_eq!(loaded.len(), tb.len());
+    for player in [Player::Sente, Player::Gote] {
+        for state in king_gold_king(player) {
+            assert_eq!(loaded.probe(&state, player), tb.probe(&state, player));
+        }
+    }
+}
+
+#[test]
+fn symmetric_positions_share_one_entry() {
+    let tb = Tablebase::generate(&[PieceType::Gold]).unwrap();
+    // 先手玉は左の3筋、後手玉は残りの24マス、金は玉のいない23マスか持ち駒
+    assert_eq!(tb.len(), 15 * 24 * (2 * 23 + 2));
+
+    let states = placements(&[(PieceType::Gold, Player::Sente)]);
+    for state in &states {
+        for player in [Player::Sente, Player::Gote] {
+            let outcome = tb.probe(state, player);
+            assert_eq!(tb.probe(&mirror(state), player), outcome);
+            assert_eq!(tb.probe(&flip(state), rules::opponent_of(player)), outcome);
+        }
+    }
+    assert_consistent(&tb, &states);
+}
+
+#[test]
+#[ignore = "release ビルドで数秒かかる"]
+fn same_type_pieces_are_counted_once() {
+    let tb = Tablebase::generate(&[PieceType::Gold, PieceType::Gold]).unwrap();
+    // 同じ金2枚の所在は 48 通りからの重複組み合わせ
+    assert_eq!(tb.len(), 15 * 24 * (48 * 49 / 2));
+    assert_consistent(
+        &tb,
+        &placements(&[
+            (PieceType::Gold, Player::Sente),
+            (PieceType::Gold, Player::Gote),
+        ]),
+    );
+}
+
+#[test]
+fn oversized_material_is_rejected() {
+    let err = Tablebase::generate(&[
+        PieceType::Gold,
+        PieceType::Silver,
+        PieceType::Bishop,
+        PieceType::Pawn,
+    ])
+    .err()
+    .unwrap();
+    assert!(err.contains("3枚まで"), "{}", err);
+    let err = Tablebase::generate(&[PieceType::Pawn; 3]).err().unwrap();
+    assert!(err.contains("2枚まで"), "{}", err);
+    assert!(Tablebase::generate(&[PieceType::King]).is_err());
+}
@@ -0,0 +1,321 @@
+use minishogi_rs::board::Player;
+use minishogi_rs::engine::EngineSpec;
+use minishogi_rs::search::SearchResult;
+use minishogi_rs::sfen;
+use minishogi_rs::tournament::{self, GameResult, MatchConfig, Score, Sprt, SprtDecision};
+use minishogi_rs::usi;
+use std::io::Cursor;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn engine_specs_parse_and_round_trip_names() {
+    for (text, spec) in [
+        ("ab", EngineSpec::AlphaBeta { depth: None }),
+        ("ab:3", EngineSpec::AlphaBeta { depth: Some(3) }),
+        (
+            "mcts:500",
+            EngineSpec::Mcts {
+                iterations: Some(500),
+            },
+        ),
+        (
+            "usi:./engine --fast",
+            EngineSpec::Usi {
+                program: "./engine".to_string(),
+                args: vec!["--fast".to_string()],
+            },
+        ),
+    ] {
+        assert_eq!(EngineSpec::parse(text), Ok(spec.clone()));
+        assert_eq!(EngineSpec::parse(&spec.name()), Ok(spec));
+    }
+    assert!(EngineSpec::parse("ab:x").is_err());
+    assert!(EngineSpec::parse("mcts:0").is_err());
+    assert!(EngineSpec::parse("usi:").is_err());
+    assert!(EngineSpec::parse("random").is_err());
+}
+
+#[test]
+fn elo_and_error_follow_the_score() {
+    let even = Score {
+        wins: 10,
+        draws: 10,
+        losses: 10,
+    };
+    assert_eq!(even.ratio(), 0.5);
+    assert!(even.elo().abs() < 1e-9);
+
+    // 勝率 75% は約 +191
+    let strong = Score {
+        wins: 30,
+        draws: 0,
+        losses: 10,
+    };
+    assert!((strong.elo() - 190.85).abs() < 0.1, "{}", strong.elo());
+    let weak = Score {
+        wins: 10,
+        draws: 0,
+        losses: 30,
+    };
+    assert!((weak.elo() + strong.elo()).abs() < 1e-9);
+
+    // 局数が増えるほど誤差は小さくなる
+    let more = Score {
+        wins: 300,
+        draws: 0,
+        losses: 100,
+    };
+    assert!(more.elo_error() < strong.elo_error() / 2.0);
+    assert!(
+        Score {
+            wins: 3,
+            draws: 0,
+            losses: 0
+        }
+        .elo_error()
+        .is_infinite()
+    );
+}
+
+#[test]
+fn sprt_decides_once_the_evidence_is_strong() {
+    let sprt = Sprt::new(0.0, 50.0);
+    let (lower, upper) = sprt.bounds();
+    assert!((upper - 2.944).abs() < 1e-3);
+    assert!((lower + 2.944).abs() < 1e-3);
+
+    let few = Score {
+        wins: 3,
+        draws: 2,
+        losses: 2,
+    };
+    assert_eq!(sprt.decision(&few), None);
+
+    let winning = Score {
+        wins: 300,
+        draws: 100,
+        losses: 150,
+    };
+    assert!(sprt.llr(&winning) > upper);
+    assert_eq!(sprt.decision(&winning), Some(SprtDecision::H1));
+
+    let even = Score {
+        wins: 200,
+        draws: 100,
+        losses: 200,
+    };
+    assert_eq!(sprt.decision(&even), Some(SprtDecision::H0));
+}
+
+#[test]
+fn openings_are_parsed_with_line_numbers() {
+    let openings = tournament::parse_openings("# 開局集\n1d1c\n\n1d1c 5b5c\n").unwrap();
+    assert_eq!(openings.len(), 2);
+    assert_eq!(openings[1].len(), 2);
+
+    let err = tournament::parse_openings("1d1c\n9z9z\n").unwrap_err();
+    assert!(err.starts_with("2行目: "), "{}", err);
+}
+
+#[test]
+fn match_alternates_colours_and_counts_every_game() {
+    let a = EngineSpec::AlphaBeta { depth: Some(1) };
+    let b = EngineSpec::AlphaBeta { depth: Some(2) };
+    let config = MatchConfig {
+        games: 4,
+        concurrency: 2,
+        max_plies: 30,
+        openings: tournament::parse_openings("1d1c\n").unwrap(),
+        ..MatchConfig::default()
+    };
+    let report = tournament::run_match(&a, &b, &config, |_, _| {}).unwrap();
+
+    assert_eq!(report.score.games(), 4);
+    assert_eq!(report.games.len(), 4);
+    let mut indices: Vec<usize> = report.games.iter().map(|g| g.index).collect();
+    indices.sort();
+    assert_eq!(indices, [0, 1, 2, 3]);
+    for game in &report.games {
+        assert_eq!(game.a_is_sente, game.index % 2 == 0);
+        assert_eq!(sfen::format_move(game.moves[0]), "1d1c");
+        assert!(game.moves.len() <= 30);
+    }
+    let wins = report
+        .games
+        .iter()
+        .filter(|g| g.result == GameResult::Win)
+        .count();
+    assert_eq!(wins, report.score.wins as usize);
+}
+
+#[test]
+fn usi_engine_answers_commands() {
+    let input = "usi\nisready\nposition sfen k4/1G3/5/5/4K b - 1\ngo byoyomi 100\n\
+                 position startpos moves 9z9z\nquit\ngo\n";
+    let mut output = Vec::new();
+    usi::run_engine(Cursor::new(input), &mut output, |state, player, options| {
+        // 秒読みから送る間の余裕を引いた時間で考え、stop でも止められる
+        assert_eq!(options.time_limit, Some(Duration::from_millis(50)));
+        assert!(options.stop.is_some());
+        EngineSpec::AlphaBeta { depth: Some(2) }
+            .search(state, player, options)
+            .unwrap()
+    })
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with("id name "));
+    assert_eq!(&lines[1..3], ["usiok", "readyok"]);
+    assert!(lines[3].starts_with("info score cp "));
+    assert!(lines[4].starts_with("bestmove 4b5a"), "{}", lines[4]);
+    assert!(lines[5].starts_with("info string "));
+    // quit の後は読まない
+    assert_eq!(lines.len(), 6);
+
+    let mut output = Vec::new();
+    usi::run_engine(Cursor::new("go\n"), &mut output, |_, _, options| {
+        // 持ち時間の指定がなければ探索の既定どおりに考える
+        assert!(options.time_limit.is_none() && options.stop.is_none());
+        SearchResult {
+            best_move: None,
+            score: None,
+            visits: Vec::new(),
+        }
+    })
+    .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "bestmove resign\n");
+}
+
+#[test]
+fn usi_engine_thinks_until_stop_or_the_clock() {
+    // go infinite は stop を受けるまで考える
+    let input = "position sfen k4/1G3/5/5/4K b - 1\ngo infinite\nstop\n\
+                 go btime 60000 wtime 0 byoyomi 1000\ngo movetime 300\nquit\n";
+    let mut output = Vec::new();
+    let mut limits = Vec::new();
+    usi::run_engine(Cursor::new(input), &mut output, |state, player, options| {
+        limits.push(options.time_limit);
+        if options.time_limit.is_none() {
+            let stop = options.stop.unwrap();
+            let started = Instant::now();
+            while !stop.load(Ordering::Relaxed) {
+                assert!(
+                    started.elapsed() < Duration::from_secs(10),
+                    "stop が届かない"
+                );
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        EngineSpec::AlphaBeta { depth: Some(1) }
+            .search(state, player, options)
+            .unwrap()
+    })
+    .unwrap();
+
+    // 残り時間の 1/20 と秒読みの合計、movetime から余裕を引いた時間
+    let ms = |ms| Some(Duration::from_millis(ms));
+    assert_eq!(limits, [None, ms(3_950), ms(250)]);
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.matches("bestmove 4b5a").count(), 3, "{}", output);
+}
+
+#[test]
+fn usi_position_rejects_illegal_moves() {
+    let (_, player) = usi::parse_position("startpos moves 1d1c").unwrap();
+    assert_eq!(player, Player::Gote);
+    assert!(usi::parse_position("startpos moves 1e1a").is_err());
+    assert!(usi::parse_position("nowhere").is_err());
+}
+
+#[test]
+fn external_usi_engine_plays_a_match() {
+    let program = env!("CARGO_BIN_EXE_minishogi-rs").to_string();
+    let external = EngineSpec::Usi {
+        program,
+        args: vec!["usi".to_string(), "ab:1".to_string()],
+    };
+    let builtin = EngineSpec::AlphaBeta { depth: Some(1) };
+    let config = MatchConfig {
+        games: 2,
+        max_plies: 20,
+        movetime_ms: 10,
+        ..MatchConfig::default()
+    };
+    let report = tournament::run_match(&external, &builtin, &config, |_, _| {}).unwrap();
+    assert_eq!(report.score.games(), 2);
+    // 同じ探索どうしなので、先後を入れ替えた2局の結果は対称になる
+    let [first, second] = &report.games[..] else {
+        panic!("{:?}", report.games);
+    };
+    assert_eq!(first.moves, second.moves);
+}
+
+/// `quit` を無視し、`go` には入玉宣言で答える USI エンジン
+#[cfg(unix)]
+const STUBBORN_ENGINE: &str = r#"
+while read command rest; do
+    case "$command" in
+        usi) echo "id name stubborn"; echo usiok ;;
+        isready) echo readyok ;;
+        go) echo "bestmove win" ;;
+    esac
+done
+"#;
+
+/// `go` に答えない USI エンジン
+#[cfg(unix)]
+const SILENT_ENGINE: &str = r#"
+while read command rest; do
+    case "$command" in
+        usi) echo usiok ;;
+        isready) echo readyok ;;
+    esac
+done
+"#;
+
+#[cfg(unix)]
+#[test]
+fn engines_that_do_not_move_in_time_lose() {
+    let silent = EngineSpec::Usi {
+        program: "sh".to_string(),
+        args: vec!["-c".to_string(), SILENT_ENGINE.to_string()],
+    };
+    let builtin = EngineSpec::AlphaBeta { depth: None };
+    let config = MatchConfig {
+        games: 2,
+        movetime_ms: 100,
+        ..MatchConfig::default()
+    };
+    let started = Instant::now();
+    let report = tournament::run_match(&builtin, &silent, &config, |_, _| {}).unwrap();
+    assert_eq!(report.score.wins, 2);
+    // 内蔵エンジンも思考時間で打ち切るので、1局は数手で終わる
+    assert!(
+        started.elapsed() < Duration::from_secs(10),
+        "{:?}",
+        started.elapsed()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn usi_win_declaration_is_rejected_and_stuck_engines_are_killed() {
+    let args = ["-c".to_string(), STUBBORN_ENGINE.to_string()];
+    let mut engine = usi::UsiEngine::spawn("sh", &args).unwrap();
+    assert_eq!(engine.name(), "stubborn");
+    let (state, player) = sfen::parse(sfen::STARTPOS).unwrap();
+    let err = engine.go(&state, player, 10).unwrap_err();
+    assert!(err.to_string().contains("bestmove win"), "{}", err);
+
+    // quit に応じなくても、待ち時間が過ぎれば止めて戻る
+    let started = Instant::now();
+    drop(engine);
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "{:?}",
+        started.elapsed()
+    );
+}
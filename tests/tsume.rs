@@ -0,0 +1,112 @@
+use minishogi_rs::board::{PieceType, Player};
+use minishogi_rs::mate;
+use minishogi_rs::rules;
+use minishogi_rs::sfen;
+use minishogi_rs::tsume::{self, Session, Verdict};
+
+#[test]
+fn bundled_problems_mate_at_their_stated_length() {
+    for problem in tsume::PROBLEMS {
+        let (state, attacker) = sfen::parse(problem.sfen).unwrap();
+        // 表示の手数より短い詰みはなく、ちょうどその手数で詰む
+        assert_eq!(
+            mate::mate_length(&state, attacker, problem.plies),
+            Some(problem.plies),
+            "{}",
+            problem.name
+        );
+        assert_eq!(
+            mate::mate_length(&state, attacker, problem.plies - 1),
+            None,
+            "{}",
+            problem.name
+        );
+
+        // 解答手順を指し進めると詰み上がる
+        let line = mate::solve(&state, attacker, problem.plies).unwrap();
+        assert_eq!(line.len() as u32, problem.plies, "{}", problem.name);
+        let mut current = state;
+        let mut player = attacker;
+        for &mv in &line {
+            assert!(rules::generate_legal_moves(&current, player).contains(&mv));
+            current = rules::make_move(&current, mv, player);
+            player = rules::opponent_of(player);
+        }
+        assert!(
+            rules::is_checkmate(&current, rules::opponent_of(attacker)),
+            "{}",
+            problem.name
+        );
+    }
+}
+
+#[test]
+fn session_judges_each_attacking_move() {
+    let problem = tsume::PROBLEMS[0];
+    let mut session = Session::from_sfen(problem.sfen, problem.plies).unwrap();
+    // 王手でない手と、王手でも詰まない手は受け付けず、局面も進めない
+    assert_eq!(
+        session.play(sfen::parse_move("5e4e").unwrap()),
+        Verdict::NotCheck
+    );
+    assert_eq!(
+        session.play(sfen::parse_move("G*2a").unwrap()),
+        Verdict::NoMate
+    );
+    assert_eq!(session.remaining, 1);
+    assert_eq!(
+        session.play(sfen::parse_move("G*1b").unwrap()),
+        Verdict::Solved { alternative: false }
+    );
+
+    // 作意どおりに指せば玉方は作意の応手で応じる
+    let problem = tsume::PROBLEMS[1];
+    let mut session = Session::from_sfen(problem.sfen, problem.plies).unwrap();
+    let solution = session.solution.clone();
+    assert_eq!(
+        session.play(solution[0]),
+        Verdict::Continue {
+            reply: solution[1],
+            alternative: false
+        }
+    );
+    assert_eq!(
+        session.play(solution[2]),
+        Verdict::Solved { alternative: false }
+    );
+
+    // 詰みのない局面は問題にできない
+    assert!(Session::from_sfen(sfen::STARTPOS, 3).is_err());
+}
+
+#[test]
+fn sfen_round_trips_hands_and_side_to_move() {
+    for position in [
+        sfen::STARTPOS,
+        "5/k4/5/1P3/K4 b RBS 1",
+        "5/5/2g1g/5/K3k w - 1",
+        "k4/5/4+R/3+r1/4K w 2Pbg 1",
+        "1+p3/k4/5/2+S1+B/4K b GSrp 1",
+    ] {
+        let (state, player) = sfen::parse(position).unwrap();
+        assert_eq!(sfen::format(&state, player), position);
+    }
+
+    let (state, player) = sfen::parse("k4/5/5/5/4K w 2Pbg").unwrap();
+    assert_eq!(player, Player::Gote);
+    assert_eq!(state.sente_hand.get(PieceType::Pawn), 2);
+    assert_eq!(state.gote_hand.get(PieceType::Bishop), 1);
+    assert_eq!(state.gote_hand.get(PieceType::Gold), 1);
+    assert_eq!(sfen::format(&state, player), "k4/5/5/5/4K w 2Pbg 1");
+
+    for bad in [
+        "k4/5/5/5/4K",
+        "k4/5/5/5/4K x - 1",
+        "k4/5/5/5 b - 1",
+        "k4/5/5/6/4K b - 1",
+        "k4/5/5/5/4K b K 1",
+        "k4/5/5/5/+G3K b - 1",
+    ] {
+        assert!(sfen::parse(bad).is_err(), "{}", bad);
+    }
+}
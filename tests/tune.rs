@@ -0,0 +1,118 @@
+use minishogi_rs::board::{self, PieceType, Player};
+use minishogi_rs::eval::{Evaluator, Positional, Weights};
+use minishogi_rs::rules::{self, Move};
+use minishogi_rs::search::Rng;
+use minishogi_rs::{sfen, tune};
+
+/// ランダムな指し手の対局を棋譜形式で作る（玉を取った側の勝ち、100手で引き分け）
+fn random_records(games: usize, seed: u64) -> String {
+    let mut rng = Rng::with_seed(seed);
+    let mut text = String::new();
+
+    for _ in 0..games {
+        let mut state = board::init();
+        let mut player = Player::Sente;
+        let mut moves: Vec<Move> = Vec::new();
+        let mut result = "1/2-1/2";
+
+        while moves.len() < 100 {
+            let legal = rules::generate_legal_moves(&state, player);
+            if legal.is_empty() {
+                break;
+            }
+            // 玉を取れるなら取る
+            let capture_king = legal.iter().copied().find(|&mv| match mv {
+                Move::To(_, to, _) => {
+                    state.board[to.y][to.x].is_some_and(|p| p.piece_type == PieceType::King)
+                }
+                Move::Drop(..) => false,
+            });
+            let mv = capture_king.unwrap_or_else(|| legal[rng.range(legal.len())]);
+            moves.push(mv);
+            state = rules::make_move(&state, mv, player);
+            if capture_king.is_some() {
+                result = match player {
+                    Player::Sente => "1-0",
+                    Player::Gote => "0-1",
+                };
+                break;
+            }
+            player = rules::opponent_of(player);
+        }
+
+        let line: Vec<String> = moves.into_iter().map(sfen::format_move).collect();
+        text.push_str(&format!("{} {}\n", line.join(" "), result));
+    }
+
+    text
+}
+
+#[test]
+fn features_reproduce_evaluation() {
+    let mut rng = Rng::with_seed(7);
+    let mut weights = Weights::default().to_params();
+    for w in &mut weights {
+        *w += rng.range(21) as i32 - 10;
+    }
+    let evaluator = Positional::new(Weights::from_params(&weights));
+
+    let text = random_records(5, 3);
+    for line in text.lines() {
+        let mut state = board::init();
+        let mut player = Player::Sente;
+        for token in line.split_whitespace() {
+            let Ok(mv) = sfen::parse_move(token) else {
+                break;
+            };
+            let dot: i32 = Positional::features(&state, player)
+                .iter()
+                .map(|&(i, c)| weights[i] * c)
+                .sum();
+            assert_eq!(dot, evaluator.evaluate(&state, player));
+            state = rules::make_move(&state, mv, player);
+            player = rules::opponent_of(player);
+        }
+    }
+}
+
+#[test]
+fn weights_round_trip_through_text() {
+    let mut params = Weights::default().to_params();
+    for (i, p) in params.iter_mut().enumerate() {
+        *p += i as i32 % 7 - 3;
+    }
+    let weights = Weights::from_params(&params);
+    assert_eq!(Weights::parse(&weights.to_text()).unwrap(), weights);
+
+    // 書かれていない項目は標準値のまま
+    let partial = Weights::parse("# 手番だけ変える\ntempo 0\n").unwrap();
+    assert_eq!(partial.tempo, 0);
+    assert_eq!(partial.piece, Weights::default().piece);
+
+    let err = Weights::parse("piece 1 2 3\n").unwrap_err();
+    assert!(err.starts_with("1行目"), "{}", err);
+    assert!(Weights::parse("pst X 0\n").is_err());
+    assert!(Weights::parse("unknown 1\n").is_err());
+}
+
+#[test]
+fn tuning_reduces_loss() {
+    let samples = tune::samples_from_records(&random_records(40, 1)).unwrap();
+    assert!(!samples.is_empty());
+
+    let initial = tune::loss(&samples, &Weights::default());
+    let mut tuner = tune::Tuner::new(&Weights::default());
+    for _ in 0..50 {
+        tuner.step(&samples);
+    }
+    let tuned = tune::loss(&samples, &tuner.weights());
+    assert!(tuned < initial, "{} -> {}", initial, tuned);
+}
+
+#[test]
+fn records_without_results_are_skipped() {
+    let samples = tune::samples_from_records("# 注釈\n1d1c 5b5c\n1d1c 1-0\n").unwrap();
+    assert_eq!(samples.len(), 2);
+    assert!(samples.iter().all(|s| s.result() == 1.0));
+    assert!(tune::samples_from_records("1e1a 1-0\n").is_err());
+}